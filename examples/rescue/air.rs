@@ -0,0 +1,129 @@
+//! AIR for proving knowledge of a Rescue-Prime preimage. Shared by the rescue
+//! example and tests.
+//!
+//! Proofs aren't zero-knowledge: the trace is a single permutation (8 rows) so
+//! the queried trace values determine the whole trace, preimage included. The
+//! example shows how to use [`RescuePermutation`] rather than how to keep a
+//! preimage private.
+//!
+//! The hash is [`ministark::hash::RescueHashFn`] i.e. Rescue-Prime over the
+//! Goldilocks field with a state width of 12, a capacity of 4 and 7 rounds. The
+//! earlier work-in-progress version of this example used its own hasher over
+//! the 252-bit STARK field with a state width of 4, a capacity of 2 and 14
+//! rounds so its digests differ from these.
+
+use alloc::vec::Vec;
+use ark_ff::Zero;
+use ministark::air;
+use ministark::air::AirConfig;
+use ministark::constraints::AlgebraicItem;
+use ministark::constraints::Constraint;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::gadgets::rescue::permutation_rows;
+use ministark::gadgets::RescuePermutation;
+use ministark::hash::rescue::RescueDigest;
+use ministark::hash::rescue::CAPACITY;
+use ministark::hash::rescue::DIGEST_RANGE;
+use ministark::hash::rescue::DIGEST_SIZE;
+use ministark::hash::rescue::STATE_WIDTH;
+use ministark::hash::Sha256HashFn;
+use ministark::hints::Hints;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::random::PublicCoinImpl;
+use ministark::stark::Stark;
+use ministark::utils::FieldVariant;
+use ministark::utils::SerdeOutput;
+use ministark::Matrix;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use sha2::Sha256;
+
+/// Number of field elements in the preimage
+pub const PREIMAGE_LEN: usize = DIGEST_SIZE;
+
+/// Hashing at most [`ministark::hash::rescue::RATE`] elements is a single
+/// permutation
+const RESCUE: RescuePermutation = RescuePermutation::new(0);
+
+pub struct RescueTrace(Matrix<Fp>);
+
+impl Trace for RescueTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+}
+
+pub struct RescueAirConfig;
+
+impl AirConfig for RescueAirConfig {
+    const AIR_ID: &'static str = "rescue-preimage";
+    const NUM_BASE_COLUMNS: usize = STATE_WIDTH;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = RescueDigest;
+
+    fn gen_hints(
+        _trace_len: usize,
+        digest: &RescueDigest,
+        _: &ministark::challenges::Challenges<Self::Fq>,
+    ) -> Result<Hints<Self::Fq>, air::Error> {
+        Ok(Hints::new(digest.0.into_iter().enumerate().collect()))
+    }
+
+    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
+        use AlgebraicItem::*;
+        let input_row = RescuePermutation::input_row(0);
+        let output_row = RescuePermutation::output_row(0);
+        let preimage_len = Constant(FieldVariant::Fp(Fp::from(PREIMAGE_LEN as u64)));
+
+        // the input is initialised as in `RescueHashFn::hash_elements`
+        let domain = RESCUE.column(0).curr() - preimage_len;
+        let capacity = (1..CAPACITY).map(|i| RESCUE.column(i).curr());
+        let padding = (CAPACITY + PREIMAGE_LEN..STATE_WIDTH).map(|i| RESCUE.column(i).curr());
+        let input_constraints = [domain]
+            .into_iter()
+            .chain(capacity)
+            .chain(padding)
+            .map(|constraint| Constraint::boundary(trace_len, input_row, constraint));
+
+        let output_constraints = DIGEST_RANGE.enumerate().map(|(i, column)| {
+            let constraint = RESCUE.column(column).curr() - Hint(i);
+            Constraint::boundary(trace_len, output_row, constraint)
+        });
+
+        RESCUE
+            .constraints(trace_len)
+            .into_iter()
+            .chain(input_constraints)
+            .chain(output_constraints)
+            .collect()
+    }
+}
+
+/// Claim of knowing a preimage of a Rescue-Prime digest
+pub struct PreimageClaim(pub RescueDigest);
+
+impl Stark for PreimageClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = RescueAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = [Fp; PREIMAGE_LEN];
+    type Trace = RescueTrace;
+
+    fn get_public_inputs(&self) -> RescueDigest {
+        self.0
+    }
+
+    fn generate_trace(&self, preimage: [Fp; PREIMAGE_LEN]) -> RescueTrace {
+        let mut input = [Fp::zero(); STATE_WIDTH];
+        input[0] = Fp::from(PREIMAGE_LEN as u64);
+        input[CAPACITY..][..PREIMAGE_LEN].copy_from_slice(&preimage);
+        RescueTrace(Matrix::from_arrays(&permutation_rows(input)))
+    }
+}
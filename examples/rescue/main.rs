@@ -1,47 +1,33 @@
-#![feature(int_roundings)]
+//! Proves knowledge of a preimage of a Rescue-Prime digest. The proof reveals
+//! the preimage (see `air.rs`).
 
-use ark_serialize::CanonicalDeserialize;
-use ark_serialize::CanonicalSerialize;
-use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
-use ark_ff::One;
-use ministark::air::AirConfig;
-use ministark::constraints::Constraint;
-use ministark::utils::FieldVariant;
-use crate::rescue::Rescue;
+extern crate alloc;
 
-mod rescue;
+mod air;
 
-#[derive(Clone, Copy, CanonicalSerialize, CanonicalDeserialize)]
-struct RescueInfo {
-    input: [Fp; 2],
-    output: [Fp; 2],
-}
-
-struct RescueAirConfig;
-
-impl AirConfig for RescueAirConfig {
-    const NUM_BASE_COLUMNS: usize = 0;
-    type Fp = Fp;
-    type Fq = Fp;
-    type PublicInputs = ();
+use air::PreimageClaim;
+use ministark::hash::ElementHashFn;
+use ministark::hash::RescueHashFn;
+use ministark::stark::Stark;
+use ministark::ProofOptions;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use std::time::Instant;
 
-    fn constraints(_trace_len: usize) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
-        todo!()
-    }
-}
+const SECURITY_LEVEL: u32 = 30;
+const OPTIONS: ProofOptions = ProofOptions::new(32, 16, 8, 2, 8);
 
 fn main() {
-    let state_width = 4; /* =m */
-    // TODO: this may not be accurate. Generate with Algorithm 7
-    let rounds = 14; /* =N */
-    let security_level = 256;
-    let capacity = 2;
-    let digest_size = 2;
-
-    let mut hasher = Rescue::new(state_width, capacity, rounds, security_level, digest_size);
-    hasher.update(Fp::one());
-    hasher.update(Fp::one());
-    let _output = hasher.finish();
-
-    todo!("Rescue example is a WIP");
+    let preimage = [1u8, 2, 3, 4].map(Fp::from);
+    let digest = RescueHashFn::hash_elements(preimage);
+    let claim = PreimageClaim(digest);
+
+    let now = Instant::now();
+    let proof = pollster::block_on(claim.prove(OPTIONS, preimage)).expect("prover failed");
+    println!("Proof generated in: {:?}", now.elapsed());
+
+    let now = Instant::now();
+    claim
+        .verify(proof, SECURITY_LEVEL)
+        .expect("verification failed");
+    println!("Proof verified in: {:?}", now.elapsed());
 }
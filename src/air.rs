@@ -6,24 +6,42 @@ use crate::constraints::Constraint;
 use crate::expression::Expr;
 use crate::hints::Hints;
 use crate::utils::FieldVariant;
+#[cfg(feature = "prover")]
 use crate::utils::GpuVec;
 use crate::Matrix;
 use crate::ProofOptions;
 use crate::StarkExtensionOf;
+use alloc::collections::BTreeMap;
 use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use ark_ff::FftField;
 use ark_poly::EvaluationDomain;
 use ark_poly::Radix2EvaluationDomain;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use core::any::Any;
+use core::any::TypeId;
+use core::ops::Range;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
 use ministark_gpu::GpuFftField;
 use num_traits::Pow;
-use std::time::Instant;
+use snafu::Snafu;
 
 pub trait AirConfig: Send + Sync + Sized + 'static {
+    /// Stable identifier of the AIR e.g. `"fibonacci-v1"`. Bound into the
+    /// public coin seed and stored in proof envelopes so it must be unique to
+    /// the AIR and must change whenever its constraints change.
+    const AIR_ID: &'static str;
     const NUM_BASE_COLUMNS: usize;
     const NUM_EXTENSION_COLUMNS: usize = 0;
+    /// Number of base columns in each commitment group. Base columns are
+    /// partitioned (in order) into groups that are committed to with separate
+    /// merkle trees. This allows committing to a static table separately from
+    /// the rest of the trace for example. Defaults to a single group.
+    const BASE_COLUMN_GROUPS: &'static [usize] = &[Self::NUM_BASE_COLUMNS];
 
     type Fp: GpuFftField<FftField = Self::Fp> + FftField;
     type Fq: StarkExtensionOf<Self::Fp>;
@@ -31,12 +49,20 @@ pub trait AirConfig: Send + Sync + Sized + 'static {
 
     fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>>;
 
+    /// Returns the hints of the AIR. Returns an error if no valid trace can
+    /// have the public inputs e.g. if the hints are derived from public inputs
+    /// that are malformed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPublicInputs`] if hints can't be generated for
+    /// the public inputs
     fn gen_hints(
         _trace_len: usize,
         _public_inputs: &Self::PublicInputs,
         _challenges: &Challenges<Self::Fq>,
-    ) -> Hints<Self::Fq> {
-        Hints::default()
+    ) -> Result<Hints<Self::Fq>, Error> {
+        Ok(Hints::default())
     }
 
     fn domain_offset() -> Self::Fp {
@@ -68,20 +94,18 @@ pub trait AirConfig: Send + Sync + Sized + 'static {
                 assert!(evaluation_degree <= composition_degree);
                 let degree_adjustment = composition_degree - evaluation_degree;
                 // TODO: if degree_adjustment is 0 then we only need one challenge
-                let constraint = constraint.map_leaves(&mut |&leaf| CompositionItem::Item(leaf));
+                let constraint =
+                    constraint.map_leaves(&mut |leaf| CompositionItem::Item(leaf.clone()));
                 let alpha = composition_coeff.next().unwrap();
                 let beta = composition_coeff.next().unwrap();
                 &constraint * (x.clone().pow(degree_adjustment) * alpha + beta)
             })
             .sum::<Expr<CompositionItem<FieldVariant<Self::Fp, Self::Fq>>>>();
-        // TODO: remove log and timing
-        let now = Instant::now();
-        let expr = expr.reuse_shared_nodes();
-        println!("Reuse took: {:?}", now.elapsed());
-        CompositionConstraint::new(expr)
+        CompositionConstraint::new(expr.reuse_shared_nodes())
     }
 
     // TODO: maybe move this into a constraint evaluator
+    #[cfg(feature = "prover")]
     #[allow(clippy::too_many_arguments)]
     fn eval_constraint(
         composition_constraint: &CompositionConstraint<FieldVariant<Self::Fp, Self::Fq>>,
@@ -94,7 +118,7 @@ pub trait AirConfig: Send + Sync + Sized + 'static {
         extension_trace_lde_cols: Option<&[&[Self::Fq]]>,
     ) -> Matrix<Self::Fq> {
         let eval_expr = composition_constraint.map_leaves(&mut |leaf| match leaf {
-            CompositionItem::Item(item) => *item,
+            CompositionItem::Item(item) => item.clone(),
             CompositionItem::CompositionCoeff(i) => {
                 AlgebraicItem::Constant(FieldVariant::Fq(composition_constraint_coeffs[*i]))
             }
@@ -132,35 +156,222 @@ pub fn trace_domain<A: AirConfig>(trace_len: usize) -> Radix2EvaluationDomain<A:
     Radix2EvaluationDomain::new(trace_len).unwrap()
 }
 
-pub struct Air<AC: AirConfig> {
+/// Error returned when proof options or public inputs can't be used with an
+/// AIR
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display(
+        "the AIR's constraints need a blowup factor of at least {ce_blowup_factor} but the \
+         options use {lde_blowup_factor}"
+    ))]
+    BlowupFactorTooSmall {
+        ce_blowup_factor: usize,
+        lde_blowup_factor: u8,
+    },
+    #[snafu(display("public inputs are invalid: {reason}"))]
+    InvalidPublicInputs { reason: String },
+    #[snafu(display(
+        "trace length `{trace_len}` must be a power of two no larger than 2^{two_adicity}"
+    ))]
+    InvalidTraceLen { trace_len: usize, two_adicity: u32 },
+    #[snafu(display(
+        "the LDE domain of size 2^{lde_domain_log_size} is larger than the field's largest FFT \
+         domain of size 2^{two_adicity}"
+    ))]
+    LdeDomainTooLarge {
+        lde_domain_log_size: u32,
+        two_adicity: u32,
+    },
+}
+
+/// Constraints of an AIR for a given trace length.
+///
+/// Doesn't depend on the public inputs or proof options so it's built once per
+/// trace length and shared by every [`Air`] with that trace length (see
+/// [`PreparedAir::cached`]).
+pub struct PreparedAir<AC: AirConfig> {
     constraints: Vec<Constraint<FieldVariant<AC::Fp, AC::Fq>>>,
     composition_constraint: CompositionConstraint<FieldVariant<AC::Fp, AC::Fq>>,
     ce_blowup_factor: usize,
     trace_len: usize,
-    options: ProofOptions,
-    public_inputs: AC::PublicInputs,
 }
 
-impl<C: AirConfig> Air<C> {
-    pub fn new(trace_len: usize, public_inputs: C::PublicInputs, options: ProofOptions) -> Self {
+/// Maximum number of prepared AIRs (across all AIR configs) kept by
+/// [`PreparedAir::cached`]. The least recently used AIR is evicted when a new
+/// AIR is added to a full cache.
+pub const MAX_CACHED_AIRS: usize = 16;
+
+/// Prepared AIR in [`PREPARED_AIRS`] along with the [`CACHE_CLOCK`] value of
+/// its last use
+struct CacheEntry {
+    prepared_air: Arc<dyn Any + Send + Sync>,
+    last_used: AtomicUsize,
+}
+
+/// Prepared AIRs by AIR config and trace length. Values are
+/// `Arc<PreparedAir<C>>` for the AIR config with the matching [`TypeId`].
+static PREPARED_AIRS: spin::RwLock<BTreeMap<(TypeId, usize), CacheEntry>> =
+    spin::RwLock::new(BTreeMap::new());
+
+/// Incremented on every cache lookup to order entries by their last use
+static CACHE_CLOCK: AtomicUsize = AtomicUsize::new(0);
+
+impl<C: AirConfig> PreparedAir<C> {
+    pub fn new(trace_len: usize) -> Self {
         let constraints = C::constraints(trace_len);
         let composition_constraint = C::composition_constraint(trace_len, &constraints);
         let ce_blowup_factor = composition_constraint.blowup_factor(trace_len);
-        assert!(ce_blowup_factor <= options.lde_blowup_factor.into());
+        assert!(
+            !C::BASE_COLUMN_GROUPS.contains(&0),
+            "column groups can't be empty"
+        );
+        assert_eq!(
+            C::NUM_BASE_COLUMNS,
+            C::BASE_COLUMN_GROUPS.iter().sum::<usize>(),
+            "column groups must contain all base columns"
+        );
 
         Self {
             constraints,
             composition_constraint,
             ce_blowup_factor,
             trace_len,
-            options,
-            public_inputs,
         }
     }
 
+    /// Returns the shared prepared AIR for `trace_len`. It's built on first
+    /// use and reused by later calls (from any thread). Proof options are
+    /// applied when the AIR is bound to a proof so they aren't part of the
+    /// cache key. The cache holds at most [`MAX_CACHED_AIRS`] AIRs so trace
+    /// lengths taken from untrusted proofs can't grow it without bound.
+    ///
+    /// # Errors
+    /// Returns an error if `trace_len` isn't a power of two or is larger than
+    /// the largest FFT domain of the field
+    pub fn cached(trace_len: usize) -> Result<Arc<Self>, Error> {
+        let two_adicity = C::Fp::TWO_ADICITY;
+        if !trace_len.is_power_of_two() || trace_len.ilog2() > two_adicity {
+            return Err(Error::InvalidTraceLen {
+                trace_len,
+                two_adicity,
+            });
+        }
+
+        let key = (TypeId::of::<C>(), trace_len);
+        let cached = PREPARED_AIRS.read().get(&key).map(|entry| {
+            let now = CACHE_CLOCK.fetch_add(1, Ordering::Relaxed);
+            entry.last_used.store(now, Ordering::Relaxed);
+            Arc::clone(&entry.prepared_air)
+        });
+        let prepared_air = cached.unwrap_or_else(|| {
+            // build outside the lock so other AIRs aren't blocked
+            let prepared_air: Arc<dyn Any + Send + Sync> = Arc::new(Self::new(trace_len));
+            let mut cache = PREPARED_AIRS.write();
+            if !cache.contains_key(&key) && cache.len() >= MAX_CACHED_AIRS {
+                let least_recently_used = cache
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used.load(Ordering::Relaxed))
+                    .map(|(key, _)| *key);
+                if let Some(key) = least_recently_used {
+                    cache.remove(&key);
+                }
+            }
+            let now = CACHE_CLOCK.fetch_add(1, Ordering::Relaxed);
+            let entry = cache.entry(key).or_insert_with(|| CacheEntry {
+                prepared_air,
+                last_used: AtomicUsize::new(now),
+            });
+            Arc::clone(&entry.prepared_air)
+        });
+        Ok(prepared_air.downcast().unwrap())
+    }
+
     pub const fn trace_len(&self) -> usize {
         self.trace_len
     }
+}
+
+/// An AIR bound to the public inputs and options of a proof. Cloning is cheap
+/// since the constraints are shared.
+pub struct Air<AC: AirConfig> {
+    prepared: Arc<PreparedAir<AC>>,
+    options: ProofOptions,
+    public_inputs: AC::PublicInputs,
+}
+
+impl<C: AirConfig> Clone for Air<C> {
+    fn clone(&self) -> Self {
+        Self {
+            prepared: Arc::clone(&self.prepared),
+            options: self.options,
+            public_inputs: self.public_inputs.clone(),
+        }
+    }
+}
+
+impl<C: AirConfig> Air<C> {
+    /// Returns the AIR for a proof. Constraints come from
+    /// [`PreparedAir::cached`] so they're only built once per trace length.
+    ///
+    /// # Panics
+    /// Panics if the options can't be used with the AIR. See [`Air::try_new`].
+    pub fn new(trace_len: usize, public_inputs: C::PublicInputs, options: ProofOptions) -> Self {
+        Self::try_new(trace_len, public_inputs, options).unwrap()
+    }
+
+    /// Returns the AIR for a proof or an error if the trace length or options
+    /// can't be used with the AIR e.g. a preset with a blowup factor of 4 and
+    /// an AIR with high degree constraints.
+    pub fn try_new(
+        trace_len: usize,
+        public_inputs: C::PublicInputs,
+        options: ProofOptions,
+    ) -> Result<Self, Error> {
+        Self::try_from_prepared(PreparedAir::cached(trace_len)?, public_inputs, options)
+    }
+
+    /// # Panics
+    /// Panics if the options can't be used with the AIR. See
+    /// [`Air::try_from_prepared`].
+    pub fn from_prepared(
+        prepared: Arc<PreparedAir<C>>,
+        public_inputs: C::PublicInputs,
+        options: ProofOptions,
+    ) -> Self {
+        Self::try_from_prepared(prepared, public_inputs, options).unwrap()
+    }
+
+    pub fn try_from_prepared(
+        prepared: Arc<PreparedAir<C>>,
+        public_inputs: C::PublicInputs,
+        options: ProofOptions,
+    ) -> Result<Self, Error> {
+        let ce_blowup_factor = prepared.ce_blowup_factor;
+        let lde_blowup_factor = options.lde_blowup_factor;
+        if ce_blowup_factor > lde_blowup_factor.into() {
+            return Err(Error::BlowupFactorTooSmall {
+                ce_blowup_factor,
+                lde_blowup_factor,
+            });
+        }
+        let lde_domain_log_size = prepared.trace_len.ilog2() + lde_blowup_factor.ilog2();
+        let two_adicity = C::Fp::TWO_ADICITY;
+        if lde_domain_log_size > two_adicity {
+            return Err(Error::LdeDomainTooLarge {
+                lde_domain_log_size,
+                two_adicity,
+            });
+        }
+        Ok(Self {
+            prepared,
+            options,
+            public_inputs,
+        })
+    }
+
+    pub fn trace_len(&self) -> usize {
+        self.prepared.trace_len
+    }
 
     pub const fn options(&self) -> ProofOptions {
         self.options
@@ -170,19 +381,49 @@ impl<C: AirConfig> Air<C> {
         &self.public_inputs
     }
 
-    pub const fn ce_blowup_factor(&self) -> usize {
-        self.ce_blowup_factor
+    pub fn ce_blowup_factor(&self) -> usize {
+        self.prepared.ce_blowup_factor
+    }
+
+    /// Returns the range of base columns in each commitment group
+    pub fn base_column_groups(&self) -> Vec<Range<usize>> {
+        let mut start = 0;
+        C::BASE_COLUMN_GROUPS
+            .iter()
+            .map(|num_cols| {
+                let group = start..start + num_cols;
+                start = group.end;
+                group
+            })
+            .collect()
+    }
+
+    /// Returns the number of distinct query positions. Capped by the size of
+    /// the LDE domain since there can't be more distinct positions than that.
+    pub fn num_queries(&self) -> usize {
+        let lde_domain_size = self.trace_len() * self.lde_blowup_factor();
+        usize::from(self.options.num_queries).min(lde_domain_size)
     }
 
     /// Returns a degree that all constraint polynomials must be normalized to.
-    pub const fn composition_degree(&self) -> usize {
-        let ce_domain_size = self.trace_len * self.ce_blowup_factor();
+    pub fn composition_degree(&self) -> usize {
+        let ce_domain_size = self.trace_len() * self.ce_blowup_factor();
         ce_domain_size - 1
     }
 
+    pub fn num_constraints(&self) -> usize {
+        self.prepared.constraints.len()
+    }
+
+    /// Returns the constraints in the order they're combined into the
+    /// composition constraint
+    pub fn constraints(&self) -> &[Constraint<FieldVariant<C::Fp, C::Fq>>] {
+        &self.prepared.constraints
+    }
+
     pub fn num_challenges(&self) -> usize {
         let mut num_challenges = 0;
-        for constraint in &self.constraints {
+        for constraint in &self.prepared.constraints {
             constraint.traverse(&mut |node| {
                 if let Expr::Leaf(AlgebraicItem::Challenge(i)) = node {
                     num_challenges = core::cmp::max(num_challenges, *i + 1);
@@ -192,13 +433,16 @@ impl<C: AirConfig> Air<C> {
         num_challenges
     }
 
-    pub fn gen_hints(&self, challenges: &Challenges<C::Fq>) -> Hints<C::Fq> {
+    /// # Errors
+    ///
+    /// Returns an error if hints can't be generated for the public inputs
+    pub fn gen_hints(&self, challenges: &Challenges<C::Fq>) -> Result<Hints<C::Fq>, Error> {
         C::gen_hints(self.trace_len(), self.public_inputs(), challenges)
     }
 
     pub fn num_composition_constraint_coeffs(&self) -> usize {
         let mut num_coeffs = 0;
-        self.composition_constraint.traverse(&mut |node| {
+        self.prepared.composition_constraint.traverse(&mut |node| {
             if let Expr::Leaf(CompositionItem::CompositionCoeff(i)) = node {
                 num_coeffs = num_coeffs.max(i + 1);
             }
@@ -207,7 +451,7 @@ impl<C: AirConfig> Air<C> {
     }
 
     pub fn trace_domain(&self) -> Radix2EvaluationDomain<C::Fp> {
-        trace_domain::<C>(self.trace_len)
+        trace_domain::<C>(self.trace_len())
     }
 
     /// Low degree extension domain
@@ -232,16 +476,96 @@ impl<C: AirConfig> Air<C> {
         self.options.lde_blowup_factor as usize
     }
 
-    pub const fn composition_constraint(
-        &self,
-    ) -> &CompositionConstraint<FieldVariant<C::Fp, C::Fq>> {
-        &self.composition_constraint
+    pub fn composition_constraint(&self) -> &CompositionConstraint<FieldVariant<C::Fp, C::Fq>> {
+        &self.prepared.composition_constraint
     }
 
     pub fn trace_arguments(&self) -> BTreeSet<(usize, isize)> {
-        self.constraints
+        self.prepared
+            .constraints
             .iter()
             .map(Constraint::trace_arguments)
             .fold(BTreeSet::new(), |a, b| &a | &b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Air;
+    use super::AirConfig;
+    use super::Error;
+    use super::PreparedAir;
+    use super::MAX_CACHED_AIRS;
+    use super::PREPARED_AIRS;
+    use crate::constraints::Constraint;
+    use crate::constraints::ExecutionTraceColumn;
+    use crate::utils::FieldVariant;
+    use crate::ProofOptions;
+    use alloc::sync::Arc;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+    use num_traits::Pow;
+
+    struct HighDegreeAirConfig;
+
+    impl AirConfig for HighDegreeAirConfig {
+        const AIR_ID: &'static str = "high-degree";
+        const NUM_BASE_COLUMNS: usize = 1;
+        type Fp = Fp;
+        type Fq = Fp;
+        type PublicInputs = ();
+
+        fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+            vec![Constraint::transition(
+                trace_len,
+                0.next() - 0.curr().pow(8),
+            )]
+        }
+    }
+
+    #[test]
+    fn options_are_validated_against_the_air() {
+        let trace_len = 1 << 10;
+
+        let fast_prover =
+            Air::<HighDegreeAirConfig>::try_new(trace_len, (), ProofOptions::FAST_PROVER_96);
+        let small_proof =
+            Air::<HighDegreeAirConfig>::try_new(trace_len, (), ProofOptions::SMALL_PROOF_96);
+
+        assert!(matches!(
+            fast_prover,
+            Err(Error::BlowupFactorTooSmall {
+                lde_blowup_factor: 4,
+                ..
+            })
+        ));
+        assert!(small_proof.is_ok());
+    }
+
+    #[test]
+    fn invalid_trace_lengths_are_rejected() {
+        let not_power_of_two = PreparedAir::<HighDegreeAirConfig>::cached(1000);
+        let too_large = PreparedAir::<HighDegreeAirConfig>::cached(1 << 33);
+
+        assert!(matches!(
+            not_power_of_two,
+            Err(Error::InvalidTraceLen {
+                trace_len: 1000,
+                ..
+            })
+        ));
+        assert!(matches!(too_large, Err(Error::InvalidTraceLen { .. })));
+    }
+
+    #[test]
+    fn least_recently_used_air_is_evicted() {
+        let first = PreparedAir::<HighDegreeAirConfig>::cached(4).unwrap();
+        for log_trace_len in 3..=MAX_CACHED_AIRS + 2 {
+            PreparedAir::<HighDegreeAirConfig>::cached(1 << log_trace_len).unwrap();
+        }
+
+        let rebuilt = PreparedAir::<HighDegreeAirConfig>::cached(4).unwrap();
+
+        assert!(!Arc::ptr_eq(&first, &rebuilt));
+        assert!(PREPARED_AIRS.read().len() <= MAX_CACHED_AIRS);
+    }
+}
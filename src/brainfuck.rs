@@ -0,0 +1,137 @@
+//! Brainf**k virtual machine prover.
+//!
+//! An implementation of the [BrainSTARK](https://aszepieniec.github.io/stark-brainfuck/brainfuck)
+//! AIR. The execution of a program is split into processor, memory,
+//! instruction, input and output tables that are linked by permutation and
+//! evaluation arguments. Programs, their input and their output are public.
+//!
+//! Programs have [`vm::MEMORY_SIZE`] memory cells that each hold a byte.
+//! Overflowing a cell, moving the memory pointer out of bounds or reading past
+//! the end of the input are errors rather than wrapping.
+
+pub mod air;
+pub mod constraints;
+pub mod tables;
+pub mod trace;
+pub mod vm;
+
+use crate::hash::Sha256HashFn;
+use crate::merkle::MatrixMerkleTreeImpl;
+use crate::prover::ProvingError;
+use crate::random::PublicCoinImpl;
+use crate::stark::Stark;
+use crate::utils::SerdeOutput;
+use crate::verifier::VerificationError;
+use crate::Proof;
+use crate::ProofOptions;
+use air::BrainfuckAirConfig;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use ministark_gpu::fields::p18446744069414584321::ark::Fq3;
+use sha2::Sha256;
+use snafu::Snafu;
+use trace::BrainfuckTrace;
+use vm::simulate;
+
+/// Proof options for 96 bit security level
+pub const OPTIONS: ProofOptions = ProofOptions::SMALL_PROOF_96;
+
+/// Security level required by [`verify_program`]
+pub const SECURITY_LEVEL: u32 = 96;
+
+/// Brainf**k error
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("program has no instructions"))]
+    EmptyProgram,
+    #[snafu(display("loop instruction at address `{ip}` has no matching bracket"))]
+    UnmatchedLoop { ip: usize },
+    #[snafu(display("memory pointer moved out of bounds in cycle `{cycle}`"))]
+    MemoryOutOfBounds { cycle: usize },
+    #[snafu(display("memory cell overflowed in cycle `{cycle}`"))]
+    CellOverflow { cycle: usize },
+    #[snafu(display("program read past the end of its input in cycle `{cycle}`"))]
+    InputExhausted { cycle: usize },
+    #[snafu(display("program only read `{read}` of `{provided}` input symbols"))]
+    UnreadInput { read: usize, provided: usize },
+    #[snafu(display("io error: {source}"))]
+    Io { source: std::io::Error },
+    #[snafu(display("proof generation failed: {error:?}"))]
+    Proving { error: ProvingError },
+    #[snafu(display("proof is invalid: {source}"))]
+    Verification { source: VerificationError },
+}
+
+/// Claim that running a program on an input gives an output
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BrainfuckClaim {
+    pub source_code: String,
+    pub input: Vec<u8>,
+    pub output: Vec<u8>,
+}
+
+impl Stark for BrainfuckClaim {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type AirConfig = BrainfuckAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fq3, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = BrainfuckTrace;
+    type Trace = BrainfuckTrace;
+
+    fn get_public_inputs(&self) -> Self {
+        self.clone()
+    }
+
+    fn generate_trace(&self, witness: BrainfuckTrace) -> BrainfuckTrace {
+        witness
+    }
+}
+
+/// Runs a program on `input` and proves its execution. Returns the output of
+/// the program and the proof. The program must read all of its input.
+pub async fn prove_program(
+    source_code: &str,
+    input: &[u8],
+) -> Result<(Vec<u8>, Proof<BrainfuckClaim>), Error> {
+    let mut remaining = input;
+    let mut output = Vec::new();
+    let trace = simulate(source_code, &mut remaining, &mut output)?;
+    if !remaining.is_empty() {
+        return Err(Error::UnreadInput {
+            read: input.len() - remaining.len(),
+            provided: input.len(),
+        });
+    }
+    let claim = BrainfuckClaim {
+        source_code: source_code.into(),
+        input: input.to_vec(),
+        output,
+    };
+    let proof = claim
+        .prove(OPTIONS, trace)
+        .await
+        .map_err(|error| Error::Proving { error })?;
+    Ok((claim.output, proof))
+}
+
+/// Verifies a proof that running a program on `input` gives `output`
+pub fn verify_program(
+    source_code: &str,
+    input: &[u8],
+    output: &[u8],
+    proof: Proof<BrainfuckClaim>,
+) -> Result<(), Error> {
+    vm::compile(source_code)?;
+    let claim = BrainfuckClaim {
+        source_code: source_code.into(),
+        input: input.to_vec(),
+        output: output.to_vec(),
+    };
+    claim
+        .verify(proof, SECURITY_LEVEL)
+        .map_err(|source| Error::Verification { source })?;
+    Ok(())
+}
@@ -1,28 +1,25 @@
-use crate::tables;
-use crate::tables::Challenge;
-use crate::tables::EvaluationArgumentHint;
-use crate::vm::compile;
-use crate::BrainfuckClaim;
+use super::tables;
+use super::tables::Challenge;
+use super::tables::EvaluationArgumentHint;
+use super::vm::compile;
+use super::BrainfuckClaim;
+use crate::air;
+use crate::air::AirConfig;
+use crate::challenges::Challenges;
+use crate::constraints::Constraint;
+use crate::constraints::Hint;
+use crate::constraints::VerifierChallenge;
+use crate::hints::Hints;
+use crate::utils::FieldVariant;
 use ark_ff::Field;
-use ark_ff::One;
 use ark_ff::Zero;
-use ark_poly::EvaluationDomain;
-use ark_poly::Radix2EvaluationDomain;
-use ministark::air::AirConfig;
-use ministark::challenges::Challenges;
-use ministark::constraints::AlgebraicItem;
-use ministark::constraints::Constraint;
-use ministark::constraints::Hint;
-use ministark::constraints::VerifierChallenge;
-use ministark::hints::Hints;
-use ministark::utils::FieldVariant;
 use ministark_gpu::fields::p18446744069414584321::ark::Fp;
 use ministark_gpu::fields::p18446744069414584321::ark::Fq3;
-use num_traits::Pow;
 
 pub struct BrainfuckAirConfig;
 
 impl AirConfig for BrainfuckAirConfig {
+    const AIR_ID: &'static str = "brainfuck";
     const NUM_BASE_COLUMNS: usize = 17;
     const NUM_EXTENSION_COLUMNS: usize = 9;
 
@@ -34,7 +31,7 @@ impl AirConfig for BrainfuckAirConfig {
         trace_len: usize,
         execution_info: &BrainfuckClaim,
         challenges: &Challenges<Self::Fq>,
-    ) -> Hints<Self::Fq> {
+    ) -> Result<Hints<Self::Fq>, air::Error> {
         use Challenge::*;
         use EvaluationArgumentHint::*;
         let BrainfuckClaim {
@@ -49,22 +46,16 @@ impl AirConfig for BrainfuckAirConfig {
             io_terminal_helper(output, challenges[Delta.index()], trace_len);
         let instruction_eval_arg = compute_instruction_evaluation_argument(source_code, challenges);
 
-        Hints::new(vec![
+        Ok(Hints::new(vec![
             (Instruction.index(), instruction_eval_arg),
             (Input.index(), input_eval_arg),
             (InputOffset.index(), input_eval_offset),
             (Output.index(), output_eval_arg),
             (OutputOffset.index(), output_eval_offset),
-        ])
+        ]))
     }
 
     fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Self::Fp, Self::Fq>>> {
-        use AlgebraicItem::*;
-        let one = Constant(FieldVariant::<Fp, Fq3>::Fp(Fp::one()));
-        let trace_xs = Radix2EvaluationDomain::<Fp>::new(trace_len).unwrap();
-        let first_trace_x = Constant(FieldVariant::<Fp, Fq3>::Fp(trace_xs.element(0)));
-        let last_trace_x = Constant(FieldVariant::Fp(trace_xs.element(trace_len - 1)));
-
         let transition_constraints = [
             tables::ProcessorBaseColumn::transition_constraints(),
             tables::ProcessorExtensionColumn::transition_constraints(),
@@ -77,13 +68,7 @@ impl AirConfig for BrainfuckAirConfig {
         ]
         .into_iter()
         .flatten()
-        .map(|constraint| {
-            // ensure constraints hold in all rows except the last
-            // multiply by `(x - t_(n-1))` to remove the last term
-            // NOTE: `x^trace_len - 1 = (x - t_0)(x - t_1)...(x - t_(n-1))`
-            // NOTE: `t^(n-1) = t^(-1)`
-            constraint * ((X - last_trace_x) / (X.pow(trace_len) - one))
-        });
+        .map(|constraint| Constraint::transition(trace_len, constraint));
 
         let boundary_constraints = [
             tables::ProcessorBaseColumn::boundary_constraints(),
@@ -96,11 +81,7 @@ impl AirConfig for BrainfuckAirConfig {
         ]
         .into_iter()
         .flatten()
-        .map(|constraint| {
-            // ensure constraint holds in the first row
-            // symbolically divide `(x - t_0)`
-            constraint / (X - first_trace_x)
-        });
+        .map(|constraint| Constraint::boundary(trace_len, 0, constraint));
 
         let terminal_constraints = [
             tables::ProcessorExtensionColumn::terminal_constraints(),
@@ -110,17 +91,11 @@ impl AirConfig for BrainfuckAirConfig {
         ]
         .into_iter()
         .flatten()
-        .map(|constraint| {
-            // ensure constraint holds in the last row
-            // symbolically divide `(x - t_(n-1))`
-            // NOTE: `t^(n-1) = t^(-1)`
-            constraint / (X - last_trace_x)
-        });
+        .map(|constraint| Constraint::terminal(trace_len, constraint));
 
         transition_constraints
             .chain(boundary_constraints)
             .chain(terminal_constraints)
-            .map(Constraint::from)
             .collect()
     }
 }
@@ -131,7 +106,9 @@ fn compute_instruction_evaluation_argument(source_code: &str, challenges: &Chall
     use Challenge::A;
     use Challenge::B;
     use Challenge::C;
-    let mut program = compile(source_code);
+    // invalid programs are given the evaluation of an empty program. Proofs
+    // can't be generated for them so verification fails
+    let mut program = compile(source_code).unwrap_or_default();
     // add padding
     program.push(0);
     // let prev_ip = None;
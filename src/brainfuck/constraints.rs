@@ -1,26 +1,26 @@
-use crate::tables::Challenge;
-use crate::tables::EvaluationArgumentHint;
-use crate::tables::InputBaseColumn;
-use crate::tables::InputExtensionColumn;
-use crate::tables::InstructionBaseColumn;
-use crate::tables::InstructionExtensionColumn;
-use crate::tables::MemoryBaseColumn;
-use crate::tables::MemoryExtensionColumn;
-use crate::tables::OutputBaseColumn;
-use crate::tables::OutputExtensionColumn;
-use crate::tables::ProcessorBaseColumn;
-use crate::tables::ProcessorExtensionColumn;
-use crate::vm::OpCode;
+use super::tables::Challenge;
+use super::tables::EvaluationArgumentHint;
+use super::tables::InputBaseColumn;
+use super::tables::InputExtensionColumn;
+use super::tables::InstructionBaseColumn;
+use super::tables::InstructionExtensionColumn;
+use super::tables::MemoryBaseColumn;
+use super::tables::MemoryExtensionColumn;
+use super::tables::OutputBaseColumn;
+use super::tables::OutputExtensionColumn;
+use super::tables::ProcessorBaseColumn;
+use super::tables::ProcessorExtensionColumn;
+use super::vm::OpCode;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::ExecutionTraceColumn;
+use crate::constraints::Hint;
+use crate::constraints::VerifierChallenge;
+use crate::expression::Expr;
+use crate::utils::FieldVariant;
+use crate::StarkExtensionOf;
 use ark_ff::FftField;
-use ministark::constraints::AlgebraicItem;
-use ministark::constraints::ExecutionTraceColumn;
-use ministark::constraints::Hint;
-use ministark::constraints::VerifierChallenge;
-use ministark::expression::Expr;
-use ministark::utils::FieldVariant;
-use ministark::StarkExtensionOf;
+use core::borrow::Borrow;
 use ministark_gpu::GpuFftField;
-use std::borrow::Borrow;
 
 impl ProcessorBaseColumn {
     pub fn boundary_constraints<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
@@ -40,8 +40,8 @@ impl ProcessorBaseColumn {
     ) -> Vec<Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>> {
         use ProcessorBaseColumn::*;
         let one = AlgebraicItem::Constant(FieldVariant::Fp(Fp::one()));
-        let two = one + one;
-        let mem_val_is_zero = MemVal.curr() * MemValInv.curr() - one;
+        let two = one.clone() + &one;
+        let mem_val_is_zero = MemVal.curr() * MemValInv.curr() - &one;
         let mut constraints = (None, None, None);
 
         use OpCode::*;
@@ -51,31 +51,32 @@ impl ProcessorBaseColumn {
 
             match instr {
                 IncrementPointer => {
-                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - one);
-                    instr_constraints.1 = Some(Mp.next() - Mp.curr() - one);
+                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - &one);
+                    instr_constraints.1 = Some(Mp.next() - Mp.curr() - &one);
                 }
                 DecrementPointer => {
-                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - one);
-                    instr_constraints.1 = Some(Mp.next() - Mp.curr() + one);
+                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - &one);
+                    instr_constraints.1 = Some(Mp.next() - Mp.curr() + &one);
                 }
                 Increment => {
-                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - one);
+                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - &one);
                     instr_constraints.1 = Some(Mp.next() - Mp.curr());
-                    instr_constraints.2 = Some(MemVal.next() - MemVal.curr() - one);
+                    instr_constraints.2 = Some(MemVal.next() - MemVal.curr() - &one);
                 }
                 Decrement => {
-                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - one);
+                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - &one);
                     instr_constraints.1 = Some(Mp.next() - Mp.curr());
-                    instr_constraints.2 = Some(MemVal.next() - MemVal.curr() + one);
+                    instr_constraints.2 = Some(MemVal.next() - MemVal.curr() + &one);
                 }
                 Write => {
-                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - one);
+                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - &one);
                     instr_constraints.1 = Some(Mp.next() - Mp.curr());
+                    instr_constraints.2 = Some(MemVal.next() - MemVal.curr());
                 }
                 Read => {
-                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - one);
+                    // the memory value is bound by the input evaluation argument
+                    instr_constraints.0 = Some(Ip.next() - Ip.curr() - &one);
                     instr_constraints.1 = Some(Mp.next() - Mp.curr());
-                    instr_constraints.2 = Some(MemVal.next() - MemVal.curr());
                 }
                 LoopBegin => {
                     instr_constraints.0 = Some(
@@ -114,13 +115,13 @@ impl ProcessorBaseColumn {
             constraints.1.unwrap(),
             constraints.2.unwrap(),
             // cycle independent constraints
-            Cycle.next() - Cycle.curr() - one,
+            Cycle.next() - Cycle.curr() - &one,
             MemVal.curr() * &mem_val_is_zero,
             MemValInv.curr() * &mem_val_is_zero,
             // dummy has to be zero or one
-            (Dummy.next() - one) * Dummy.next(),
+            (Dummy.next() - &one) * Dummy.next(),
             // dummy indicates if the row is padding
-            instr_zerofier(CurrInstr.curr()) * (Dummy.curr() - one)
+            instr_zerofier(CurrInstr.curr()) * (Dummy.curr() - &one)
                 + CurrInstr.curr() * Dummy.curr(),
         ]
     }
@@ -147,7 +148,7 @@ impl ProcessorExtensionColumn {
             // instruction permutation:
             // 1. instruction and processor are not padding
             InstructionBaseColumn::CurrInstr.curr()
-                * (Dummy.curr() - one)
+                * (Dummy.curr() - &one)
                 * (InstructionExtensionColumn::ProcessorPermutation.curr()
                     * (Alpha.challenge()
                         - A.challenge() * InstructionBaseColumn::Ip.curr()
@@ -160,7 +161,7 @@ impl ProcessorExtensionColumn {
                             - C.challenge() * NextInstr.curr()))
                 // 2. instruction is padding but processor is not
                 + instr_zerofier(InstructionBaseColumn::CurrInstr.curr())
-                    * (Dummy.curr() - one)
+                    * (Dummy.curr() - &one)
                     * (InstructionExtensionColumn::ProcessorPermutation.curr()
                         - InstructionPermutation.curr()
                             * (Alpha.challenge()
@@ -183,8 +184,8 @@ impl ProcessorExtensionColumn {
                     - InstructionPermutation.curr()),
             // memory permutation:
             // 1. memory and processor are not padding
-            (MemoryBaseColumn::Dummy.curr() - one)
-                * (Dummy.curr() - one)
+            (MemoryBaseColumn::Dummy.curr() - &one)
+                * (Dummy.curr() - &one)
                 * (MemoryExtensionColumn::Permutation.curr()
                     * (Beta.challenge()
                         - Challenge::D.challenge() * MemoryBaseColumn::Cycle.curr()
@@ -197,7 +198,7 @@ impl ProcessorExtensionColumn {
                             - Challenge::F.challenge() * MemVal.curr()))
                 // 2. memory table is padding but processor table is not
                 + MemoryBaseColumn::Dummy.curr()
-                    * (Dummy.curr() - one)
+                    * (Dummy.curr() - &one)
                     * (MemoryExtensionColumn::Permutation.curr()
                         - MemoryPermutation.curr()
                             * (Beta.challenge()
@@ -205,7 +206,7 @@ impl ProcessorExtensionColumn {
                                 - Challenge::E.challenge() * Mp.curr()
                                 - Challenge::F.challenge() * MemVal.curr()))
                 // 3. processor is padding but memory table is not
-                + (MemoryBaseColumn::Dummy.curr() - one)
+                + (MemoryBaseColumn::Dummy.curr() - &one)
                     * Dummy.curr()
                     * (MemoryExtensionColumn::Permutation.curr()
                         * (Beta.challenge()
@@ -290,7 +291,7 @@ impl MemoryBaseColumn {
         vec![
             // 1. memory pointer increases by one or zero
             // note: remember table is sorted by memory address
-            (Mp.next() - Mp.curr() - one) * (Mp.next() - Mp.curr()),
+            (Mp.next() - Mp.curr() - &one) * (Mp.next() - Mp.curr()),
             //
             // 2. the memory value changes only if (a.) the memory pointer does not increase or
             // (b.) the cycle count increases by one.These constraints are implied by 3.
@@ -298,13 +299,13 @@ impl MemoryBaseColumn {
             // 3. if the memory pointer increases by one, then the memory value must be set to zero
             (Mp.next() - Mp.curr()) * MemVal.next(),
             // 4. dummy has to be zero or one
-            (Dummy.next() - one) * Dummy.next(),
+            (Dummy.next() - &one) * Dummy.next(),
             // 5. if dummy is set the memory pointer can not change
             (Mp.next() - Mp.curr()) * Dummy.curr(),
             // 6. if dummy is set the memory value can not change
             (MemVal.next() - MemVal.curr()) * Dummy.curr(),
             // 7. if the memory pointer remains the same, then the cycle has to increase by one
-            (Mp.next() - Mp.curr() - one) * (Cycle.next() - Cycle.curr() - one),
+            (Mp.next() - Mp.curr() - &one) * (Cycle.next() - Cycle.curr() - &one),
         ]
     }
 }
@@ -343,15 +344,15 @@ impl InstructionBaseColumn {
         let one = AlgebraicItem::Constant(FieldVariant::Fp(Fp::one()));
         vec![
             // instruction pointer increases by 0 or 1
-            (Ip.next() - Ip.curr() - one) * (Ip.next() - Ip.curr()),
+            (Ip.next() - Ip.curr() - &one) * (Ip.next() - Ip.curr()),
             // if address increases the next instruction in the current row must equal the current
             // instruction in the next row
             // NOTE: redundant because it is implied by the evaluation argument
             // (Ip.next() - Ip.curr()) * (NextInstr.curr() - CurrInstr.next()),
             // if address is the same, then current instruction is also
-            (Ip.next() - Ip.curr() - one) * (CurrInstr.next() - CurrInstr.curr()),
+            (Ip.next() - Ip.curr() - &one) * (CurrInstr.next() - CurrInstr.curr()),
             // if address is the same, then next instruction is also
-            (Ip.next() - Ip.curr() - one) * (NextInstr.next() - NextInstr.curr()),
+            (Ip.next() - Ip.curr() - &one) * (NextInstr.next() - NextInstr.curr()),
             // dummy has to be zero or one
             // (Dummy.next() - one) * Dummy.next(),
             // // dummy indicates if the row should be included in the permutation argument
@@ -399,7 +400,7 @@ impl InstructionExtensionColumn {
             // - processor permutation doesn't change if `curr_instr=0` i.e. padding
             // - processor permutation doesn't change if `ip` stays the same
             CurrInstr.curr()
-                * (Ip.curr() - Ip.next() + one)
+                * (Ip.curr() - Ip.next() + &one)
                 * (ProcessorPermutation.next()
                     - ProcessorPermutation.curr()
                         * (Alpha.challenge()
@@ -412,7 +413,7 @@ impl InstructionExtensionColumn {
                     * (ProcessorPermutation.curr() - ProcessorPermutation.next()),
             // - no evaluation change if `ip` remains the same
             // - evaluation change if `ip` changes
-            (Ip.next() - Ip.curr() - one) * (ProgramEvaluation.next() - ProgramEvaluation.curr())
+            (Ip.next() - Ip.curr() - &one) * (ProgramEvaluation.next() - ProgramEvaluation.curr())
                 + (Ip.next() - Ip.curr())
                     * (ProgramEvaluation.next()
                         - ProgramEvaluation.curr() * Eta.challenge()
@@ -19,7 +19,7 @@ pub enum Challenge {
     Eta,
 }
 
-impl ministark::constraints::VerifierChallenge for Challenge {
+impl crate::constraints::VerifierChallenge for Challenge {
     fn index(&self) -> usize {
         *self as usize
     }
@@ -34,7 +34,7 @@ pub enum EvaluationArgumentHint {
     OutputOffset,
 }
 
-impl ministark::constraints::Hint for EvaluationArgumentHint {
+impl crate::constraints::Hint for EvaluationArgumentHint {
     fn index(&self) -> usize {
         *self as usize
     }
@@ -108,8 +108,8 @@ pub enum OutputExtensionColumn {
 }
 
 impl BrainfuckColumn for ProcessorBaseColumn {
-    const FIRST_TRACE_COL_INDEX: usize = ProcessorBaseColumn::Cycle as usize;
-    const LAST_TRACE_COL_INDEX: usize = ProcessorBaseColumn::Dummy as usize;
+    const FIRST_TRACE_COL_INDEX: usize = Self::Cycle as usize;
+    const LAST_TRACE_COL_INDEX: usize = Self::Dummy as usize;
 }
 
 impl BrainfuckColumn for MemoryBaseColumn {
@@ -161,7 +161,7 @@ impl BrainfuckColumn for OutputExtensionColumn {
 
 macro_rules! impl_column {
     ($t:ty) => {
-        impl ministark::constraints::ExecutionTraceColumn for $t {
+        impl crate::constraints::ExecutionTraceColumn for $t {
             fn index(&self) -> usize {
                 Self::FIRST_TRACE_COL_INDEX + *self as usize
             }
@@ -1,28 +1,27 @@
-use crate::tables::BrainfuckColumn;
-use crate::tables::Challenge;
-use crate::tables::InputBaseColumn;
-use crate::tables::InputExtensionColumn;
-use crate::tables::InstructionBaseColumn;
-use crate::tables::InstructionExtensionColumn;
-use crate::tables::MemoryBaseColumn;
-use crate::tables::MemoryExtensionColumn;
-use crate::tables::OutputBaseColumn;
-use crate::tables::OutputExtensionColumn;
-use crate::tables::ProcessorBaseColumn;
-use crate::tables::ProcessorExtensionColumn;
-use crate::vm::OpCode;
+use super::tables::BrainfuckColumn;
+use super::tables::Challenge;
+use super::tables::InputBaseColumn;
+use super::tables::InputExtensionColumn;
+use super::tables::InstructionBaseColumn;
+use super::tables::InstructionExtensionColumn;
+use super::tables::MemoryBaseColumn;
+use super::tables::MemoryExtensionColumn;
+use super::tables::OutputBaseColumn;
+use super::tables::OutputExtensionColumn;
+use super::tables::ProcessorBaseColumn;
+use super::tables::ProcessorExtensionColumn;
+use super::vm::OpCode;
+use crate::challenges::Challenges;
+use crate::constraints::VerifierChallenge;
+use crate::utils::GpuAllocator;
+use crate::utils::GpuVec;
+use crate::Matrix;
+use crate::Trace;
 use ark_ff::Field;
 use ark_ff::One;
 use ark_ff::PrimeField;
 use ark_ff::UniformRand;
 use ark_ff::Zero;
-use ministark::challenges::Challenges;
-use ministark::constraints::VerifierChallenge;
-use ministark::utils::GpuAllocator;
-use ministark::utils::GpuVec;
-// use ministark::constraint::Challenge as _;
-use ministark::Matrix;
-use ministark::Trace;
 use ministark_gpu::fields::p18446744069414584321::ark::Fp;
 use ministark_gpu::fields::p18446744069414584321::ark::Fq3;
 
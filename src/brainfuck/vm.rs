@@ -1,17 +1,24 @@
-use crate::tables::BrainfuckColumn;
-use crate::tables::InputBaseColumn;
-use crate::tables::InstructionBaseColumn;
-use crate::tables::MemoryBaseColumn;
-use crate::tables::OutputBaseColumn;
-use crate::tables::ProcessorBaseColumn;
-use crate::trace::into_columns;
-use crate::BrainfuckTrace;
+use super::tables::BrainfuckColumn;
+use super::tables::InputBaseColumn;
+use super::tables::InstructionBaseColumn;
+use super::tables::MemoryBaseColumn;
+use super::tables::OutputBaseColumn;
+use super::tables::ProcessorBaseColumn;
+use super::trace::into_columns;
+use super::trace::BrainfuckTrace;
+use super::Error;
+use crate::Matrix;
 use ark_ff::Field;
 use ark_ff::One;
 use ark_ff::Zero;
-use ministark::Matrix;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
 
-type Fp = <BrainfuckTrace as ministark::Trace>::Fp;
+type Fp = <BrainfuckTrace as crate::Trace>::Fp;
+
+/// Number of memory cells available to programs
+pub const MEMORY_SIZE: usize = 1 << 16;
 
 /// Opcodes determined by the lexer
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -65,11 +72,13 @@ fn lex(source: &str) -> Vec<OpCode> {
     operations
 }
 
-pub fn compile(source: &str) -> Vec<usize> {
+/// Compiles source code into a program. Loop instructions are followed by the
+/// address after their matching bracket.
+pub fn compile(source: &str) -> Result<Vec<usize>, Error> {
     let opcodes = lex(source);
     let mut program = Vec::new();
     let mut stack = Vec::new();
-    for opcode in opcodes.into_iter() {
+    for opcode in opcodes {
         program.push(opcode as usize);
         match opcode {
             OpCode::LoopBegin => {
@@ -78,14 +87,21 @@ pub fn compile(source: &str) -> Vec<usize> {
                 stack.push(program.len() - 1);
             }
             OpCode::LoopEnd => {
-                let last = stack.pop().expect("loop has no beginning");
+                let Some(last) = stack.pop() else {
+                    return Err(Error::UnmatchedLoop {
+                        ip: program.len() - 1,
+                    });
+                };
                 program.push(last + 1); // loop end
                 program[last] = program.len(); // loop beginning
             }
             _ => (),
         }
     }
-    program
+    if let Some(&last) = stack.last() {
+        return Err(Error::UnmatchedLoop { ip: last - 1 });
+    }
+    Ok(program)
 }
 
 /// Registers of the brainfuck VM
@@ -105,24 +121,27 @@ struct Register {
     mem_val: usize,
 }
 
-// Outputs base execution trace
+/// Runs the program and returns the base execution trace. Symbols read by `,`
+/// are taken from `input` and symbols written by `.` are written to `output`.
+/// Memory is [`MEMORY_SIZE`] cells that each hold a byte.
+#[allow(clippy::too_many_lines)]
 pub fn simulate(
     source_code: &str,
-    input: &mut impl std::io::Read,
-    output: &mut impl std::io::Write,
-) -> BrainfuckTrace {
-    let program = compile(source_code);
+    input: &mut impl Read,
+    output: &mut impl Write,
+) -> Result<BrainfuckTrace, Error> {
+    let program = compile(source_code)?;
+    if program.is_empty() {
+        return Err(Error::EmptyProgram);
+    }
 
-    let mut tape = [0u8; 1024];
+    let mut tape = vec![0u8; MEMORY_SIZE];
     let mut register = Register {
-        curr_instr: program[0],
-        next_instr: if program.len() == 1 { 0 } else { program[1] },
+        curr_instr: program.first().map_or(0, |&x| x),
+        next_instr: program.get(1).map_or(0, |&x| x),
         ..Default::default()
     };
 
-    let mut input_symbols = Vec::new();
-    let mut output_symbols = Vec::new();
-
     // execution trace tables in row major
     let mut processor_rows = Vec::new();
     let mut instruction_rows = Vec::new();
@@ -181,29 +200,52 @@ pub fn simulate(
             }
         } else if register.curr_instr == OpCode::DecrementPointer as usize {
             register.ip += 1;
-            register.mp -= 1;
+            register.mp = register.mp.checked_sub(1).ok_or(Error::MemoryOutOfBounds {
+                cycle: register.cycle,
+            })?;
         } else if register.curr_instr == OpCode::IncrementPointer as usize {
             register.ip += 1;
             register.mp += 1;
+            if register.mp == MEMORY_SIZE {
+                return Err(Error::MemoryOutOfBounds {
+                    cycle: register.cycle,
+                });
+            }
         } else if register.curr_instr == OpCode::Increment as usize {
             register.ip += 1;
-            tape[register.mp] += 1;
+            tape[register.mp] = tape[register.mp]
+                .checked_add(1)
+                .ok_or(Error::CellOverflow {
+                    cycle: register.cycle,
+                })?;
         } else if register.curr_instr == OpCode::Decrement as usize {
             register.ip += 1;
-            tape[register.mp] -= 1;
+            tape[register.mp] = tape[register.mp]
+                .checked_sub(1)
+                .ok_or(Error::CellOverflow {
+                    cycle: register.cycle,
+                })?;
         } else if register.curr_instr == OpCode::Write as usize {
             register.ip += 1;
-            let x = &tape[register.mp..register.mp + 1];
-            output.write_all(x).expect("failed to write output");
-            output_rows.push([x[0].into()]);
-            output_symbols.push(x[0]);
+            let x = tape[register.mp];
+            output
+                .write_all(&[x])
+                .map_err(|source| Error::Io { source })?;
+            output_rows.push([x.into()]);
         } else if register.curr_instr == OpCode::Read as usize {
             register.ip += 1;
             let mut x = [0u8; 1];
-            input.read_exact(&mut x).expect("failed to read input");
+            match input.read_exact(&mut x) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                    return Err(Error::InputExhausted {
+                        cycle: register.cycle,
+                    });
+                }
+                Err(source) => return Err(Error::Io { source }),
+            }
             tape[register.mp] = x[0];
             input_rows.push([x[0].into()]);
-            input_symbols.push(x[0]);
         } else {
             panic!("unrecognized instruction at ip:{}", register.ip);
         }
@@ -270,13 +312,13 @@ pub fn simulate(
     let input_base_trace = Matrix::new(into_columns(input_rows));
     let output_base_trace = Matrix::new(into_columns(output_rows));
 
-    BrainfuckTrace::new(
+    Ok(BrainfuckTrace::new(
         processor_base_trace,
         memory_base_trace,
         instruction_base_trace,
         input_base_trace,
         output_base_trace,
-    )
+    ))
 }
 
 fn pad_processor_rows(rows: &mut Vec<[Fp; ProcessorBaseColumn::NUM_TRACE_COLUMNS]>, n: usize) {
@@ -7,7 +7,11 @@ use core::ops::Deref;
 use core::ops::Index;
 
 #[derive(Default, Clone, Debug, CanonicalDeserialize, CanonicalSerialize)]
-pub struct Challenges<F: Field>(Vec<F>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent, bound = ""))]
+pub struct Challenges<F: Field>(
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))] Vec<F>,
+);
 
 impl<F: Field> Challenges<F> {
     pub fn new(challenges: Vec<F>) -> Self {
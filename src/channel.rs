@@ -2,24 +2,25 @@ use crate::challenges::Challenges;
 use crate::fri;
 use crate::fri::FriProof;
 use crate::hints::Hints;
+use crate::merkle::MerkleCap;
 use crate::random::PublicCoin;
 use crate::stark::Stark;
 use crate::trace::Queries;
 use crate::Air;
 use crate::Proof;
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use ark_ff::Field;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
-use std::collections::BTreeSet;
 
 pub struct ProverChannel<'a, S: Stark> {
     air: &'a Air<S::AirConfig>,
     pub public_coin: S::PublicCoin,
-    base_trace_commitment: S::Digest,
-    extension_trace_commitment: Option<S::Digest>,
-    composition_trace_commitment: S::Digest,
-    fri_layer_commitments: Vec<S::Digest>,
+    base_trace_commitments: Vec<MerkleCap<S::Digest>>,
+    extension_trace_commitment: Option<MerkleCap<S::Digest>>,
+    composition_trace_commitment: MerkleCap<S::Digest>,
+    fri_layer_commitments: Vec<MerkleCap<S::Digest>>,
     fri_remainder_coeffs: Vec<S::Fq>,
     execution_trace_ood_evals: Vec<S::Fq>,
     composition_trace_ood_evals: Vec<S::Fq>,
@@ -33,8 +34,8 @@ impl<'a, S: Stark> ProverChannel<'a, S> {
             air,
             public_coin,
             extension_trace_commitment: None,
-            base_trace_commitment: S::Digest::default(),
-            composition_trace_commitment: S::Digest::default(),
+            base_trace_commitments: Vec::new(),
+            composition_trace_commitment: MerkleCap::from_root(S::Digest::default()),
             execution_trace_ood_evals: Vec::new(),
             composition_trace_ood_evals: Vec::new(),
             fri_layer_commitments: Vec::new(),
@@ -43,18 +44,22 @@ impl<'a, S: Stark> ProverChannel<'a, S> {
         }
     }
 
-    pub fn commit_base_trace(&mut self, commitment: S::Digest) {
-        self.public_coin.reseed_with_digest(&commitment);
-        self.base_trace_commitment = commitment;
+    /// Commits to the base trace. Takes a commitment for each base column
+    /// group.
+    pub fn commit_base_trace(&mut self, commitments: Vec<MerkleCap<S::Digest>>) {
+        for commitment in &commitments {
+            self.public_coin.reseed_with_merkle_cap(commitment);
+        }
+        self.base_trace_commitments = commitments;
     }
 
-    pub fn commit_extension_trace(&mut self, commitment: S::Digest) {
-        self.public_coin.reseed_with_digest(&commitment);
+    pub fn commit_extension_trace(&mut self, commitment: MerkleCap<S::Digest>) {
+        self.public_coin.reseed_with_merkle_cap(&commitment);
         self.extension_trace_commitment = Some(commitment);
     }
 
-    pub fn commit_composition_trace(&mut self, commitment: S::Digest) {
-        self.public_coin.reseed_with_digest(&commitment);
+    pub fn commit_composition_trace(&mut self, commitment: MerkleCap<S::Digest>) {
+        self.public_coin.reseed_with_merkle_cap(&commitment);
         self.composition_trace_commitment = commitment;
     }
 
@@ -93,9 +98,8 @@ impl<'a, S: Stark> ProverChannel<'a, S> {
     }
 
     pub fn get_fri_query_positions(&mut self) -> BTreeSet<usize> {
-        // TODO: voulnerability if multiple positions are the same
         let lde_domain_size = self.air.trace_len() * self.air.lde_blowup_factor();
-        let num_queries = self.air.options().num_queries as usize;
+        let num_queries = self.air.num_queries();
         self.public_coin.draw_queries(num_queries, lde_domain_size)
     }
 
@@ -107,7 +111,7 @@ impl<'a, S: Stark> ProverChannel<'a, S> {
         Proof {
             options: self.air.options(),
             trace_len: self.air.trace_len(),
-            base_trace_commitment: self.base_trace_commitment,
+            base_trace_commitments: self.base_trace_commitments,
             extension_trace_commitment: self.extension_trace_commitment,
             composition_trace_commitment: self.composition_trace_commitment,
             execution_trace_ood_evals: self.execution_trace_ood_evals,
@@ -125,8 +129,8 @@ impl<'a, S: Stark> fri::ProverChannel for ProverChannel<'a, S> {
     type Digest = S::Digest;
     type Field = S::Fq;
 
-    fn commit_fri_layer(&mut self, commitment: S::Digest) {
-        self.public_coin.reseed_with_digest(&commitment);
+    fn commit_fri_layer(&mut self, commitment: MerkleCap<S::Digest>) {
+        self.public_coin.reseed_with_merkle_cap(&commitment);
         self.fri_layer_commitments.push(commitment);
     }
 
@@ -143,9 +147,12 @@ impl<'a, S: Stark> fri::ProverChannel for ProverChannel<'a, S> {
 
 // TODO: maybe just have a VerifierChannel
 #[derive(Debug, Clone, CanonicalDeserialize, CanonicalSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct VerifierChannelArtifacts<F: Field> {
     pub air_challenges: Challenges<F>,
     pub air_hints: Hints<F>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub fri_alphas: Vec<F>,
     pub query_positions: Vec<usize>,
 }
@@ -6,13 +6,14 @@ use crate::utils::GpuAllocator;
 use crate::utils::GpuVec;
 use crate::Air;
 use crate::Matrix;
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use ark_ff::Field;
 use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
+use core::iter::zip;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use std::iter::zip;
 
 pub struct DeepPolyComposer<'a, A: AirConfig> {
     z: A::Fq,
@@ -101,10 +102,6 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
             degree: (degree_alpha, degree_beta),
         } = composition_coeffs;
 
-        let trace_domain = air.trace_domain();
-        let g = trace_domain.group_gen();
-        let g_inv = trace_domain.group_gen_inv();
-
         // divide out OOD point from composition trace polys
         let z_n = self.z.pow([composition_trace_polys.num_cols() as u64]);
         let composition_trace_quotients = ark_std::cfg_into_iter!(composition_trace_polys.0)
@@ -118,25 +115,20 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
         let base_column_range = 0..A::NUM_BASE_COLUMNS;
         let extension_column_range = A::NUM_BASE_COLUMNS..num_columns;
         let trace_arguments = air.trace_arguments();
-        let execution_trace_xs_and_alphas = |col_idx| {
-            let mut xs = Vec::new();
-            let mut alphas = Vec::new();
-            for (&(col, offset), &alpha) in zip(&trace_arguments, &execution_trace_alphas) {
-                if col == col_idx {
-                    let generator = if offset >= 0 { g } else { g_inv };
-                    let offset = offset.unsigned_abs() as u64;
-                    let x = z * generator.pow([offset]);
-                    xs.push(x);
-                    alphas.push(alpha);
-                }
-            }
-            (xs, alphas)
+        let xs_and_alphas = |col_idx| {
+            execution_trace_xs_and_alphas(
+                air,
+                z,
+                &trace_arguments,
+                &execution_trace_alphas,
+                col_idx,
+            )
         };
 
         let base_trace_quotients = ark_std::cfg_into_iter!(base_trace_polys.0)
             .zip(base_column_range)
             .map(|(coeffs, col_idx)| {
-                let (xs, alphas) = execution_trace_xs_and_alphas(col_idx);
+                let (xs, alphas) = xs_and_alphas(col_idx);
                 // TODO: inefficient when Fp != Fq
                 let mut coeffs = coeffs
                     .into_iter()
@@ -151,7 +143,7 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
             ark_std::cfg_into_iter!(extension_trace_polys.map_or(vec![], |t| t.0))
                 .zip(extension_column_range)
                 .map(|(mut coeffs, col_idx)| {
-                    let (xs, alphas) = execution_trace_xs_and_alphas(col_idx);
+                    let (xs, alphas) = xs_and_alphas(col_idx);
                     divide_out_points_into(&mut coeffs, &xs, &alphas);
                     coeffs
                 });
@@ -163,29 +155,137 @@ impl<'a, A: AirConfig> DeepPolyComposer<'a, A> {
                 .collect(),
         );
         let mut combined_coeffs = GpuVec::try_from(quotients.sum_columns()).unwrap();
+        adjust_degree(&mut combined_coeffs, degree_alpha, degree_beta);
+        Matrix::new(vec![combined_coeffs])
+    }
 
-        let chunk_size = 1 << 16;
-        if degree_beta.is_zero() {
-            // P(x) * alpha
-            ark_std::cfg_chunks_mut!(combined_coeffs, chunk_size).for_each(|coeff_chunk| {
-                for coeff in coeff_chunk {
-                    *coeff *= degree_alpha;
-                }
-            });
-        } else {
-            // Adjust the degree
-            // P(x) * (alpha + x * beta)
-            let mut last = A::Fq::zero();
-            for coeff in &mut combined_coeffs {
-                let tmp = *coeff;
-                *coeff *= degree_alpha;
-                *coeff += last * degree_beta;
-                last = tmp;
-            }
+    /// Computes the same polynomial as [`Self::into_deep_poly`] but keeps the
+    /// trace polynomials and only holds a single quotient in memory at a time
+    pub fn deep_poly(&self, composition_coeffs: DeepCompositionCoeffs<A::Fq>) -> Matrix<A::Fq> {
+        let Self {
+            z,
+            air,
+            base_trace_polys,
+            extension_trace_polys,
+            composition_trace_polys,
+        } = self;
+
+        let DeepCompositionCoeffs {
+            execution_trace: execution_trace_alphas,
+            composition_trace: composition_trace_alphas,
+            degree: (degree_alpha, degree_beta),
+        } = composition_coeffs;
+
+        let mut combined_coeffs = Vec::with_capacity_in(air.trace_len(), GpuAllocator);
+        combined_coeffs.resize(air.trace_len(), A::Fq::zero());
+        let mut accumulate = |quotient: &[A::Fq]| {
+            ark_std::cfg_iter_mut!(combined_coeffs)
+                .zip(quotient)
+                .for_each(|(coeff, quotient_coeff)| *coeff += quotient_coeff);
+        };
+
+        // divide out OOD point from composition trace polys
+        let z_n = z.pow([composition_trace_polys.num_cols() as u64]);
+        for (column, alpha) in zip(&**composition_trace_polys, composition_trace_alphas) {
+            let mut coeffs = column.to_vec_in(GpuAllocator);
+            divide_out_point_into(&mut coeffs, &z_n, &alpha);
+            accumulate(&coeffs);
         }
 
+        let trace_arguments = air.trace_arguments();
+        let extension_columns = extension_trace_polys
+            .as_deref()
+            .map_or(&[][..], Vec::as_slice);
+        for (col_idx, column) in base_trace_polys.iter().enumerate() {
+            let (xs, alphas) = execution_trace_xs_and_alphas(
+                air,
+                *z,
+                &trace_arguments,
+                &execution_trace_alphas,
+                col_idx,
+            );
+            let mut coeffs = Vec::with_capacity_in(column.len(), GpuAllocator);
+            coeffs.extend(column.iter().map(|&coeff| A::Fq::from(coeff)));
+            divide_out_points_into(&mut coeffs, &xs, &alphas);
+            accumulate(&coeffs);
+        }
+        for (i, column) in extension_columns.iter().enumerate() {
+            let col_idx = A::NUM_BASE_COLUMNS + i;
+            let (xs, alphas) = execution_trace_xs_and_alphas(
+                air,
+                *z,
+                &trace_arguments,
+                &execution_trace_alphas,
+                col_idx,
+            );
+            let mut coeffs = column.to_vec_in(GpuAllocator);
+            divide_out_points_into(&mut coeffs, &xs, &alphas);
+            accumulate(&coeffs);
+        }
+
+        adjust_degree(&mut combined_coeffs, degree_alpha, degree_beta);
         Matrix::new(vec![combined_coeffs])
     }
+
+    /// Returns the trace polynomials in the form
+    /// `(base_trace_polys, extension_trace_polys, composition_trace_polys)`
+    #[allow(clippy::type_complexity)]
+    pub fn into_trace_polys(self) -> (Matrix<A::Fp>, Option<Matrix<A::Fq>>, Matrix<A::Fq>) {
+        (
+            self.base_trace_polys,
+            self.extension_trace_polys,
+            self.composition_trace_polys,
+        )
+    }
+}
+
+/// Returns the OOD points and DEEP coefficients of the execution trace column
+/// `col_idx`
+fn execution_trace_xs_and_alphas<A: AirConfig>(
+    air: &Air<A>,
+    z: A::Fq,
+    trace_arguments: &BTreeSet<(usize, isize)>,
+    execution_trace_alphas: &[A::Fq],
+    col_idx: usize,
+) -> (Vec<A::Fq>, Vec<A::Fq>) {
+    let trace_domain = air.trace_domain();
+    let g = trace_domain.group_gen();
+    let g_inv = trace_domain.group_gen_inv();
+    let mut xs = Vec::new();
+    let mut alphas = Vec::new();
+    for (&(col, offset), &alpha) in zip(trace_arguments, execution_trace_alphas) {
+        if col == col_idx {
+            let generator = if offset >= 0 { g } else { g_inv };
+            let offset = offset.unsigned_abs() as u64;
+            let x = z * generator.pow([offset]);
+            xs.push(x);
+            alphas.push(alpha);
+        }
+    }
+    (xs, alphas)
+}
+
+/// Adjusts the degree of the DEEP composition polynomial
+fn adjust_degree<F: Field>(combined_coeffs: &mut [F], degree_alpha: F, degree_beta: F) {
+    let chunk_size = 1 << 16;
+    if degree_beta.is_zero() {
+        // P(x) * alpha
+        ark_std::cfg_chunks_mut!(combined_coeffs, chunk_size).for_each(|coeff_chunk| {
+            for coeff in coeff_chunk {
+                *coeff *= degree_alpha;
+            }
+        });
+    } else {
+        // Adjust the degree
+        // P(x) * (alpha + x * beta)
+        let mut last = F::zero();
+        for coeff in combined_coeffs {
+            let tmp = *coeff;
+            *coeff *= degree_alpha;
+            *coeff += last * degree_beta;
+            last = tmp;
+        }
+    }
 }
 
 pub struct DeepCompositionCoeffs<F> {
@@ -1,8 +1,18 @@
 use crate::expression::Expr;
 use crate::utils;
+use crate::utils::FieldVariant;
 use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ark_ff::FftField;
+use ark_ff::Field;
 use ark_ff::One;
 use ark_ff::Zero;
+use ark_poly::domain::DomainCoeff;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use core::fmt::Debug;
+use core::hash::Hash;
 use core::iter::Product;
 use core::iter::Sum;
 use core::ops::Add;
@@ -13,23 +23,20 @@ use core::ops::Mul;
 use core::ops::Neg;
 use core::ops::Sub;
 use num_traits::Pow;
-use std::fmt::Debug;
-use std::hash::Hash;
 
-// TODO: should really remove copy as this type might change in the future
-#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
 pub enum AlgebraicItem<T: 'static> {
     X,
     Constant(T),
     Challenge(usize),
-    Periodic(PeriodicColumn<'static, T>),
+    Periodic(PeriodicColumn<T>),
     Hint(usize),
     Trace(/* =column */ usize, /* =offset */ isize),
 }
 
 impl<T> AlgebraicItem<T> {
     // Returns an upper bound on the item's degree in `x`
-    const fn degree(&self, trace_degree: usize) -> Degree {
+    fn degree(&self, trace_degree: usize) -> Degree {
         use AlgebraicItem::*;
         match &self {
             // TODO: handle implications of a zero?
@@ -103,17 +110,25 @@ forward_ref_binop!(impl< T: Clone > Add, add for AlgebraicItem<T>, AlgebraicItem
 forward_ref_binop!(impl< T: Clone > Sub, sub for AlgebraicItem<T>, AlgebraicItem<T>);
 
 /// A periodic column that repeats itself every `interval_size` many rows.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct PeriodicColumn<'a, T> {
-    coeffs: &'a [T],
+///
+/// Columns own their coefficients (shared between clones) so they can be
+/// created at runtime with [`PeriodicColumn::new`] or
+/// [`PeriodicColumn::from_evals`] e.g. in [`AirConfig::constraints`] for
+/// columns that depend on the trace length.
+///
+/// [`AirConfig::constraints`]: crate::air::AirConfig::constraints
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PeriodicColumn<T> {
+    coeffs: Arc<[T]>,
     interval_size: usize,
 }
 
-impl<'a, T> PeriodicColumn<'a, T> {
+impl<T> PeriodicColumn<T> {
     /// # Panics
     /// Panics if the number of coefficients or the
     /// interval size is not a power of two.
-    pub const fn new(coeffs: &'a [T], interval_size: usize) -> Self {
+    pub fn new(coeffs: impl Into<Arc<[T]>>, interval_size: usize) -> Self {
+        let coeffs = coeffs.into();
         assert!(coeffs.len().is_power_of_two());
         assert!(interval_size.is_power_of_two());
         assert!(coeffs.len() <= interval_size);
@@ -127,20 +142,56 @@ impl<'a, T> PeriodicColumn<'a, T> {
         self.interval_size
     }
 
-    pub const fn coeffs(&self) -> &'a [T] {
-        self.coeffs
+    pub fn coeffs(&self) -> &[T] {
+        &self.coeffs
     }
 
     /// Returns an upper bound on the preiodic column's degree in `x`
-    const fn degree(&self, trace_degree: usize) -> Degree {
+    fn degree(&self, trace_degree: usize) -> Degree {
         let trace_len = trace_degree + 1;
         assert!(trace_len.is_power_of_two());
+        assert!(
+            self.interval_size <= trace_len,
+            "interval exceeds the trace"
+        );
         let poly_degree = self.coeffs.len() - 1;
         let num_intervals = trace_len / self.interval_size;
         Degree(poly_degree * num_intervals, 0)
     }
 }
 
+impl<Fp: FftField, Fq: Field + DomainCoeff<Fp>> PeriodicColumn<FieldVariant<Fp, Fq>> {
+    /// Creates a periodic column that takes the values `evals` over each
+    /// interval. The interval size is the number of values.
+    ///
+    /// # Panics
+    /// Panics if the number of values is not a power of two.
+    pub fn from_evals(evals: FieldVariant<Vec<Fp>, Vec<Fq>>) -> Self {
+        let coeffs = match evals {
+            FieldVariant::Fp(evals) => {
+                assert!(evals.len().is_power_of_two());
+                let domain = Radix2EvaluationDomain::<Fp>::new(evals.len()).unwrap();
+                domain
+                    .ifft(&evals)
+                    .into_iter()
+                    .map(FieldVariant::Fp)
+                    .collect::<Vec<_>>()
+            }
+            FieldVariant::Fq(evals) => {
+                assert!(evals.len().is_power_of_two());
+                let domain = Radix2EvaluationDomain::<Fp>::new(evals.len()).unwrap();
+                domain
+                    .ifft(&evals)
+                    .into_iter()
+                    .map(FieldVariant::Fq)
+                    .collect()
+            }
+        };
+        let interval_size = coeffs.len();
+        Self::new(coeffs, interval_size)
+    }
+}
+
 #[derive(Clone)]
 pub struct Constraint<T: 'static>(Expr<AlgebraicItem<T>>);
 
@@ -260,6 +311,76 @@ impl<T> Constraint<T> {
     }
 }
 
+impl<Fp: FftField, Fq: Field> Constraint<FieldVariant<Fp, Fq>> {
+    /// Constraint that `expression` is zero in `row` of a trace with
+    /// `trace_len` rows i.e. `expression / (X - g^row)`
+    pub fn boundary(
+        trace_len: usize,
+        row: usize,
+        expression: impl Into<ConstraintExpr<Fp, Fq>>,
+    ) -> Self {
+        Self::on_rows(trace_len, [row], expression)
+    }
+
+    /// Constraint that `expression` is zero in every row of a trace with
+    /// `trace_len` rows except the last i.e.
+    /// `expression * (X - g^(trace_len - 1)) / (X^trace_len - 1)`.
+    /// Used for constraints between the current and next row.
+    pub fn transition(trace_len: usize, expression: impl Into<ConstraintExpr<Fp, Fq>>) -> Self {
+        use AlgebraicItem::*;
+        let last_row = row_point::<Fp, Fq>(trace_len, trace_len - 1);
+        let every_row = Expr::from(X).pow(trace_len) - Constant(FieldVariant::Fp(Fp::one()));
+        Self::new(expression.into() * ((X - last_row) / every_row))
+    }
+
+    /// Constraint that `expression` is zero in the last row of a trace with
+    /// `trace_len` rows i.e. `expression / (X - g^(trace_len - 1))`
+    pub fn terminal(trace_len: usize, expression: impl Into<ConstraintExpr<Fp, Fq>>) -> Self {
+        Self::boundary(trace_len, trace_len - 1, expression)
+    }
+
+    /// Constraint that `expression` is zero in each of `rows` of a trace with
+    /// `trace_len` rows i.e. `expression / prod_(r in rows) (X - g^r)`.
+    /// Constraints that hold in every row are divided by `X^trace_len - 1`.
+    ///
+    /// # Panics
+    /// Panics if `rows` is empty or contains a row outside the trace
+    pub fn on_rows(
+        trace_len: usize,
+        rows: impl IntoIterator<Item = usize>,
+        expression: impl Into<ConstraintExpr<Fp, Fq>>,
+    ) -> Self {
+        use AlgebraicItem::*;
+        let rows = rows.into_iter().collect::<BTreeSet<usize>>();
+        assert!(!rows.is_empty(), "constraint must hold in at least one row");
+        assert!(
+            rows.iter().all(|&row| row < trace_len),
+            "rows must be less than the trace length {trace_len}"
+        );
+        let zerofier = if rows.len() == trace_len {
+            Expr::from(X).pow(trace_len) - Constant(FieldVariant::Fp(Fp::one()))
+        } else {
+            rows.into_iter()
+                .map(|row| X - row_point::<Fp, Fq>(trace_len, row))
+                .reduce(|acc, divisor| acc * divisor)
+                .unwrap()
+        };
+        Self::new(expression.into() / zerofier)
+    }
+}
+
+/// Expression of a constraint over base field `Fp` and extension field `Fq`
+pub type ConstraintExpr<Fp, Fq> = Expr<AlgebraicItem<FieldVariant<Fp, Fq>>>;
+
+/// Returns the constant `g^row` where `g` generates the trace domain
+fn row_point<Fp: FftField, Fq>(
+    trace_len: usize,
+    row: usize,
+) -> AlgebraicItem<FieldVariant<Fp, Fq>> {
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(trace_len).unwrap();
+    AlgebraicItem::Constant(FieldVariant::Fp(trace_domain.element(row)))
+}
+
 impl<T> From<Expr<AlgebraicItem<T>>> for Constraint<T> {
     fn from(value: Expr<AlgebraicItem<T>>) -> Self {
         Self::new(value)
@@ -280,7 +401,7 @@ impl<T> DerefMut for Constraint<T> {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
 pub enum CompositionItem<T: 'static> {
     Item(AlgebraicItem<T>),
     CompositionCoeff(usize),
@@ -288,7 +409,7 @@ pub enum CompositionItem<T: 'static> {
 
 impl<T> CompositionItem<T> {
     // Returns the item's corresponding degree
-    const fn degree(&self, trace_degree: usize) -> Degree {
+    fn degree(&self, trace_degree: usize) -> Degree {
         match &self {
             Self::Item(item) => item.degree(trace_degree),
             Self::CompositionCoeff(_) => Degree(0, 0),
@@ -303,6 +424,7 @@ impl<T: Zero> Sum<Self> for Expr<CompositionItem<T>> {
     }
 }
 
+#[derive(Clone)]
 pub struct CompositionConstraint<T: 'static>(Expr<CompositionItem<T>>);
 
 impl<T: Clone + Copy + Zero + Ord + Hash> CompositionConstraint<T> {
@@ -343,7 +465,9 @@ const fn blowup_factor(
     trace_degree: usize,
 ) -> usize {
     let degree = numerator_degree.saturating_sub(denominator_degree);
-    utils::ceil_power_of_two(degree) / trace_degree
+    // divide by the trace length rather than the trace degree so the result
+    // stays a power of two for short traces
+    utils::ceil_power_of_two(degree) / (trace_degree + 1)
 }
 
 pub trait Hint {
@@ -0,0 +1,269 @@
+//! Versioned, self-describing container for serialized proofs.
+//!
+//! ```text
+//! | magic (4 bytes) | version (u16) | header | proof |
+//! ```
+//!
+//! The header identifies the fields, hash functions, AIR and public inputs
+//! a proof was generated for so a mismatched proof is rejected with a clear
+//! error before verification. The AIR and hash functions are identified by
+//! [`Stark::air_id`] and [`Stark::hash_id`] which are stable across compiler
+//! versions. Proofs serialized without an envelope (i.e. with
+//! [`CanonicalSerialize`] directly on [`Proof`]) are decoded as version
+//! [`LEGACY_VERSION`].
+
+use crate::stark::Stark;
+use crate::Proof;
+use alloc::string::String;
+use alloc::vec::Vec;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ministark_gpu::GpuField;
+use snafu::Snafu;
+
+/// Magic bytes at the start of every proof envelope
+pub const MAGIC: [u8; 4] = *b"MSTK";
+
+/// Version of the envelope format written by [`encode_proof`]
+pub const VERSION: u16 = 1;
+
+/// Version given to proofs that were serialized without an envelope
+pub const LEGACY_VERSION: u16 = 0;
+
+/// Errors that can occur when decoding a proof envelope
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("envelope version `{version}` is not supported (latest is `{VERSION}`)"))]
+    UnsupportedVersion { version: u16 },
+    #[snafu(display("proof is over the base field `{actual}` but `{expected}` was expected"))]
+    BaseFieldMismatch { expected: String, actual: String },
+    #[snafu(display(
+        "proof is over the extension field `{actual}` but `{expected}` was expected"
+    ))]
+    ExtensionFieldMismatch { expected: String, actual: String },
+    #[snafu(display("proof uses the hash functions `{actual}` but `{expected}` was expected"))]
+    HashMismatch { expected: String, actual: String },
+    #[snafu(display("proof is for the AIR `{actual}` but `{expected}` was expected"))]
+    AirMismatch { expected: String, actual: String },
+    #[snafu(display("proof is for different public inputs"))]
+    PublicInputsMismatch,
+    #[snafu(display("failed to decode the envelope header: {error}"))]
+    InvalidHeader { error: SerializationError },
+    #[snafu(display("failed to decode the proof: {error}"))]
+    InvalidProof { error: SerializationError },
+}
+
+/// Describes what a proof was generated for
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalSerialize)]
+pub struct ProofHeader {
+    /// Name of the base field from [`GpuField::field_name`]
+    pub base_field: String,
+    /// Name of the extension field from [`GpuField::field_name`]
+    pub extension_field: String,
+    /// See [`Stark::hash_id`]
+    pub hash_id: String,
+    /// See [`Stark::air_id`]
+    pub air_id: String,
+    /// Blake3 hash of the serialized public inputs
+    pub public_inputs_hash: [u8; 32],
+}
+
+impl Valid for ProofHeader {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for ProofHeader {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let base_field = String::deserialize_with_mode(&mut reader, compress, validate)?;
+        let extension_field = String::deserialize_with_mode(&mut reader, compress, validate)?;
+        let hash_id = String::deserialize_with_mode(&mut reader, compress, validate)?;
+        let air_id = String::deserialize_with_mode(&mut reader, compress, validate)?;
+        // read directly since deserializing `[u8; 32]` panics on truncated input
+        let mut public_inputs_hash = [0; 32];
+        reader.read_exact(&mut public_inputs_hash)?;
+        Ok(Self {
+            base_field,
+            extension_field,
+            hash_id,
+            air_id,
+            public_inputs_hash,
+        })
+    }
+}
+
+impl ProofHeader {
+    pub fn new<S: Stark>(stark: &S) -> Self {
+        let public_inputs = stark.get_public_inputs();
+        let mut public_inputs_bytes = Vec::new();
+        public_inputs
+            .serialize_compressed(&mut public_inputs_bytes)
+            .unwrap();
+        Self {
+            base_field: S::Fp::field_name(),
+            extension_field: S::Fq::field_name(),
+            hash_id: stark.hash_id(),
+            air_id: stark.air_id(),
+            public_inputs_hash: blake3::hash(&public_inputs_bytes).into(),
+        }
+    }
+
+    /// Checks a proof with this header can be verified by `expected`
+    fn check(self, expected: Self) -> Result<(), Error> {
+        if self.base_field != expected.base_field {
+            return Err(Error::BaseFieldMismatch {
+                expected: expected.base_field,
+                actual: self.base_field,
+            });
+        }
+        if self.extension_field != expected.extension_field {
+            return Err(Error::ExtensionFieldMismatch {
+                expected: expected.extension_field,
+                actual: self.extension_field,
+            });
+        }
+        if self.hash_id != expected.hash_id {
+            return Err(Error::HashMismatch {
+                expected: expected.hash_id,
+                actual: self.hash_id,
+            });
+        }
+        if self.air_id != expected.air_id {
+            return Err(Error::AirMismatch {
+                expected: expected.air_id,
+                actual: self.air_id,
+            });
+        }
+        if self.public_inputs_hash != expected.public_inputs_hash {
+            return Err(Error::PublicInputsMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Serializes a proof generated by `stark` into the latest envelope format
+pub fn encode_proof<S: Stark>(stark: &S, proof: &Proof<S>) -> Result<Vec<u8>, SerializationError> {
+    let mut bytes = MAGIC.to_vec();
+    VERSION.serialize_compressed(&mut bytes)?;
+    ProofHeader::new(stark).serialize_compressed(&mut bytes)?;
+    proof.serialize_compressed(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Returns the envelope version and header of an encoded proof. Proofs without
+/// an envelope have version [`LEGACY_VERSION`] and no header.
+pub fn decode_header(mut bytes: &[u8]) -> Result<(u16, Option<ProofHeader>), Error> {
+    read_envelope(&mut bytes)
+}
+
+/// Deserializes a proof that is to be verified by `stark`.
+///
+/// Checks the fields, hash functions, AIR and public inputs in the envelope
+/// header match `stark`. Proofs without an envelope have nothing to check so
+/// they're only deserialized.
+pub fn decode_proof<S: Stark>(stark: &S, mut bytes: &[u8]) -> Result<Proof<S>, Error> {
+    if let (_version, Some(header)) = read_envelope(&mut bytes)? {
+        header.check(ProofHeader::new(stark))?;
+    }
+    Proof::deserialize_compressed(bytes).map_err(|error| Error::InvalidProof { error })
+}
+
+/// Reads the magic bytes, version and header (if any) and advances `bytes` to
+/// the start of the proof
+fn read_envelope(bytes: &mut &[u8]) -> Result<(u16, Option<ProofHeader>), Error> {
+    // legacy proofs start with the proof options which can't match the magic
+    // bytes since `b'S'` isn't a valid blowup factor
+    let Some(rest) = bytes.strip_prefix(&MAGIC) else {
+        return Ok((LEGACY_VERSION, None));
+    };
+    *bytes = rest;
+    let version =
+        u16::deserialize_compressed(&mut *bytes).map_err(|error| Error::InvalidHeader { error })?;
+    match version {
+        VERSION => {
+            let header = ProofHeader::deserialize_compressed(&mut *bytes)
+                .map_err(|error| Error::InvalidHeader { error })?;
+            Ok((version, Some(header)))
+        }
+        _ => Err(Error::UnsupportedVersion { version }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_header;
+    use super::Error;
+    use super::ProofHeader;
+    use super::LEGACY_VERSION;
+    use super::MAGIC;
+    use super::VERSION;
+    use crate::ProofOptions;
+    use ark_serialize::CanonicalSerialize;
+
+    fn header() -> ProofHeader {
+        ProofHeader {
+            base_field: "fp".into(),
+            extension_field: "fq".into(),
+            hash_id: "sha256".into(),
+            air_id: "fib".into(),
+            public_inputs_hash: [7; 32],
+        }
+    }
+
+    #[test]
+    fn decode_header_round_trip() {
+        let mut bytes = MAGIC.to_vec();
+        VERSION.serialize_compressed(&mut bytes).unwrap();
+        header().serialize_compressed(&mut bytes).unwrap();
+
+        let (version, decoded) = decode_header(&bytes).unwrap();
+
+        assert_eq!(VERSION, version);
+        assert_eq!(Some(header()), decoded);
+    }
+
+    #[test]
+    fn decode_header_of_legacy_proof() {
+        let mut bytes = Vec::new();
+        ProofOptions::FAST_PROVER_96
+            .serialize_compressed(&mut bytes)
+            .unwrap();
+
+        assert_eq!((LEGACY_VERSION, None), decode_header(&bytes).unwrap());
+    }
+
+    #[test]
+    fn decode_header_fails_on_unsupported_version() {
+        let mut bytes = MAGIC.to_vec();
+        (VERSION + 1).serialize_compressed(&mut bytes).unwrap();
+
+        let res = decode_header(&bytes);
+
+        assert!(
+            matches!(res, Err(Error::UnsupportedVersion { version }) if version == VERSION + 1)
+        );
+    }
+
+    #[test]
+    fn decode_header_fails_on_truncated_header() {
+        let mut bytes = MAGIC.to_vec();
+        VERSION.serialize_compressed(&mut bytes).unwrap();
+        header().serialize_compressed(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 1);
+
+        assert!(matches!(
+            decode_header(&bytes),
+            Err(Error::InvalidHeader { .. })
+        ));
+    }
+}
@@ -132,8 +132,8 @@ fn eval_impl<
                             panic!("invalid column {col_idx}")
                         }
                     }
-                    Periodic(col) => {
-                        let lde = periodic_column_evals_map.get(&col).unwrap();
+                    Periodic(ref col) => {
+                        let lde = periodic_column_evals_map.get(col).unwrap();
                         match lde {
                             FieldVariant::Fp(lde) => EvalItem::Evals(Box::new(FieldVariant::Fp(
                                 extract_lde_chunk(lde, chunk_offset),
@@ -184,10 +184,10 @@ pub fn build_periodic_column_evals_map<
     trace_len: usize,
     blowup_factor: usize,
     min_domain_size: usize,
-) -> BTreeMap<PeriodicColumn<'static, FieldVariant<Fp, Fq>>, FieldVariant<Vec<Fp>, Vec<Fq>>> {
+) -> BTreeMap<PeriodicColumn<FieldVariant<Fp, Fq>>, FieldVariant<Vec<Fp>, Vec<Fq>>> {
     let mut res = BTreeMap::new();
     expr.traverse(&mut |node| {
-        if let &Expr::Leaf(AlgebraicItem::Periodic(col)) = node {
+        if let Expr::Leaf(AlgebraicItem::Periodic(col)) = node {
             let interval_size = col.interval_size();
             let coeffs = col.coeffs();
             let is_fp = |&v| match v {
@@ -203,29 +203,29 @@ pub fn build_periodic_column_evals_map<
                         FieldVariant::Fq(_) => unreachable!(),
                     })
                     .collect();
-                let col = PeriodicColumn::new(&coeffs, interval_size);
+                let col = PeriodicColumn::new(coeffs, interval_size);
                 let lde = eval_periodic_column(
                     domain_offset,
                     trace_len,
                     blowup_factor,
-                    col,
+                    &col,
                     min_domain_size,
                 );
                 FieldVariant::Fp(lde)
             } else {
                 let coeffs: Vec<Fq> = coeffs.iter().map(FieldVariant::as_fq).collect();
-                let col = PeriodicColumn::new(&coeffs, interval_size);
+                let col = PeriodicColumn::new(coeffs, interval_size);
                 let lde = eval_periodic_column(
                     domain_offset,
                     trace_len,
                     blowup_factor,
-                    col,
+                    &col,
                     min_domain_size,
                 );
                 FieldVariant::Fq(lde)
             };
 
-            res.insert(col, lde);
+            res.insert(col.clone(), lde);
         }
     });
     res
@@ -236,7 +236,7 @@ pub fn eval_periodic_column<F: GpuField + Field + DomainCoeff<F::FftField>>(
     domain_offset: F::FftField,
     trace_len: usize,
     blowup_factor: usize,
-    periodic_column: PeriodicColumn<'_, F>,
+    periodic_column: &PeriodicColumn<F>,
     min_len: usize,
 ) -> Vec<F>
 where
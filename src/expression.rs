@@ -2,10 +2,14 @@
 #![allow(clippy::arc_with_non_send_sync)]
 
 use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
 use ark_ff::One;
 use ark_std::Zero;
+use core::cell::RefCell;
 use core::cmp::Ordering;
 use core::hash::Hash;
+use core::hash::Hasher;
 use core::iter::Product;
 use core::iter::Sum;
 use core::ops::Add;
@@ -17,17 +21,50 @@ use core::ops::MulAssign;
 use core::ops::Neg;
 use core::ops::Sub;
 use core::ops::SubAssign;
+use core::ptr::addr_of;
 use num_traits::Pow;
-use std::cell::RefCell;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::Hasher;
-use std::ptr::addr_of;
-use std::rc::Rc;
-use std::sync::Arc;
+#[cfg(feature = "std")]
 use std::sync::RwLock;
+#[cfg(not(feature = "std"))]
+use sync::RwLock;
 
 pub type P<T> = Arc<RwLock<T>>;
 
+/// Lock with the same API as [`std::sync::RwLock`] for `no_std` builds
+#[cfg(not(feature = "std"))]
+mod sync {
+    use core::convert::Infallible;
+    use spin::RwLockReadGuard;
+    use spin::RwLockWriteGuard;
+
+    pub struct RwLock<T>(spin::RwLock<T>);
+
+    impl<T> RwLock<T> {
+        pub const fn new(value: T) -> Self {
+            Self(spin::RwLock::new(value))
+        }
+
+        pub fn read(&self) -> Result<RwLockReadGuard<'_, T>, Infallible> {
+            Ok(self.0.read())
+        }
+
+        pub fn write(&self) -> Result<RwLockWriteGuard<'_, T>, Infallible> {
+            Ok(self.0.write())
+        }
+
+        pub fn into_inner(self) -> Result<T, Infallible> {
+            Ok(self.0.into_inner())
+        }
+    }
+}
+
+/// Returns the hasher used to identify shared nodes. `DefaultHasher` isn't
+/// available without `std`.
+#[allow(deprecated)]
+fn node_hasher() -> core::hash::SipHasher {
+    core::hash::SipHasher::new()
+}
+
 /// Expression
 #[derive(Clone)]
 pub enum Expr<T> {
@@ -199,7 +236,7 @@ impl<T> Expr<T> {
         impl<T: Hash + Clone> IdNode<T> {
             fn new_leaf(leaf: &T, seen: SeenSet<T>) -> Self {
                 // `id` is the hash of the leaf
-                let mut hasher = DefaultHasher::new();
+                let mut hasher = node_hasher();
                 ("leaf", leaf).hash(&mut hasher);
                 let id = hasher.finish();
 
@@ -225,7 +262,7 @@ impl<T> Expr<T> {
             fn add(self, rhs: Self) -> Self::Output {
                 let seen = self.seen;
 
-                let mut hasher = DefaultHasher::new();
+                let mut hasher = node_hasher();
                 ("add", self.id, rhs.id).hash(&mut hasher);
                 let id = hasher.finish();
 
@@ -251,7 +288,7 @@ impl<T> Expr<T> {
             fn mul(self, rhs: Self) -> Self::Output {
                 let seen = self.seen;
 
-                let mut hasher = DefaultHasher::new();
+                let mut hasher = node_hasher();
                 ("mul", self.id, rhs.id).hash(&mut hasher);
                 let id = hasher.finish();
 
@@ -277,7 +314,7 @@ impl<T> Expr<T> {
             fn div(self, rhs: Self) -> Self::Output {
                 let seen = self.seen;
 
-                let mut hasher = DefaultHasher::new();
+                let mut hasher = node_hasher();
                 ("div", self.id, rhs.id).hash(&mut hasher);
                 let id = hasher.finish();
 
@@ -303,7 +340,7 @@ impl<T> Expr<T> {
             fn neg(self) -> Self::Output {
                 let seen = self.seen;
 
-                let mut hasher = DefaultHasher::new();
+                let mut hasher = node_hasher();
                 ("neg", self.id).hash(&mut hasher);
                 let id = hasher.finish();
 
@@ -329,7 +366,7 @@ impl<T> Expr<T> {
             fn pow(self, exp: usize) -> Self::Output {
                 let seen = self.seen;
 
-                let mut hasher = DefaultHasher::new();
+                let mut hasher = node_hasher();
                 ("pow", self.id, exp).hash(&mut hasher);
                 let id = hasher.finish();
 
@@ -408,7 +445,7 @@ impl<T> Expr<T> {
     /// Intermediate results are cached to prevent re-evaluation
     pub fn graph_eval<U>(&self, f: &mut impl FnMut(&T) -> U) -> U
     where
-        T: Ord + Copy,
+        T: Ord,
         U: Clone
             + Add<Output = U>
             + Neg<Output = U>
@@ -1,11 +1,14 @@
 use crate::hash::Digest;
 use crate::merkle;
 use crate::merkle::MatrixMerkleTree;
+use crate::merkle::MerkleCap;
 use crate::merkle::MerkleTree;
 use crate::random::PublicCoin;
 use crate::utils::GpuAllocator;
 use crate::utils::GpuVec;
 use crate::Matrix;
+use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use ark_ff::FftField;
 use ark_ff::Field;
@@ -17,20 +20,21 @@ use ark_poly::Polynomial;
 use ark_poly::Radix2EvaluationDomain;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use core::iter::zip;
+use core::marker::PhantomData;
 use ministark_gpu::prelude::*;
 use ministark_gpu::utils::bit_reverse;
 use ministark_gpu::utils::bit_reverse_index;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use snafu::Snafu;
-use std::iter::zip;
-use std::marker::PhantomData;
 
 #[derive(Clone, Copy)]
 pub struct FriOptions {
     folding_factor: usize,
     max_remainder_coeffs: usize,
     blowup_factor: usize,
+    merkle_cap_height: u32,
 }
 
 impl FriOptions {
@@ -43,9 +47,16 @@ impl FriOptions {
             folding_factor,
             max_remainder_coeffs,
             blowup_factor,
+            merkle_cap_height: 0,
         }
     }
 
+    /// Commit to FRI layers with merkle caps of height `merkle_cap_height`
+    pub const fn with_merkle_cap_height(mut self, merkle_cap_height: u32) -> Self {
+        self.merkle_cap_height = merkle_cap_height;
+        self
+    }
+
     pub const fn num_layers(&self, mut domain_size: usize) -> usize {
         let mut num_layers = 0;
         while domain_size > self.max_remainder_coeffs * self.blowup_factor {
@@ -73,8 +84,11 @@ impl FriOptions {
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct FriProof<F: Field, D: Digest, M: MatrixMerkleTree<F>> {
     pub layers: Vec<LayerProof<F, D, M>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub remainder_coeffs: Vec<F>,
 }
 
@@ -96,17 +110,24 @@ struct FriLayer<F: GpuField, M: MerkleTree> {
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct LayerProof<F: Field, D: Digest, M: MatrixMerkleTree<F>> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub flattenend_rows: Vec<F>,
     pub merkle_proof: M::Proof,
-    pub commitment: D,
+    pub commitment: MerkleCap<D>,
 }
 
 impl<F: GpuField + Field, D: Digest, M: MatrixMerkleTree<F, Root = D>> LayerProof<F, D, M>
 where
     F::FftField: FftField,
 {
-    pub fn new<const N: usize>(rows: Vec<[F; N]>, merkle_proof: M::Proof, commitment: D) -> Self {
+    pub fn new<const N: usize>(
+        rows: Vec<[F; N]>,
+        merkle_proof: M::Proof,
+        commitment: MerkleCap<D>,
+    ) -> Self {
         Self {
             flattenend_rows: rows.into_iter().flatten().collect(),
             merkle_proof,
@@ -117,7 +138,7 @@ where
     pub fn verify<const N: usize>(self, positions: &[usize]) -> Result<(), merkle::Error> {
         let commitment = &self.commitment;
         let (rows, _remainder) = &self.flattenend_rows.as_chunks::<N>();
-        M::verify_rows(commitment, positions, rows, self.merkle_proof)
+        M::verify_rows_to_cap(commitment, positions, rows, self.merkle_proof)
     }
 }
 
@@ -149,15 +170,16 @@ where
         let folding_factor = self.options.folding_factor;
         // let (last_layer, initial_layers) = self.layers.split_last().unwrap();
         let mut proof_layers = Vec::new();
+        let cap_height = self.options.merkle_cap_height;
         let mut positions = positions.to_vec();
         for layer in &self.layers {
             positions = fold_positions(&positions, folding_factor);
             proof_layers.push(match folding_factor {
-                2 => query_layer::<F, D, M, 2>(layer, &positions),
-                4 => query_layer::<F, D, M, 4>(layer, &positions),
-                6 => query_layer::<F, D, M, 6>(layer, &positions),
-                8 => query_layer::<F, D, M, 8>(layer, &positions),
-                16 => query_layer::<F, D, M, 16>(layer, &positions),
+                2 => query_layer::<F, D, M, 2>(layer, &positions, cap_height),
+                4 => query_layer::<F, D, M, 4>(layer, &positions, cap_height),
+                6 => query_layer::<F, D, M, 6>(layer, &positions, cap_height),
+                8 => query_layer::<F, D, M, 8>(layer, &positions, cap_height),
+                16 => query_layer::<F, D, M, 16>(layer, &positions, cap_height),
                 _ => unimplemented!("folding factor {folding_factor} is not supported"),
             });
         }
@@ -214,7 +236,7 @@ where
 
         let matrix = Matrix::from_arrays(cosets);
         let merkle_tree = M::from_matrix(&matrix);
-        channel.commit_fri_layer(merkle_tree.root());
+        channel.commit_fri_layer(merkle_tree.cap(self.options.merkle_cap_height));
 
         self.layers.push(FriLayer {
             merkle_tree,
@@ -251,14 +273,48 @@ where
 
 #[derive(Debug, Snafu)]
 pub enum VerificationError {
-    #[snafu(display("queries do not resolve to their commitment in layer {layer}"))]
-    LayerCommitmentInvalid { layer: usize },
-    #[snafu(display("degree respecting projection is invalid for layer {layer}"))]
-    InvalidDegreeRespectingProjection { layer: usize },
-    #[snafu(display("the number of query positions does not match the number of evaluations"))]
-    NumPositionEvaluationMismatch,
-    #[snafu(display("remainder is invalid"))]
-    RemainderCommitmentInvalid,
+    #[snafu(display("queries do not resolve to their commitment in layer {layer}: {source}"))]
+    LayerCommitmentInvalid { layer: usize, source: merkle::Error },
+    #[snafu(display("merkle cap for layer {layer} has {actual} nodes but expected {expected}"))]
+    InvalidLayerCapSize {
+        layer: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[snafu(display("proof has {actual} layers but expected {expected}"))]
+    NumLayersMismatch { expected: usize, actual: usize },
+    #[snafu(display("layer {layer} has {actual} rows but expected {expected}"))]
+    NumLayerRowsMismatch {
+        layer: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[snafu(display(
+        "degree respecting projection is invalid for layer {layer} at query {query} (position \
+         {position}): expected {expected} but the layer has {actual}"
+    ))]
+    InvalidDegreeRespectingProjection {
+        layer: usize,
+        query: usize,
+        position: usize,
+        expected: String,
+        actual: String,
+    },
+    #[snafu(display("there are {positions} query positions but {evaluations} evaluations"))]
+    NumPositionEvaluationMismatch {
+        positions: usize,
+        evaluations: usize,
+    },
+    #[snafu(display(
+        "remainder is invalid at query {query} (position {position}): expected {expected} but \
+         the remainder evaluates to {actual}"
+    ))]
+    RemainderCommitmentInvalid {
+        query: usize,
+        position: usize,
+        expected: String,
+        actual: String,
+    },
     #[snafu(display("remainder is not a degree {degree} polynomial"))]
     RemainderDegreeMismatch { degree: usize },
     #[snafu(display("{size} can't be divided by {folding_factor} (layer {layer})"))]
@@ -276,7 +332,7 @@ where
     F::FftField: FftField,
 {
     options: FriOptions,
-    layer_commitments: Vec<D>,
+    layer_commitments: Vec<MerkleCap<D>>,
     pub layer_alphas: Vec<F>,
     proof: FriProof<F, D, M>,
     domain: Radix2EvaluationDomain<F::FftField>,
@@ -301,13 +357,29 @@ where
         let domain_size = max_poly_degree.next_power_of_two() * options.blowup_factor;
         let domain = Radix2EvaluationDomain::new_coset(domain_size, domain_offset).unwrap();
 
+        let num_layers = options.num_layers(domain_size);
+        if proof.layers.len() != num_layers {
+            return Err(VerificationError::NumLayersMismatch {
+                expected: num_layers,
+                actual: proof.layers.len(),
+            });
+        }
+
         let mut layer_alphas = Vec::new();
         let mut layer_commitments = Vec::new();
         let mut layer_codeword_len = domain_size;
         for (i, layer) in proof.layers.iter().enumerate() {
-            // TODO: batch merkle tree proofs
-            // get the merkle root from the first merkle path
-            public_coin.reseed_with_digest(&layer.commitment);
+            let num_rows = layer_codeword_len / folding_factor;
+            let expected_cap_size = M::cap_size(num_rows, options.merkle_cap_height);
+            if layer.commitment.len() != expected_cap_size {
+                return Err(VerificationError::InvalidLayerCapSize {
+                    layer: i,
+                    expected: expected_cap_size,
+                    actual: layer.commitment.len(),
+                });
+            }
+
+            public_coin.reseed_with_merkle_cap(&layer.commitment);
             let alpha = public_coin.draw();
             layer_alphas.push(alpha);
             layer_commitments.push(layer.commitment.clone());
@@ -364,30 +436,35 @@ where
             let layer_alpha = layer_alphas.next().unwrap();
             let layer_commitment = layer_commitments.next().unwrap();
 
-            // TODO: change assert to error. Check remainder
             let layer = layers.next().unwrap();
             let (rows, _) = &layer.flattenend_rows.as_chunks::<N>();
-            assert_eq!(rows.len(), folded_positions.len());
+            if rows.len() != folded_positions.len() {
+                return Err(VerificationError::NumLayerRowsMismatch {
+                    layer: i,
+                    expected: folded_positions.len(),
+                    actual: rows.len(),
+                });
+            }
 
             // verify the layer values against the layer's commitment
-            M::verify_rows(
+            M::verify_rows_to_cap(
                 &layer_commitment,
                 &folded_positions,
                 rows,
                 layer.merkle_proof,
             )
-            .map_err(|_| VerificationError::LayerCommitmentInvalid { layer: i })?;
+            .map_err(|source| VerificationError::LayerCommitmentInvalid { layer: i, source })?;
 
             let query_values = get_query_values(rows, &positions, &folded_positions);
-            // println!("evaluatinos: {:?}", evaluations);
-            // println!("query vals: {:?}", query_values);
-            assert_eq!(evaluations.len(), query_values.len());
-            for (i, (eval, query_val)) in zip(&evaluations, &query_values).enumerate() {
-                assert_eq!(eval, query_val, "mismatch at {i}");
-            }
-
-            if evaluations != query_values {
-                return Err(VerificationError::InvalidDegreeRespectingProjection { layer: i });
+            let mismatch = zip(&evaluations, &query_values).position(|(eval, value)| eval != value);
+            if let Some(query) = mismatch {
+                return Err(VerificationError::InvalidDegreeRespectingProjection {
+                    layer: i,
+                    query,
+                    position: positions[query],
+                    expected: evaluations[query].to_string(),
+                    actual: query_values[query].to_string(),
+                });
             }
 
             let polys = rows
@@ -426,7 +503,10 @@ where
 
     pub fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError> {
         if positions.len() != evaluations.len() {
-            return Err(VerificationError::NumPositionEvaluationMismatch);
+            return Err(VerificationError::NumPositionEvaluationMismatch {
+                positions: positions.len(),
+                evaluations: evaluations.len(),
+            });
         }
 
         match self.options.folding_factor {
@@ -458,10 +538,8 @@ where
             degree: expected_degree,
         });
     }
-    let xs = positions
-        .iter()
-        .map(|p| domain_generator.pow([bit_reverse_index(domain_size, *p) as u64]));
-    for (i, x) in xs.enumerate() {
+    for (i, &position) in positions.iter().enumerate() {
+        let x = domain_generator.pow([bit_reverse_index(domain_size, position) as u64]);
         // TODO: fix types and just call horner_evaluate
         let y = remainder_poly
             .iter()
@@ -470,7 +548,12 @@ where
                 result + coeff
             });
         if expected_evaluations[i] != y {
-            return Err(VerificationError::RemainderCommitmentInvalid);
+            return Err(VerificationError::RemainderCommitmentInvalid {
+                query: i,
+                position,
+                expected: expected_evaluations[i].to_string(),
+                actual: y.to_string(),
+            });
         }
     }
     Ok(())
@@ -480,7 +563,7 @@ pub trait ProverChannel {
     type Digest: Digest;
     type Field: GpuField;
 
-    fn commit_fri_layer(&mut self, layer_root: Self::Digest);
+    fn commit_fri_layer(&mut self, layer_cap: MerkleCap<Self::Digest>);
 
     fn commit_remainder(&mut self, remainder_coeffs: &[Self::Field]);
 
@@ -650,15 +733,19 @@ pub fn get_query_values<F: Field, const N: usize>(
 fn query_layer<F: GpuField + Field, D: Digest, M: MatrixMerkleTree<F, Root = D>, const N: usize>(
     layer: &FriLayer<F, M>,
     positions: &[usize],
+    cap_height: u32,
 ) -> LayerProof<F, D, M>
 where
     F::FftField: FftField,
 {
-    let merkle_proof = layer.merkle_tree.prove_rows(positions).unwrap();
+    let merkle_proof = layer
+        .merkle_tree
+        .prove_rows_to_cap(positions, cap_height)
+        .unwrap();
     let mut rows: Vec<[F; N]> = Vec::new();
     for &position in positions {
         let row = layer.evaluations.get_row(position).unwrap();
         rows.push(row.try_into().unwrap());
     }
-    LayerProof::new(rows, merkle_proof, layer.merkle_tree.root())
+    LayerProof::new(rows, merkle_proof, layer.merkle_tree.cap(cap_height))
 }
@@ -0,0 +1,26 @@
+//! Reusable AIR components. Hash based gadgets are over the 64-bit Goldilocks
+//! field.
+//!
+//! Gadgets own a range of trace columns and provide the constraints and trace
+//! generation for those columns. AIRs combine gadgets with their own
+//! constraints e.g. to fix the inputs or outputs of a gadget.
+
+pub mod merkle;
+pub mod range_check;
+pub mod rescue;
+
+use crate::constraints::AlgebraicItem;
+use crate::constraints::PeriodicColumn;
+use crate::expression::Expr;
+use crate::utils::FieldVariant;
+use alloc::vec::Vec;
+pub use merkle::MerkleMembership;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+pub use range_check::RangeCheck;
+pub use rescue::RescuePermutation;
+
+/// Returns a periodic column that takes `values` over each interval
+pub(crate) fn periodic_column(values: Vec<Fp>) -> Expr<AlgebraicItem<FieldVariant<Fp, Fp>>> {
+    let column = PeriodicColumn::from_evals(FieldVariant::Fp(values));
+    AlgebraicItem::Periodic(column).into()
+}
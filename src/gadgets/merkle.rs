@@ -0,0 +1,389 @@
+//! Merkle membership gadget.
+//!
+//! Proves a leaf is a row of a [`MatrixMerkleTreeImpl<RescueHashFn>`] with a
+//! given root. Hashing is done by a [`RescuePermutation`] and the direction of
+//! each node on the path is a bit in an extra column:
+//!
+//! ```text
+//! ┌─────────────────┬────────────────────────────┬─────┬───────────────┐
+//! │ permutation 0   │ permutation 1              │ ... │ permutation h │
+//! │ hash(leaf)      │ merge(node, sibling 0)     │     │ outputs root  │
+//! │ bit unused      │ bit 0 (1 if node is right) │     │ bit h - 1     │
+//! └─────────────────┴────────────────────────────┴─────┴───────────────┘
+//! ```
+//!
+//! Leaves are hashed with [`RescueHashFn::hash_elements`] and nodes with
+//! [`RescueHashFn::merge`] exactly like [`MatrixMerkleTreeImpl`] so paths from
+//! trees built off-circuit can be used directly. The leaf values aren't
+//! constrained by the gadget. AIRs can leave them private or constrain them.
+//!
+//! Only binary trees (the default arity) are supported. For a tree committed
+//! to with a merkle cap, use the height of the tree below the cap and
+//! constrain the output to the cap node instead of the root. [`merkle_paths`]
+//! returns paths that end below the cap.
+
+use super::rescue::permutation_rows;
+use super::rescue::PERMUTATION_ROWS;
+use super::RescuePermutation;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::Constraint;
+use crate::constraints::ExecutionTraceColumn;
+use crate::expression::Expr;
+use crate::hash::rescue::RescueDigest;
+use crate::hash::rescue::CAPACITY;
+use crate::hash::rescue::DIGEST_SIZE;
+use crate::hash::rescue::RATE;
+use crate::hash::rescue::STATE_WIDTH;
+use crate::hash::HashFn;
+use crate::hash::RescueHashFn;
+use crate::merkle::Error;
+use crate::merkle::MatrixMerkleTreeImpl;
+use crate::merkle::MerkleTree;
+use crate::merkle::MerkleView;
+use crate::utils::FieldVariant;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use ark_ff::Zero;
+use core::iter::zip;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+/// Merkle path from a leaf of `leaf_width` elements to the root (or merkle cap
+/// node) `height` levels above it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MerkleMembership {
+    rescue: RescuePermutation,
+    leaf_width: usize,
+    height: u32,
+}
+
+impl MerkleMembership {
+    /// Number of columns used by the gadget. The Rescue-Prime state followed
+    /// by the direction bit.
+    pub const NUM_COLUMNS: usize = STATE_WIDTH + 1;
+
+    /// Row that holds the leaf
+    pub const LEAF_ROW: usize = RescuePermutation::input_row(0);
+
+    /// # Panics
+    /// Panics if `leaf_width` is zero or more than [`RATE`]
+    pub const fn new(first_column: usize, leaf_width: usize, height: u32) -> Self {
+        assert!(
+            0 < leaf_width && leaf_width <= RATE,
+            "unsupported leaf width"
+        );
+        Self {
+            rescue: RescuePermutation::new(first_column),
+            leaf_width,
+            height,
+        }
+    }
+
+    /// Returns the Rescue-Prime permutation used for hashing
+    pub const fn rescue(&self) -> RescuePermutation {
+        self.rescue
+    }
+
+    /// Returns the column of the direction bits
+    pub const fn bit_column(&self) -> usize {
+        self.rescue.column(0) + STATE_WIDTH
+    }
+
+    /// Returns the column holding the `i`th leaf element in [`Self::LEAF_ROW`]
+    pub const fn leaf_column(&self, i: usize) -> usize {
+        assert!(i < self.leaf_width);
+        self.rescue.column(CAPACITY + i)
+    }
+
+    /// Returns the row that holds the root
+    pub const fn root_row(&self) -> usize {
+        RescuePermutation::output_row(self.height as usize)
+    }
+
+    /// Returns the minimum trace length
+    pub const fn trace_len(&self) -> usize {
+        PERMUTATION_ROWS * (self.height as usize + 1).next_power_of_two()
+    }
+
+    /// Constraints that the path from the leaf leads to `root`. Rows after the
+    /// root are only constrained to be Rescue-Prime permutations.
+    ///
+    /// # Panics
+    /// Panics if `trace_len` is less than [`Self::trace_len`]
+    pub fn constraints(
+        &self,
+        trace_len: usize,
+        root: [Expr<AlgebraicItem<FieldVariant<Fp, Fp>>>; DIGEST_SIZE],
+    ) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        assert!(trace_len >= self.trace_len(), "trace is too short");
+        let constant = |v: usize| Constant(FieldVariant::Fp(Fp::from(v as u64)));
+        let one = constant(1);
+        let state = |i: usize| self.rescue.column(i);
+        let rate = |i: usize| state(CAPACITY + i);
+        let bit = self.bit_column().curr();
+        let merge_rows = (1..=self.height as usize).map(RescuePermutation::input_row);
+        let merge_rows = merge_rows.collect::<Vec<usize>>();
+        let mut constraints = self.rescue.constraints(trace_len);
+
+        // hash the leaf
+        let leaf_row = Self::LEAF_ROW;
+        let leaf_len = state(0).curr() - constant(self.leaf_width);
+        constraints.push(Constraint::boundary(trace_len, leaf_row, leaf_len));
+        for i in (1..CAPACITY).chain(CAPACITY + self.leaf_width..STATE_WIDTH) {
+            constraints.push(Constraint::boundary(trace_len, leaf_row, state(i).curr()));
+        }
+
+        // merge with the sibling on the side given by the direction bit
+        if !merge_rows.is_empty() {
+            let on_merge_rows =
+                |expr: Expr<_>| Constraint::on_rows(trace_len, merge_rows.clone(), expr);
+            constraints.push(on_merge_rows(state(0).curr() - constant(RATE)));
+            for i in 1..CAPACITY {
+                constraints.push(on_merge_rows(state(i).curr()));
+            }
+            constraints.push(on_merge_rows(&bit * &bit - &bit));
+            for i in 0..DIGEST_SIZE {
+                let node = rate(i).offset(-1);
+                let left = rate(i).curr() - &node;
+                let right = rate(DIGEST_SIZE + i).curr() - node;
+                constraints.push(on_merge_rows((&bit - &one) * left));
+                constraints.push(on_merge_rows(&bit * right));
+            }
+        }
+
+        // check the root
+        for (i, root) in root.into_iter().enumerate() {
+            let root = rate(i).curr() - root;
+            constraints.push(Constraint::boundary(trace_len, self.root_row(), root));
+        }
+
+        constraints
+    }
+
+    /// Returns the rows of the gadget (the state followed by the direction
+    /// bit) for the leaf at `index` with authentication path `path`. Rows after
+    /// the root are padded with permutations of zero.
+    ///
+    /// # Panics
+    /// Panics if the leaf or path don't match the gadget or `trace_len` is less
+    /// than [`Self::trace_len`]
+    pub fn gen_rows(
+        &self,
+        trace_len: usize,
+        leaf: &[Fp],
+        index: usize,
+        path: &[RescueDigest],
+    ) -> Vec<[Fp; Self::NUM_COLUMNS]> {
+        assert_eq!(self.leaf_width, leaf.len(), "leaf has the wrong width");
+        assert_eq!(
+            self.height as usize,
+            path.len(),
+            "path has the wrong length"
+        );
+        assert!(trace_len >= self.trace_len(), "trace is too short");
+        let mut rows = Vec::with_capacity(trace_len);
+        let mut input = [Fp::zero(); STATE_WIDTH];
+        input[0] = Fp::from(leaf.len() as u64);
+        input[CAPACITY..][..leaf.len()].copy_from_slice(leaf);
+        let mut node = push_permutation(&mut rows, input, false);
+        for (level, sibling) in path.iter().enumerate() {
+            let bit = (index >> level) & 1 == 1;
+            let (left, right) = if bit {
+                (sibling, &node)
+            } else {
+                (&node, sibling)
+            };
+            let mut input = [Fp::zero(); STATE_WIDTH];
+            input[0] = Fp::from(RATE as u64);
+            input[CAPACITY..][..DIGEST_SIZE].copy_from_slice(&left.0);
+            input[CAPACITY + DIGEST_SIZE..].copy_from_slice(&right.0);
+            node = push_permutation(&mut rows, input, bit);
+        }
+        while rows.len() < trace_len {
+            push_permutation(&mut rows, [Fp::zero(); STATE_WIDTH], false);
+        }
+        rows
+    }
+}
+
+/// Appends the rows of the permutation of `input` and returns the digest
+fn push_permutation(
+    rows: &mut Vec<[Fp; MerkleMembership::NUM_COLUMNS]>,
+    input: [Fp; STATE_WIDTH],
+    bit: bool,
+) -> RescueDigest {
+    let permutation = permutation_rows(input);
+    for state in permutation {
+        let mut row = [Fp::zero(); MerkleMembership::NUM_COLUMNS];
+        row[..STATE_WIDTH].copy_from_slice(&state);
+        row[STATE_WIDTH] = Fp::from(bit);
+        rows.push(row);
+    }
+    let output = permutation[PERMUTATION_ROWS - 1];
+    RescueDigest(output[CAPACITY..][..DIGEST_SIZE].try_into().unwrap())
+}
+
+/// Returns the authentication path (sibling nodes from the leaf upwards) of
+/// the leaf at `index`
+pub fn authentication_path(
+    tree: &MatrixMerkleTreeImpl<RescueHashFn>,
+    index: usize,
+) -> Result<Vec<RescueDigest>, Error> {
+    let proof = tree.prove(&[index])?;
+    let arity = MatrixMerkleTreeImpl::<RescueHashFn>::ARITY;
+    let mut paths = merkle_paths(&proof, &[index], arity, 0)?;
+    Ok(paths.pop().unwrap())
+}
+
+/// Returns the authentication path (sibling nodes from the leaf upwards) of
+/// each leaf opened by a batched merkle proof.
+///
+/// Paths end below the merkle cap at `cap_height` so they lead to a cap node
+/// rather than the root if `cap_height` is not zero. `indices` must be sorted
+/// and distinct.
+///
+/// # Errors
+/// Returns an error if `arity` isn't 2 (the gadget only merges two nodes at a
+/// time) or if the proof doesn't open the leaves at `indices`
+pub fn merkle_paths(
+    proof: &MerkleView<RescueDigest, RescueDigest>,
+    indices: &[usize],
+    arity: usize,
+    cap_height: u32,
+) -> Result<Vec<Vec<RescueDigest>>, Error> {
+    if arity != 2 {
+        return Err(Error::UnsupportedArity { arity });
+    }
+    let height = proof.height;
+    if height == 0 || height >= usize::BITS {
+        return Err(Error::InvalidProof);
+    }
+    let num_leaves = 1 << height;
+    if let Some(&i) = indices.iter().find(|&&i| i >= num_leaves) {
+        return Err(Error::LeafIndexOutOfBounds { i, n: num_leaves });
+    }
+    let is_sorted = indices.windows(2).all(|w| w[0] < w[1]);
+    if !is_sorted || indices.len() != proof.initial_leaves.len() {
+        return Err(Error::InvalidProof);
+    }
+
+    let mut known = zip(
+        indices.iter().copied(),
+        proof.initial_leaves.iter().copied(),
+    )
+    .collect::<BTreeMap<usize, RescueDigest>>();
+    let mut siblings = proof.sibling_leaves.iter().chain(&proof.nodes).copied();
+    // the cap can't contain leaves
+    let num_levels = height - cap_height.min(height - 1);
+    let mut levels = Vec::new();
+    for _ in 0..num_levels {
+        let mut level = BTreeMap::new();
+        let mut parents = BTreeMap::new();
+        for &position in known.keys() {
+            let parent = position / 2;
+            if parents.contains_key(&parent) {
+                continue;
+            }
+            let [Some(left), Some(right)] = [2 * parent, 2 * parent + 1]
+                .map(|i| known.get(&i).copied().or_else(|| siblings.next()))
+            else {
+                return Err(Error::InvalidProof);
+            };
+            level.insert(2 * parent, left);
+            level.insert(2 * parent + 1, right);
+            parents.insert(parent, RescueHashFn::merge(&left, &right));
+        }
+        levels.push(level);
+        known = parents;
+    }
+    if siblings.next().is_some() {
+        return Err(Error::InvalidProof);
+    }
+
+    Ok(indices
+        .iter()
+        .map(|&i| {
+            let path = levels
+                .iter()
+                .enumerate()
+                .map(|(l, level)| level[&((i >> l) ^ 1)]);
+            path.collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::authentication_path;
+    use super::merkle_paths;
+    use super::MerkleMembership;
+    use crate::hash::rescue::CAPACITY;
+    use crate::hash::rescue::DIGEST_SIZE;
+    use crate::hash::RescueHashFn;
+    use crate::merkle::Error;
+    use crate::merkle::MatrixMerkleTree;
+    use crate::merkle::MatrixMerkleTreeImpl;
+    use crate::merkle::MerkleTree;
+    use crate::Matrix;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    fn tree() -> (Vec<[Fp; 3]>, MatrixMerkleTreeImpl<RescueHashFn>) {
+        let rows = (0..16u64)
+            .map(|i| [i, i * i, 7].map(Fp::from))
+            .collect::<Vec<[Fp; 3]>>();
+        let tree = MatrixMerkleTreeImpl::<RescueHashFn>::from_matrix(&Matrix::from_arrays(&rows));
+        (rows, tree)
+    }
+
+    #[test]
+    fn gen_rows_computes_root_of_matrix_merkle_tree() {
+        let (rows, tree) = tree();
+        let gadget = MerkleMembership::new(0, 3, 4);
+        let index = 11;
+        let path = authentication_path(&tree, index).unwrap();
+
+        let trace = gadget.gen_rows(gadget.trace_len(), &rows[index], index, &path);
+
+        let root = &trace[gadget.root_row()][CAPACITY..][..DIGEST_SIZE];
+        assert_eq!(tree.root().0, root);
+    }
+
+    #[test]
+    fn gen_rows_computes_merkle_cap_node() {
+        let (rows, tree) = tree();
+        let cap_height = 1;
+        let gadget = MerkleMembership::new(0, 3, 4 - cap_height);
+        let index = 11;
+        let proof = tree.prove_to_cap(&[index], cap_height).unwrap();
+        let path = merkle_paths(&proof, &[index], 2, cap_height).unwrap().pop();
+
+        let trace = gadget.gen_rows(gadget.trace_len(), &rows[index], index, &path.unwrap());
+
+        let node = &trace[gadget.root_row()][CAPACITY..][..DIGEST_SIZE];
+        assert_eq!(tree.cap(cap_height)[index >> 3].0, node);
+    }
+
+    #[test]
+    fn merkle_paths_rejects_malformed_proofs() {
+        let (_, tree) = tree();
+        let proof = tree.prove(&[3, 11]).unwrap();
+        let mut missing_node = proof.clone();
+        missing_node.nodes.pop();
+
+        let unsorted = merkle_paths(&proof, &[11, 3], 2, 0);
+        let out_of_bounds = merkle_paths(&proof, &[3, 16], 2, 0);
+        let missing_node = merkle_paths(&missing_node, &[3, 11], 2, 0);
+        let quaternary = merkle_paths(&proof, &[3, 11], 4, 0);
+
+        assert!(matches!(unsorted, Err(Error::InvalidProof)));
+        assert!(matches!(
+            out_of_bounds,
+            Err(Error::LeafIndexOutOfBounds { i: 16, n: 16 })
+        ));
+        assert!(matches!(missing_node, Err(Error::InvalidProof)));
+        assert!(matches!(
+            quaternary,
+            Err(Error::UnsupportedArity { arity: 4 })
+        ));
+    }
+}
@@ -0,0 +1,260 @@
+//! Range check gadget.
+//!
+//! Checks every value of a base column is in `[0, 2^bits)` (`bits` is 16 by
+//! default) without changing the column so it can be constrained by the rest
+//! of the AIR. Three helper base columns are used: a table column holding
+//! each value in the range and two sorted columns. Together the sorted columns
+//! hold the checked values merged with the table in ascending order
+//! (interleaved so row `i` holds elements `2i` and `2i + 1`). The sorted
+//! values start at `0`, end at `2^bits - 1` and each step increases by `0` or
+//! `1`. A grand product extension column shows the sorted values are a
+//! permutation of the checked values and the table:
+//!
+//! ```text
+//! p_0 = 1
+//! p_(i+1)·(α - sorted0_i)·(α - sorted1_i) = p_i·(α - values_i)·(α - table_i)
+//! p_(n-1)·(α - values_(n-1))·(α - table_(n-1)) = (α - sorted0_(n-1))·(α - sorted1_(n-1))
+//! ```
+//!
+//! Since the sorted values can't leave the range neither can the checked
+//! values. The table isn't constrained: any value it holds also ends up in
+//! the sorted columns. It only supplies the values needed to fill the gaps
+//! between the checked values so traces must have at least `2^bits` rows.
+
+use crate::constraints::AlgebraicItem;
+use crate::constraints::Constraint;
+use crate::constraints::ExecutionTraceColumn;
+use crate::constraints::VerifierChallenge;
+use crate::utils::FieldVariant;
+use crate::utils::GpuAllocator;
+use crate::utils::GpuVec;
+use alloc::vec::Vec;
+use ark_ff::batch_inversion;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_ff::PrimeField;
+use core::iter::zip;
+
+/// Range check of a base column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RangeCheck {
+    values: usize,
+    table: usize,
+    sorted: usize,
+    permutation: usize,
+    challenge: usize,
+    bits: u32,
+}
+
+impl RangeCheck {
+    /// Checks values of the base column `values` are in `[0, 2^16)` using the
+    /// base columns `table`, `sorted` and `sorted + 1`, the extension column
+    /// `permutation` and the verifier challenge `challenge`
+    pub const fn new(
+        values: usize,
+        table: usize,
+        sorted: usize,
+        permutation: usize,
+        challenge: usize,
+    ) -> Self {
+        Self {
+            values,
+            table,
+            sorted,
+            permutation,
+            challenge,
+            bits: 16,
+        }
+    }
+
+    /// Checks values are in `[0, 2^bits)` instead
+    ///
+    /// # Panics
+    /// Panics if `bits` is more than 32
+    pub const fn with_bits(mut self, bits: u32) -> Self {
+        assert!(bits <= 32, "range is too large");
+        self.bits = bits;
+        self
+    }
+
+    /// Returns the largest value in the range
+    pub const fn max(&self) -> u64 {
+        (1 << self.bits) - 1
+    }
+
+    /// Returns the minimum trace length
+    pub const fn trace_len(&self) -> usize {
+        1 << self.bits
+    }
+
+    /// Returns the two base columns holding the sorted values
+    pub const fn sorted_columns(&self) -> [usize; 2] {
+        [self.sorted, self.sorted + 1]
+    }
+
+    /// Constraints of the range check
+    ///
+    /// # Panics
+    /// Panics if `trace_len` is less than [`Self::trace_len`]
+    pub fn constraints<Fp: FftField, Fq: Field>(
+        &self,
+        trace_len: usize,
+    ) -> Vec<Constraint<FieldVariant<Fp, Fq>>> {
+        use AlgebraicItem::*;
+        assert!(trace_len >= self.trace_len(), "trace is too short");
+        let one = Constant(FieldVariant::Fp(Fp::one()));
+        let max = Constant(FieldVariant::Fp(Fp::from(self.max())));
+        let alpha = self.challenge.challenge();
+        let values = self.values.curr();
+        let table = self.table.curr();
+        let [sorted0, sorted1] = self.sorted_columns();
+        let (sorted0, sorted1, sorted0_next) = (sorted0.curr(), sorted1.curr(), sorted0.next());
+        let product = self.permutation.curr();
+        let product_next = self.permutation.next();
+        let step_in_row = &sorted1 - &sorted0;
+        let step_to_next_row = &sorted0_next - &sorted1;
+        let checked = (&alpha - &values) * (&alpha - &table);
+        let sorted = (&alpha - &sorted0) * (&alpha - &sorted1);
+
+        alloc::vec![
+            // sorted values go from 0 to the max in steps of 0 or 1
+            Constraint::boundary(trace_len, 0, sorted0),
+            Constraint::terminal(trace_len, &sorted1 - max),
+            Constraint::on_rows(
+                trace_len,
+                0..trace_len,
+                &step_in_row * (&step_in_row - &one)
+            ),
+            Constraint::transition(trace_len, &step_to_next_row * (&step_to_next_row - &one)),
+            // sorted values are a permutation of the values and the table
+            Constraint::boundary(trace_len, 0, &product - one),
+            Constraint::transition(trace_len, product_next * &sorted - &product * &checked),
+            Constraint::terminal(trace_len, product * checked - sorted),
+        ]
+    }
+
+    /// Returns a table column holding each value in the range in ascending
+    /// order padded with the max value to `trace_len`
+    ///
+    /// # Panics
+    /// Panics if `trace_len` is less than [`Self::trace_len`]
+    pub fn gen_table_column<Fp: PrimeField>(&self, trace_len: usize) -> GpuVec<Fp> {
+        assert!(trace_len >= self.trace_len(), "trace is too short");
+        let mut column = Vec::with_capacity_in(trace_len, GpuAllocator);
+        column.extend((0..=self.max()).map(Fp::from));
+        column.resize(trace_len, Fp::from(self.max()));
+        column
+    }
+
+    /// Returns the two sorted columns for the values column `values` and the
+    /// table column `table`
+    ///
+    /// # Panics
+    /// Panics if a value is out of range or the columns have different lengths
+    pub fn gen_sorted_columns<Fp: PrimeField>(
+        &self,
+        values: &[Fp],
+        table: &[Fp],
+    ) -> [GpuVec<Fp>; 2] {
+        assert_eq!(values.len(), table.len());
+        let mut counts = alloc::vec![0; self.trace_len()];
+        for value in values.iter().chain(table) {
+            counts[self.position(value)] += 1;
+        }
+        let sorted = counts
+            .into_iter()
+            .enumerate()
+            .flat_map(|(v, count)| (0..count).map(move |_| Fp::from(v as u64)));
+        let mut columns = [(); 2].map(|()| Vec::with_capacity_in(values.len(), GpuAllocator));
+        for (i, value) in sorted.enumerate() {
+            columns[i % 2].push(value);
+        }
+        columns
+    }
+
+    /// Returns the permutation column for the values column `values`, table
+    /// column `table` and sorted columns `sorted` using the verifier challenge
+    /// `challenge`
+    pub fn gen_permutation_column<Fp: PrimeField, Fq: Field<BasePrimeField = Fp>>(
+        values: &[Fp],
+        table: &[Fp],
+        sorted: [&[Fp]; 2],
+        challenge: Fq,
+    ) -> GpuVec<Fq> {
+        let [sorted0, sorted1] = sorted;
+        assert_eq!(values.len(), table.len());
+        assert_eq!(values.len(), sorted0.len());
+        assert_eq!(values.len(), sorted1.len());
+        let from_fp = Fq::from_base_prime_field;
+        let mut denominators = zip(sorted0, sorted1)
+            .map(|(&s0, &s1)| (challenge - from_fp(s0)) * (challenge - from_fp(s1)))
+            .collect::<Vec<Fq>>();
+        batch_inversion(&mut denominators);
+        let mut column = Vec::with_capacity_in(values.len(), GpuAllocator);
+        let mut product = Fq::one();
+        for ((&v, &t), denominator) in zip(values, table).zip(denominators) {
+            column.push(product);
+            product *= (challenge - from_fp(v)) * (challenge - from_fp(t)) * denominator;
+        }
+        column
+    }
+
+    /// Returns the position of `value` in the range
+    fn position<Fp: PrimeField>(&self, value: &Fp) -> usize {
+        let value = value.into_bigint();
+        let max = Fp::from(self.max()).into_bigint();
+        assert!(value <= max, "value {value} out of range");
+        usize::try_from(value.as_ref()[0]).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeCheck;
+    use ark_ff::One;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    #[test]
+    fn gen_columns_for_range_check() {
+        let range_check = RangeCheck::new(0, 1, 2, 4, 0).with_bits(4);
+        let mut values = [3u8, 15, 3, 0, 7].map(Fp::from).to_vec();
+        values.resize(32, Fp::from(9u8));
+        let challenge = Fp::from(999u32);
+
+        let table = range_check.gen_table_column(32);
+        let [sorted0, sorted1] = range_check.gen_sorted_columns(&values, &table);
+        let sorted = [&*sorted0, &*sorted1];
+        let permutation = RangeCheck::gen_permutation_column(&values, &table, sorted, challenge);
+
+        assert_eq!(32, table.len());
+        assert_eq!(32, sorted0.len());
+        assert_eq!(32, sorted1.len());
+        assert_eq!(Fp::from(0u8), sorted0[0]);
+        assert_eq!(Fp::from(15u8), *sorted1.last().unwrap());
+        let merged = sorted0
+            .iter()
+            .zip(sorted1.iter())
+            .flat_map(|(&s0, &s1)| [s0, s1])
+            .collect::<Vec<Fp>>();
+        assert!(merged
+            .windows(2)
+            .all(|w| w[1] == w[0] || w[1] == w[0] + Fp::one()));
+        let n = values.len() - 1;
+        assert_eq!(
+            (challenge - sorted0[n]) * (challenge - sorted1[n]),
+            permutation[n] * (challenge - values[n]) * (challenge - table[n])
+        );
+        assert_eq!(Fp::one(), permutation[0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn gen_sorted_columns_fails_for_out_of_range_values() {
+        let range_check = RangeCheck::new(0, 1, 2, 4, 0).with_bits(4);
+        let table = range_check.gen_table_column::<Fp>(16);
+        let mut values = vec![Fp::from(0u8); 16];
+        values[3] = Fp::from(16u8);
+
+        range_check.gen_sorted_columns(&values, &table);
+    }
+}
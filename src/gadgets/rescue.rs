@@ -0,0 +1,154 @@
+//! Rescue-Prime permutation gadget.
+//!
+//! A permutation spans [`PERMUTATION_ROWS`] rows of [`STATE_WIDTH`] columns:
+//! the input state followed by the state after each round. Permutations are
+//! laid out back to back from the first row of the trace and the round
+//! constants are supplied by periodic columns. AIRs using the gadget constrain
+//! the input and output rows of each permutation themselves.
+//!
+//! A round maps `s` to `s' = M·(M·s^α + c1)^(1/α) + c2` which is checked as
+//! `M·s^α + c1 = (M^(-1)·s' - M^(-1)·c2)^α` so the forward and backward S-boxes
+//! both have degree `α` (see [`crate::hash::rescue`] for `M`, `c1` and `c2`).
+
+use super::periodic_column;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::Constraint;
+use crate::constraints::ExecutionTraceColumn;
+use crate::expression::Expr;
+use crate::hash::rescue::apply_mds;
+use crate::hash::rescue::apply_round;
+use crate::hash::rescue::ALPHA;
+use crate::hash::rescue::MDS;
+use crate::hash::rescue::MDS_INV;
+use crate::hash::rescue::NUM_ROUNDS;
+use crate::hash::rescue::ROUND_CONSTANTS;
+use crate::hash::rescue::STATE_WIDTH;
+use crate::utils::FieldVariant;
+use alloc::vec::Vec;
+use ark_ff::Zero;
+use core::iter::zip;
+use core::ops::Range;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use num_traits::Pow;
+
+/// Number of rows of each Rescue-Prime permutation
+pub const PERMUTATION_ROWS: usize = NUM_ROUNDS + 1;
+
+/// Rescue-Prime permutation over [`STATE_WIDTH`] consecutive columns
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RescuePermutation {
+    first_column: usize,
+}
+
+impl RescuePermutation {
+    pub const fn new(first_column: usize) -> Self {
+        Self { first_column }
+    }
+
+    /// Returns the state columns
+    pub const fn columns(&self) -> Range<usize> {
+        self.first_column..self.first_column + STATE_WIDTH
+    }
+
+    /// Returns the column of the `i`th state element
+    pub const fn column(&self, i: usize) -> usize {
+        assert!(i < STATE_WIDTH);
+        self.first_column + i
+    }
+
+    /// Returns the row holding the input of the `i`th permutation
+    pub const fn input_row(i: usize) -> usize {
+        PERMUTATION_ROWS * i
+    }
+
+    /// Returns the row holding the output of the `i`th permutation
+    pub const fn output_row(i: usize) -> usize {
+        PERMUTATION_ROWS * i + NUM_ROUNDS
+    }
+
+    /// Constraints that the rows of each permutation are the rounds applied to
+    /// its input row. Transitions from an output row are unconstrained.
+    ///
+    /// # Panics
+    /// Panics if `trace_len` isn't a multiple of [`PERMUTATION_ROWS`]
+    pub fn constraints(&self, trace_len: usize) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        assert_eq!(
+            0,
+            trace_len % PERMUTATION_ROWS,
+            "trace length must be a multiple of {PERMUTATION_ROWS}"
+        );
+        let constant = |v: u64| Expr::from(AlgebraicItem::Constant(FieldVariant::Fp(Fp::from(v))));
+        let alpha = usize::try_from(ALPHA).unwrap();
+        let is_round = periodic_column(round_values(|round| Fp::from(u8::from(round.is_some()))));
+        let sbox = self
+            .columns()
+            .map(|column| column.curr().pow(alpha))
+            .collect::<Vec<_>>();
+        let inv_sbox = (0..STATE_WIDTH).map(|i| {
+            let c2 = periodic_column(round_values(|round| {
+                round.map_or_else(Fp::zero, |r| {
+                    let mut c2 = ROUND_CONSTANTS[2 * r + 1].map(Fp::from);
+                    apply_mds(&mut c2, &MDS_INV);
+                    c2[i]
+                })
+            }));
+            let next_state = zip(MDS_INV[i], self.columns())
+                .map(|(m, column)| constant(m) * column.next())
+                .sum::<Expr<_>>();
+            (next_state - c2).pow(alpha)
+        });
+        inv_sbox
+            .enumerate()
+            .map(|(i, inv_sbox)| {
+                let c1 = periodic_column(round_values(|round| {
+                    round.map_or_else(Fp::zero, |r| Fp::from(ROUND_CONSTANTS[2 * r][i]))
+                }));
+                let state = zip(MDS[i], &sbox)
+                    .map(|(m, s)| constant(m) * s)
+                    .sum::<Expr<_>>();
+                Constraint::transition(trace_len, &is_round * (state + c1 - inv_sbox))
+            })
+            .collect()
+    }
+}
+
+/// Returns the rows of the permutation of `input` i.e. `input` followed by the
+/// state after each round
+pub fn permutation_rows(mut input: [Fp; STATE_WIDTH]) -> [[Fp; STATE_WIDTH]; PERMUTATION_ROWS] {
+    let mut rows = [input; PERMUTATION_ROWS];
+    for (round, row) in rows.iter_mut().skip(1).enumerate() {
+        apply_round(&mut input, round);
+        *row = input;
+    }
+    rows
+}
+
+/// Returns the values of a periodic column of interval [`PERMUTATION_ROWS`]
+/// with `f(Some(round))` on the rows followed by a round and `f(None)` on the
+/// output row
+fn round_values(f: impl Fn(Option<usize>) -> Fp) -> Vec<Fp> {
+    (0..PERMUTATION_ROWS)
+        .map(|row| f((row < NUM_ROUNDS).then_some(row)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::permutation_rows;
+    use super::RescuePermutation;
+    use crate::hash::rescue::permute;
+    use crate::hash::rescue::STATE_WIDTH;
+    use ark_ff::Zero;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    #[test]
+    fn permutation_rows_end_with_permutation() {
+        let mut state = [Fp::zero(); STATE_WIDTH];
+        state[0] = Fp::from(5u8);
+        state[7] = Fp::from(11u8);
+        let rows = permutation_rows(state);
+        permute(&mut state);
+
+        assert_eq!(state, rows[RescuePermutation::output_row(0)]);
+    }
+}
@@ -1,10 +1,15 @@
+pub mod rescue;
+
 use crate::utils::SerdeOutput;
+use alloc::vec::Vec;
 use ark_ff::Field;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use blake3::Hasher as Blake3;
 use core::fmt::Debug;
-use digest::Digest as _;
+pub use rescue::RescueHashFn;
 use sha2::Sha256;
+use sha3::Keccak256;
 
 /// Trait inspired by Winterfell: <https://github.com/facebook/winterfell/blob/main/crypto/src/hash/mod.rs#L33>
 pub trait HashFn: Send + Sync + 'static {
@@ -14,6 +19,9 @@ pub trait HashFn: Send + Sync + 'static {
     /// Collision resistance of the hash function measured in bits.
     const COLLISION_RESISTANCE: u32;
 
+    /// Stable name of the hash function e.g. `"sha256"`.
+    const ID: &'static str;
+
     /// Returns a hash of the provided sequence of bytes.
     fn hash(bytes: impl IntoIterator<Item = u8>) -> Self::Digest;
 
@@ -24,6 +32,16 @@ pub trait HashFn: Send + Sync + 'static {
     /// construction of Merkle trees.
     fn merge(v0: &Self::Digest, v1: &Self::Digest) -> Self::Digest;
 
+    /// Returns a hash of many digests. This method is intended for use in
+    /// construction of higher arity Merkle trees.
+    fn merge_many(values: &[Self::Digest]) -> Self::Digest {
+        let bytes = values
+            .iter()
+            .map(Digest::as_bytes)
+            .collect::<Vec<[u8; 32]>>();
+        Self::hash_chunks(bytes.iter().map(<[u8; 32]>::as_slice))
+    }
+
     /// Returns hash(`seed` || `value`). This method is intended for use in PRNG
     /// and proof-of-work contexts.
     fn merge_with_int(seed: &Self::Digest, value: u64) -> Self::Digest;
@@ -55,46 +73,129 @@ pub trait Digest:
     fn as_bytes(&self) -> [u8; 32];
 }
 
+/// Implements [`HashFn`] and [`ElementHashFn`] for a hash function that
+/// implements the [`digest::Digest`] trait and has a 32 byte output.
+// NOTE: methods are called with fully qualified syntax since some hashers
+// (e.g. BLAKE3) have inherent methods with the same names.
+macro_rules! impl_digest_hash_fn {
+    ($name:ident, $hasher:ty, $id:literal, $collision_resistance:expr) => {
+        impl HashFn for $name {
+            type Digest = SerdeOutput<$hasher>;
+
+            const COLLISION_RESISTANCE: u32 = $collision_resistance;
+            const ID: &'static str = $id;
+
+            fn hash(bytes: impl IntoIterator<Item = u8>) -> SerdeOutput<$hasher> {
+                let bytes = bytes.into_iter().collect::<Vec<u8>>();
+                SerdeOutput::new(<$hasher as digest::Digest>::digest(bytes))
+            }
+
+            fn hash_chunks<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> SerdeOutput<$hasher> {
+                let mut hasher = <$hasher as digest::Digest>::new();
+                chunks
+                    .into_iter()
+                    .for_each(|c| digest::Digest::update(&mut hasher, c));
+                SerdeOutput::new(digest::Digest::finalize(hasher))
+            }
+
+            fn merge(v0: &SerdeOutput<$hasher>, v1: &SerdeOutput<$hasher>) -> SerdeOutput<$hasher> {
+                let mut hasher = <$hasher as digest::Digest>::new();
+                digest::Digest::update(&mut hasher, **v0);
+                digest::Digest::update(&mut hasher, **v1);
+                SerdeOutput::new(digest::Digest::finalize(hasher))
+            }
+
+            fn merge_many(values: &[SerdeOutput<$hasher>]) -> SerdeOutput<$hasher> {
+                let mut hasher = <$hasher as digest::Digest>::new();
+                for v in values {
+                    digest::Digest::update(&mut hasher, **v);
+                }
+                SerdeOutput::new(digest::Digest::finalize(hasher))
+            }
+
+            fn merge_with_int(seed: &SerdeOutput<$hasher>, value: u64) -> SerdeOutput<$hasher> {
+                let mut hasher = <$hasher as digest::Digest>::new();
+                digest::Digest::update(&mut hasher, **seed);
+                digest::Digest::update(&mut hasher, value.to_be_bytes());
+                SerdeOutput::new(digest::Digest::finalize(hasher))
+            }
+        }
+
+        impl<F: Field> ElementHashFn<F> for $name {
+            fn hash_elements(elements: impl IntoIterator<Item = F>) -> Self::Digest {
+                let mut byte_buffer = Vec::new();
+                for element in elements {
+                    element.serialize_uncompressed(&mut byte_buffer).unwrap();
+                }
+                Self::hash_chunks([&*byte_buffer])
+            }
+        }
+    };
+}
+
+/// SHA-256 hash function
 pub struct Sha256HashFn;
 
-impl HashFn for Sha256HashFn {
-    type Digest = SerdeOutput<Sha256>;
+impl_digest_hash_fn!(Sha256HashFn, Sha256, "sha256", 128);
 
-    const COLLISION_RESISTANCE: u32 = 128;
+/// BLAKE3 hash function with a 256 bit output
+pub struct Blake3HashFn;
 
-    fn hash(bytes: impl IntoIterator<Item = u8>) -> SerdeOutput<Sha256> {
-        let mut hasher = Sha256::new();
-        bytes.into_iter().for_each(|b| hasher.update([b]));
-        SerdeOutput::new(hasher.finalize())
-    }
+impl_digest_hash_fn!(Blake3HashFn, Blake3, "blake3", 128);
 
-    fn hash_chunks<'a>(slices: impl IntoIterator<Item = &'a [u8]>) -> SerdeOutput<Sha256> {
-        let mut hasher = Sha256::new();
-        slices.into_iter().for_each(|s| hasher.update(s));
-        SerdeOutput::new(hasher.finalize())
+/// Keccak-256 hash function (as used by Ethereum)
+///
+/// Note this uses the original Keccak padding rather than the padding
+/// standardised in SHA3-256.
+pub struct Keccak256HashFn;
+
+impl_digest_hash_fn!(Keccak256HashFn, Keccak256, "keccak256", 128);
+
+#[cfg(test)]
+mod tests {
+    use super::Blake3HashFn;
+    use super::HashFn;
+    use super::Keccak256HashFn;
+    use core::fmt::Write;
+
+    #[test]
+    fn keccak256_empty_input() {
+        // Ethereum's `keccak256("")`
+        let expected = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+
+        let digest = Keccak256HashFn::hash([0u8; 0]);
+
+        assert_eq!(expected, to_hex(&digest));
     }
 
-    fn merge(v0: &SerdeOutput<Sha256>, v1: &SerdeOutput<Sha256>) -> SerdeOutput<Sha256> {
-        let mut hasher = Sha256::new();
-        hasher.update(**v0);
-        hasher.update(**v1);
-        SerdeOutput::new(hasher.finalize())
+    #[test]
+    fn blake3_empty_input() {
+        let expected = "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262";
+
+        let digest = Blake3HashFn::hash([0u8; 0]);
+
+        assert_eq!(expected, to_hex(&digest));
     }
 
-    fn merge_with_int(seed: &SerdeOutput<Sha256>, value: u64) -> SerdeOutput<Sha256> {
-        let mut hasher = Sha256::new();
-        hasher.update(**seed);
-        hasher.update(value.to_be_bytes());
-        SerdeOutput::new(hasher.finalize())
+    #[test]
+    fn hash_matches_hash_chunks() {
+        let bytes = (0..=255).collect::<Vec<u8>>();
+        let (lhs, rhs) = bytes.split_at(100);
+
+        assert_eq!(
+            Blake3HashFn::hash(bytes.iter().copied()),
+            Blake3HashFn::hash_chunks([lhs, rhs])
+        );
+        assert_eq!(
+            Keccak256HashFn::hash(bytes.iter().copied()),
+            Keccak256HashFn::hash_chunks([lhs, rhs])
+        );
     }
-}
 
-impl<F: Field> ElementHashFn<F> for Sha256HashFn {
-    fn hash_elements(elements: impl IntoIterator<Item = F>) -> Self::Digest {
-        let mut byte_buffer = Vec::new();
-        for element in elements {
-            element.serialize_uncompressed(&mut byte_buffer).unwrap();
-        }
-        Self::hash_chunks([&*byte_buffer])
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().fold(String::new(), |mut hex, b| {
+            write!(hex, "{b:02x}").unwrap();
+            hex
+        })
     }
 }
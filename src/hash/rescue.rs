@@ -0,0 +1,790 @@
+//! Rescue-Prime over the 64-bit Goldilocks field `p = 2^64 - 2^32 + 1`.
+//!
+//! Unlike byte oriented hash functions Rescue-Prime is cheap to verify in an
+//! AIR. Digests are four field elements and merging two digests is a single
+//! permutation. The instantiation has a state width of 12 (capacity 4, rate 8),
+//! `α = 7` and 7 rounds which targets 128 bits of security. The MDS matrix and
+//! round constants are generated as described in the Rescue-Prime
+//! specification <https://eprint.iacr.org/2020/1143>.
+#![allow(clippy::unreadable_literal)]
+
+use super::Digest;
+use super::ElementHashFn;
+use super::HashFn;
+use alloc::vec::Vec;
+use ark_ff::Field;
+use ark_ff::PrimeField;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use core::iter::zip;
+use core::ops::Range;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+pub const STATE_WIDTH: usize = 12;
+pub const CAPACITY: usize = 4;
+pub const RATE: usize = STATE_WIDTH - CAPACITY;
+pub const DIGEST_SIZE: usize = 4;
+pub const NUM_ROUNDS: usize = 7;
+pub const ALPHA: u64 = 7;
+/// Inverse of [`ALPHA`] modulo `p - 1`
+pub const ALPHA_INV: u64 = 10540996611094048183;
+/// Elements of the state that inputs are absorbed into
+pub const RATE_RANGE: Range<usize> = CAPACITY..STATE_WIDTH;
+/// Elements of the state that are output as the digest
+pub const DIGEST_RANGE: Range<usize> = CAPACITY..CAPACITY + DIGEST_SIZE;
+
+/// Number of bytes packed into each field element when hashing bytes
+const BYTES_PER_ELEMENT: usize = 7;
+
+/// Rescue-Prime digest of four field elements
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RescueDigest(pub [Fp; DIGEST_SIZE]);
+
+impl Digest for RescueDigest {
+    fn as_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        for (chunk, element) in zip(bytes.chunks_mut(8), self.0) {
+            chunk.copy_from_slice(&element.into_bigint().0[0].to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Rescue-Prime sponge over the Goldilocks field.
+///
+/// The first capacity element is initialised with the number of inputs and
+/// the second with `0` when hashing field elements or `1` when hashing bytes.
+/// Inputs are added to the rate in chunks of eight with a permutation after
+/// each chunk. Hashing at most eight field elements, and therefore merging two
+/// digests, is a single permutation.
+pub struct RescueHashFn;
+
+impl RescueHashFn {
+    fn sponge(mut state: [Fp; STATE_WIDTH], elements: &[Fp]) -> RescueDigest {
+        if elements.is_empty() {
+            permute(&mut state);
+        }
+        for chunk in elements.chunks(RATE) {
+            for (s, element) in zip(&mut state[RATE_RANGE], chunk) {
+                *s += element;
+            }
+            permute(&mut state);
+        }
+        RescueDigest(state[DIGEST_RANGE].try_into().unwrap())
+    }
+}
+
+impl HashFn for RescueHashFn {
+    type Digest = RescueDigest;
+
+    const COLLISION_RESISTANCE: u32 = 128;
+    const ID: &'static str = "rescue-prime-goldilocks";
+
+    fn hash(bytes: impl IntoIterator<Item = u8>) -> RescueDigest {
+        let bytes = bytes.into_iter().collect::<Vec<u8>>();
+        let elements = bytes
+            .chunks(BYTES_PER_ELEMENT)
+            .map(|chunk| {
+                let mut buf = [0; 8];
+                buf[..chunk.len()].copy_from_slice(chunk);
+                Fp::from(u64::from_le_bytes(buf))
+            })
+            .collect::<Vec<Fp>>();
+        let mut state = [Fp::zero(); STATE_WIDTH];
+        state[0] = Fp::from(bytes.len() as u64);
+        state[1] = Fp::from(1u8);
+        Self::sponge(state, &elements)
+    }
+
+    fn hash_chunks<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> RescueDigest {
+        Self::hash(chunks.into_iter().flatten().copied())
+    }
+
+    fn merge(v0: &RescueDigest, v1: &RescueDigest) -> RescueDigest {
+        Self::hash_elements(v0.0.into_iter().chain(v1.0))
+    }
+
+    fn merge_many(values: &[RescueDigest]) -> RescueDigest {
+        Self::hash_elements(values.iter().flat_map(|v| v.0))
+    }
+
+    fn merge_with_int(seed: &RescueDigest, value: u64) -> RescueDigest {
+        // split the integer into 32 bit limbs since it may exceed the modulus
+        let lo = Fp::from(value & 0xFFFF_FFFF);
+        let hi = Fp::from(value >> 32);
+        Self::hash_elements(seed.0.into_iter().chain([lo, hi]))
+    }
+}
+
+impl ElementHashFn<Fp> for RescueHashFn {
+    fn hash_elements(elements: impl IntoIterator<Item = Fp>) -> RescueDigest {
+        let elements = elements.into_iter().collect::<Vec<Fp>>();
+        let mut state = [Fp::zero(); STATE_WIDTH];
+        state[0] = Fp::from(elements.len() as u64);
+        Self::sponge(state, &elements)
+    }
+}
+
+/// Applies the Rescue-Prime permutation to `state`
+pub fn permute(state: &mut [Fp; STATE_WIDTH]) {
+    for round in 0..NUM_ROUNDS {
+        apply_round(state, round);
+    }
+}
+
+/// Applies a single round of the Rescue-Prime permutation to `state`
+pub fn apply_round(state: &mut [Fp; STATE_WIDTH], round: usize) {
+    apply_sbox(state, ALPHA);
+    apply_mds(state, &MDS);
+    add_constants(state, &ROUND_CONSTANTS[2 * round]);
+    apply_sbox(state, ALPHA_INV);
+    apply_mds(state, &MDS);
+    add_constants(state, &ROUND_CONSTANTS[2 * round + 1]);
+}
+
+/// Multiplies `state` by the matrix `mds` (either [`MDS`] or [`MDS_INV`])
+pub fn apply_mds(state: &mut [Fp; STATE_WIDTH], mds: &[[u64; STATE_WIDTH]; STATE_WIDTH]) {
+    let res = mds.map(|row| zip(row, &*state).map(|(m, s)| Fp::from(m) * s).sum::<Fp>());
+    *state = res;
+}
+
+fn apply_sbox(state: &mut [Fp; STATE_WIDTH], exponent: u64) {
+    for v in state {
+        *v = v.pow([exponent]);
+    }
+}
+
+fn add_constants(state: &mut [Fp; STATE_WIDTH], constants: &[u64; STATE_WIDTH]) {
+    for (v, &c) in zip(state, constants) {
+        *v += Fp::from(c);
+    }
+}
+
+/// Maximum distance separable matrix
+pub const MDS: [[u64; STATE_WIDTH]; 12] = [
+    [
+        2108866337646019936,
+        11223275256334781131,
+        2318414738826783588,
+        11240468238955543594,
+        8007389560317667115,
+        11080831380224887131,
+        3922954383102346493,
+        17194066286743901609,
+        152620255842323114,
+        7203302445933022224,
+        17781531460838764471,
+        2306881200,
+    ],
+    [
+        3368836954250922620,
+        5531382716338105518,
+        7747104620279034727,
+        14164487169476525880,
+        4653455932372793639,
+        5504123103633670518,
+        3376629427948045767,
+        1687083899297674997,
+        8324288417826065247,
+        17651364087632826504,
+        15568475755679636039,
+        4656488262337620150,
+    ],
+    [
+        2560535215714666606,
+        10793518538122219186,
+        408467828146985886,
+        13894393744319723897,
+        17856013635663093677,
+        14510101432365346218,
+        12175743201430386993,
+        12012700097100374591,
+        976880602086740182,
+        3187015135043748111,
+        4630899319883688283,
+        17674195666610532297,
+    ],
+    [
+        10940635879119829731,
+        9126204055164541072,
+        13441880452578323624,
+        13828699194559433302,
+        6245685172712904082,
+        3117562785727957263,
+        17389107632996288753,
+        3643151412418457029,
+        10484080975961167028,
+        4066673631745731889,
+        8847974898748751041,
+        9548808324754121113,
+    ],
+    [
+        15656099696515372126,
+        309741777966979967,
+        16075523529922094036,
+        5384192144218250710,
+        15171244241641106028,
+        6660319859038124593,
+        6595450094003204814,
+        15330207556174961057,
+        2687301105226976975,
+        15907414358067140389,
+        2767130804164179683,
+        8135839249549115549,
+    ],
+    [
+        14687393836444508153,
+        8122848807512458890,
+        16998154830503301252,
+        2904046703764323264,
+        11170142989407566484,
+        5448553946207765015,
+        9766047029091333225,
+        3852354853341479440,
+        14577128274897891003,
+        11994931371916133447,
+        8299269445020599466,
+        2859592328380146288,
+    ],
+    [
+        4920761474064525703,
+        13379538658122003618,
+        3169184545474588182,
+        15753261541491539618,
+        622292315133191494,
+        14052907820095169428,
+        5159844729950547044,
+        17439978194716087321,
+        9945483003842285313,
+        13647273880020281344,
+        14750994260825376,
+        12575187259316461486,
+    ],
+    [
+        3371852905554824605,
+        8886257005679683950,
+        15677115160380392279,
+        13242906482047961505,
+        12149996307978507817,
+        1427861135554592284,
+        4033726302273030373,
+        14761176804905342155,
+        11465247508084706095,
+        12112647677590318112,
+        17343938135425110721,
+        14654483060427620352,
+    ],
+    [
+        5421794552262605237,
+        14201164512563303484,
+        5290621264363227639,
+        1020180205893205576,
+        14311345105258400438,
+        7828111500457301560,
+        9436759291445548340,
+        5716067521736967068,
+        15357555109169671716,
+        4131452666376493252,
+        16785275933585465720,
+        11180136753375315897,
+    ],
+    [
+        10451661389735482801,
+        12128852772276583847,
+        10630876800354432923,
+        6884824371838330777,
+        16413552665026570512,
+        13637837753341196082,
+        2558124068257217718,
+        4327919242598628564,
+        4236040195908057312,
+        2081029262044280559,
+        2047510589162918469,
+        6835491236529222042,
+    ],
+    [
+        5675273097893923172,
+        8120839782755215647,
+        9856415804450870143,
+        1960632704307471239,
+        15279057263127523057,
+        17999325337309257121,
+        72970456904683065,
+        8899624805082057509,
+        16980481565524365258,
+        6412696708929498357,
+        13917768671775544479,
+        5505378218427096880,
+    ],
+    [
+        10318314766641004576,
+        17320192463105632563,
+        11540812969169097044,
+        7270556942018024148,
+        4755326086930560682,
+        2193604418377108959,
+        11681945506511803967,
+        8000243866012209465,
+        6746478642521594042,
+        12096331252283646217,
+        13208137848575217268,
+        5548519654341606996,
+    ],
+];
+/// Inverse of [`MDS`]
+pub const MDS_INV: [[u64; STATE_WIDTH]; 12] = [
+    [
+        1025714968950054217,
+        2820417286206414279,
+        4993698564949207576,
+        12970218763715480197,
+        15096702659601816313,
+        5737881372597660297,
+        13327263231927089804,
+        4564252978131632277,
+        16119054824480892382,
+        6613927186172915989,
+        6454498710731601655,
+        2510089799608156620,
+    ],
+    [
+        14311337779007263575,
+        10306799626523962951,
+        7776331823117795156,
+        4922212921326569206,
+        8669179866856828412,
+        936244772485171410,
+        4077406078785759791,
+        2938383611938168107,
+        16650590241171797614,
+        16578411244849432284,
+        17600191004694808340,
+        5913375445729949081,
+    ],
+    [
+        13640353831792923980,
+        1583879644687006251,
+        17678309436940389401,
+        6793918274289159258,
+        3594897835134355282,
+        2158539885379341689,
+        12473871986506720374,
+        14874332242561185932,
+        16402478875851979683,
+        9893468322166516227,
+        8142413325661539529,
+        3444000755516388321,
+    ],
+    [
+        14009777257506018221,
+        18218829733847178457,
+        11151899210182873569,
+        14653120475631972171,
+        9591156713922565586,
+        16622517275046324812,
+        3958136700677573712,
+        2193274161734965529,
+        15125079516929063010,
+        3648852869044193741,
+        4405494440143722315,
+        15549070131235639125,
+    ],
+    [
+        14324333194410783741,
+        12565645879378458115,
+        4028590290335558535,
+        17936155181893467294,
+        1833939650657097992,
+        14310984655970610026,
+        4701042357351086687,
+        1226379890265418475,
+        2550212856624409740,
+        5670703442709406167,
+        3281485106506301394,
+        9804247840970323440,
+    ],
+    [
+        7778523590474814059,
+        7154630063229321501,
+        17790326505487126055,
+        3160574440608126866,
+        7292349907185131376,
+        1916491575080831825,
+        11523142515674812675,
+        2162357063341827157,
+        6650415936886875699,
+        11522955632464608509,
+        16740856792338897018,
+        16987840393715133187,
+    ],
+    [
+        14499296811525152023,
+        118549270069446537,
+        3041471724857448013,
+        3827228106225598612,
+        2081369067662751050,
+        15406142490454329462,
+        8943531526276617760,
+        3545513411057560337,
+        11433277564645295966,
+        9558995950666358829,
+        7443251815414752292,
+        12335092608217610725,
+    ],
+    [
+        184304165023253232,
+        11596940249585433199,
+        18170668175083122019,
+        8318891703682569182,
+        4387895409295967519,
+        14599228871586336059,
+        2861651216488619239,
+        567601091253927304,
+        10135289435539766316,
+        14905738261734377063,
+        3345637344934149303,
+        3159874422865401171,
+    ],
+    [
+        1134458872778032479,
+        4102035717681749376,
+        14030271225872148070,
+        10312336662487337312,
+        12938229830489392977,
+        17758804398255988457,
+        15482323580054918356,
+        1010277923244261213,
+        12904552397519353856,
+        5073478003078459047,
+        11514678194579805863,
+        4419017610446058921,
+    ],
+    [
+        2916054498252226520,
+        9880379926449218161,
+        15314650755395914465,
+        8335514387550394159,
+        8955267746483690029,
+        16353914237438359160,
+        4173425891602463552,
+        14892581052359168234,
+        17561678290843148035,
+        7292975356887551984,
+        18039512759118984712,
+        5411253583520971237,
+    ],
+    [
+        9848042270158364544,
+        809689769037458603,
+        5884047526712050760,
+        12956871945669043745,
+        14265127496637532237,
+        6211568220597222123,
+        678544061771515015,
+        16295989318674734123,
+        11782767968925152203,
+        1359397660819991739,
+        16148400912425385689,
+        14440017265059055146,
+    ],
+    [
+        1634272668217219807,
+        16290589064070324125,
+        5311838222680798126,
+        15044064140936894715,
+        15775025788428030421,
+        12586374713559327349,
+        8118943473454062014,
+        13223746794660766349,
+        13059674280609257192,
+        16605443174349648289,
+        13586971219878687822,
+        16337009014471658360,
+    ],
+];
+/// Round constants. Round `i` adds `ROUND_CONSTANTS[2 * i]` after the `x^α`
+/// S-box and `ROUND_CONSTANTS[2 * i + 1]` after the `x^(1/α)` S-box.
+pub const ROUND_CONSTANTS: [[u64; STATE_WIDTH]; 2 * NUM_ROUNDS] = [
+    [
+        16089809142501829443,
+        3960375389654894755,
+        2341987601489900096,
+        16513505200733590422,
+        2491992808872511534,
+        2243959319871113313,
+        1072250566756987431,
+        9576211715023554739,
+        13816740116943445245,
+        1013981081016507493,
+        6469202228346393176,
+        651486455260752235,
+    ],
+    [
+        10659391161334081468,
+        6658732499907968660,
+        13472970356821082105,
+        11254129182906430457,
+        2200184099877207561,
+        9367536782889046900,
+        5776283441396365529,
+        15880305242785227614,
+        15064577366950298089,
+        17182365414675952436,
+        221227465681839092,
+        10904420836212840752,
+    ],
+    [
+        6770068611756627448,
+        9429015895190610092,
+        6345154718738704426,
+        1348264131729825254,
+        11257253180296854021,
+        10209505772531486556,
+        13936278878169192368,
+        465229985152496221,
+        16122840733837976660,
+        15126432412337961371,
+        18195743520412640434,
+        4482481892207055145,
+    ],
+    [
+        9371429429698492981,
+        15659859461375396037,
+        3395558493871255061,
+        660144660555450404,
+        5074125520981119417,
+        17453702653133595770,
+        11221110160893954851,
+        6495862879055376432,
+        17061625752140729123,
+        12368428993775985339,
+        8908366829754037876,
+        2078111330029178445,
+    ],
+    [
+        4392703580426358869,
+        1665895348145983,
+        4219736658995217386,
+        1227613135081507795,
+        8190773212267744239,
+        8282001820492621236,
+        15836395107332526493,
+        5607076305580595108,
+        8785440730814333716,
+        15628355668353690236,
+        15635676168256493691,
+        8231009457495604357,
+    ],
+    [
+        13168535446547922823,
+        18239226123757899503,
+        7641189915286036988,
+        7820691679952216969,
+        1111836394951152974,
+        139835781513562161,
+        7076109422888404220,
+        5005587840202053100,
+        6487413309175970078,
+        5695661949695470409,
+        18151333218502551049,
+        12789465505850716019,
+    ],
+    [
+        3242413417035426569,
+        10974415453760425628,
+        18279530845486603448,
+        14045481066120861736,
+        12525452082923300704,
+        1905254592892409109,
+        9346668368089967636,
+        1735104742415647612,
+        3317525224474295113,
+        3946195652028520851,
+        444992070656934445,
+        3102693390775176900,
+    ],
+    [
+        17167036726114384788,
+        5848569342998419381,
+        14114543252495674018,
+        15114629034072612072,
+        5270549373288442547,
+        12129247407828856056,
+        18281855207204785420,
+        597402865817114738,
+        6042112508927673927,
+        112810046686999112,
+        2881728079621071110,
+        3443512534203368354,
+    ],
+    [
+        11524270175738513568,
+        16596131169768068084,
+        12046592239696686456,
+        10335258789985873044,
+        3804833210737803414,
+        4871342344579357943,
+        5506150606643613730,
+        1144769156473837296,
+        15770771149643607584,
+        22835664835299105,
+        15624512048862012204,
+        8438597895149015250,
+    ],
+    [
+        13297012143576436426,
+        7353183188832933627,
+        14475065819552011569,
+        1989958170371263671,
+        2759712450935595252,
+        5888211745553259072,
+        3366223208861836535,
+        10871170457430163614,
+        7436939156294010029,
+        10083282185253045512,
+        1727628517966770716,
+        15876537645083757620,
+    ],
+    [
+        2077569020629574154,
+        29247543278389127,
+        7513950682870485886,
+        14493142396838430095,
+        13137935083971782251,
+        17044896521696396448,
+        8358879158995995396,
+        6631372338926182917,
+        16141080336903561376,
+        12097878985033236818,
+        16582826484887094232,
+        11184522740344979309,
+    ],
+    [
+        14491184939776942308,
+        16755331289686337123,
+        4204064227783814013,
+        17375825663893345502,
+        16513382692712470059,
+        12671191098792302109,
+        7367953856881804491,
+        4828831248603618923,
+        605213678344474020,
+        10779667723419446880,
+        15588592678889744953,
+        16719715619459928934,
+    ],
+    [
+        11545814656420730331,
+        7520668505762229291,
+        5433441394427246897,
+        17588828388580402390,
+        8308794351872961990,
+        14007549481740032380,
+        15898890571959671932,
+        812931430828255689,
+        6818534534911166209,
+        12562621953249472036,
+        3817830678013523962,
+        16954219307307160453,
+    ],
+    [
+        7976559292405617294,
+        10624879739965265183,
+        11858994588137577101,
+        6953938202587799945,
+        15487983798101099477,
+        828942630404743552,
+        15918441202173246890,
+        10151280024237311966,
+        10562603357011259664,
+        18397974285238070711,
+        878544804620014725,
+        16579617335735550589,
+    ],
+];
+#[cfg(test)]
+mod tests {
+    use super::permute;
+    use super::ALPHA;
+    use super::ALPHA_INV;
+    use super::MDS;
+    use super::MDS_INV;
+    use super::NUM_ROUNDS;
+    use super::ROUND_CONSTANTS;
+    use super::STATE_WIDTH;
+    use ark_ff::Field;
+    use ark_ff::One;
+    use ark_ff::PrimeField;
+    use ark_ff::Zero;
+    use core::iter::zip;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+    use sha3::digest::ExtendableOutput;
+    use sha3::digest::Update;
+    use sha3::digest::XofReader;
+    use sha3::Shake256;
+
+    #[test]
+    fn alpha_inv_inverts_alpha() {
+        let x = Fp::from(123_456_789u64);
+
+        assert_eq!(x, x.pow([ALPHA]).pow([ALPHA_INV]));
+    }
+
+    #[test]
+    fn round_constants_match_specification() {
+        let modulus = Fp::MODULUS.0[0];
+        let seed = format!("Rescue-XLIX({modulus},{STATE_WIDTH},4,128)");
+        let mut shake = Shake256::default();
+        shake.update(seed.as_bytes());
+        let mut reader = shake.finalize_xof();
+
+        for constants in &ROUND_CONSTANTS {
+            for &constant in constants {
+                let mut bytes = [0; 9];
+                reader.read(&mut bytes);
+                let value = bytes
+                    .iter()
+                    .rev()
+                    .fold(0u128, |acc, &byte| (acc << 8) | u128::from(byte));
+                assert_eq!(u128::from(constant), value % u128::from(modulus));
+            }
+        }
+        assert_eq!(2 * NUM_ROUNDS, ROUND_CONSTANTS.len());
+    }
+
+    #[test]
+    fn mds_inv_inverts_mds() {
+        for (i, mds_row) in MDS.iter().enumerate() {
+            for j in 0..STATE_WIDTH {
+                let v = zip(mds_row, &MDS_INV)
+                    .map(|(&m, inv_row)| Fp::from(m) * Fp::from(inv_row[j]))
+                    .sum::<Fp>();
+                assert_eq!(if i == j { Fp::one() } else { Fp::zero() }, v);
+            }
+        }
+    }
+
+    #[test]
+    fn permutation_matches_reference() {
+        let mut state = core::array::from_fn(|i| Fp::from(i as u64));
+
+        permute(&mut state);
+
+        let expected = [
+            8830151182902853374u64,
+            13187004865337989000,
+            15101162352278848053,
+            14435613191222483279,
+            1292156311573281399,
+            8764222276562584657,
+            3431628250940046033,
+            3850389327580572944,
+            14991535190515157846,
+            5109801939400497066,
+            15005662634163591475,
+            2058051616562672581,
+        ];
+        assert_eq!(expected.map(Fp::from), state);
+    }
+}
@@ -0,0 +1,179 @@
+#![cfg(feature = "serde")]
+//! Serde helpers that encode arkworks types as hex strings.
+//!
+//! Field elements and digests are encoded as `0x` prefixed lowercase hex of
+//! their compressed [`CanonicalSerialize`] bytes. Note arkworks serializes
+//! field elements in little-endian byte order.
+//!
+//! With the `serde` feature enabled [`Proof`], [`FriProof`], [`Queries`],
+//! [`MerkleView`] and [`VerifierChannelArtifacts`] serialize to objects with
+//! the same field names as their Rust structs. Merkle caps, challenges and
+//! hints serialize to arrays of hex strings.
+//!
+//! Used with `#[serde(with = "crate::hex")]` on single values and
+//! `#[serde(with = "crate::hex::vec")]` on vectors.
+//!
+//! [`Proof`]: crate::Proof
+//! [`FriProof`]: crate::fri::FriProof
+//! [`Queries`]: crate::trace::Queries
+//! [`MerkleView`]: crate::merkle::MerkleView
+//! [`VerifierChannelArtifacts`]: crate::channel::VerifierChannelArtifacts
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use core::fmt::Write;
+use serde::de::Error;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serializer;
+
+const PREFIX: &str = "0x";
+
+/// Returns the hex encoding of a value's compressed serialization
+pub fn encode<T: CanonicalSerialize>(value: &T) -> String {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes).unwrap();
+    bytes.iter().fold(String::from(PREFIX), |mut res, byte| {
+        write!(res, "{byte:02x}").unwrap();
+        res
+    })
+}
+
+/// Decodes a value from the hex encoding of its compressed serialization
+pub fn decode<T: CanonicalDeserialize>(hex: &str) -> Result<T, String> {
+    let hex = hex.strip_prefix(PREFIX).unwrap_or(hex);
+    if hex.len() % 2 != 0 {
+        return Err(format!("hex string has odd length {}", hex.len()));
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|err| format!("invalid hex string: {err}"))?;
+    T::deserialize_compressed(bytes.as_slice()).map_err(|err| format!("invalid value: {err}"))
+}
+
+pub fn serialize<T: CanonicalSerialize, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(value))
+}
+
+pub fn deserialize<'de, T: CanonicalDeserialize, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    decode(&hex).map_err(D::Error::custom)
+}
+
+/// Encodes vectors as arrays of hex strings
+pub mod vec {
+    use super::decode;
+    use super::encode;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use ark_serialize::CanonicalDeserialize;
+    use ark_serialize::CanonicalSerialize;
+    use serde::de::Error;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    pub fn serialize<T: CanonicalSerialize, S: Serializer>(
+        values: &[T],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(encode))
+    }
+
+    pub fn deserialize<'de, T: CanonicalDeserialize, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<T>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|hex| decode(hex).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::challenges::Challenges;
+    use crate::channel::VerifierChannelArtifacts;
+    use crate::hash::HashFn;
+    use crate::hash::Sha256HashFn;
+    use crate::hints::Hints;
+    use crate::merkle::MerkleCap;
+    use crate::merkle::MerkleView;
+    use crate::utils::SerdeOutput;
+    use crate::ProofOptions;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+    use sha2::Sha256;
+
+    type Digest = SerdeOutput<Sha256>;
+
+    fn digest(i: u8) -> Digest {
+        Sha256HashFn::hash([i])
+    }
+
+    #[test]
+    fn hex_encoding() {
+        let value: Fp = Fp::from(258u16);
+
+        assert_eq!("0x0201000000000000", super::encode(&value));
+        assert_eq!(value, super::decode::<Fp>("0x0201000000000000").unwrap());
+        assert!(super::decode::<Fp>("0x020").is_err());
+        assert!(super::decode::<Fp>("0xzz").is_err());
+    }
+
+    #[test]
+    fn proof_options_round_trip() {
+        let options = ProofOptions::SMALL_PROOF_128;
+
+        let json = serde_json::to_string(&options).unwrap();
+
+        assert_eq!(options, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn merkle_view_round_trip() {
+        let view = MerkleView::<Digest, Digest> {
+            nodes: vec![digest(0), digest(1)],
+            initial_leaves: vec![digest(2)],
+            sibling_leaves: vec![digest(3)],
+            height: 3,
+        };
+        let cap = MerkleCap(vec![digest(4), digest(5)]);
+
+        let view_json = serde_json::to_string(&view).unwrap();
+        let cap_json = serde_json::to_value(&cap).unwrap();
+
+        assert_eq!(view, serde_json::from_str(&view_json).unwrap());
+        assert_eq!(
+            serde_json::json!([super::encode(&digest(4)), super::encode(&digest(5))]),
+            cap_json
+        );
+        assert_eq!(cap, serde_json::from_value(cap_json).unwrap());
+    }
+
+    #[test]
+    fn verifier_channel_artifacts_round_trip() {
+        let artifacts = VerifierChannelArtifacts::<Fp> {
+            air_challenges: Challenges::new(vec![Fp::from(1u8), Fp::from(2u8)]),
+            air_hints: Hints::new(vec![(0, Fp::from(3u8))]),
+            fri_alphas: vec![Fp::from(4u8)],
+            query_positions: vec![5, 6],
+        };
+
+        let json = serde_json::to_string(&artifacts).unwrap();
+        let decoded: VerifierChannelArtifacts<Fp> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(&*artifacts.air_challenges, &*decoded.air_challenges);
+        assert_eq!(&*artifacts.air_hints, &*decoded.air_hints);
+        assert_eq!(artifacts.fri_alphas, decoded.fri_alphas);
+        assert_eq!(artifacts.query_positions, decoded.query_positions);
+    }
+}
@@ -7,7 +7,9 @@ use core::ops::Deref;
 use core::ops::Index;
 
 #[derive(Default, Debug, Clone, CanonicalDeserialize, CanonicalSerialize)]
-pub struct Hints<F: Field>(Vec<F>);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent, bound = ""))]
+pub struct Hints<F: Field>(#[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))] Vec<F>);
 
 impl<F: Field> Hints<F> {
     pub fn new(mut hints: Vec<(usize, F)>) -> Self {
@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(clippy::all, clippy::pedantic, clippy::cargo, clippy::nursery)]
 #![allow(
     incomplete_features,
@@ -33,22 +34,34 @@
 #[macro_use]
 pub mod macros;
 pub mod air;
+#[cfg(feature = "brainfuck")]
+pub mod brainfuck;
 pub mod challenges;
 pub mod channel;
 pub mod composer;
 pub mod constraints;
+#[cfg(feature = "prover")]
 pub mod debug;
+pub mod envelope;
+#[cfg(feature = "prover")]
 pub mod eval_cpu;
 pub mod eval_gpu;
 pub mod expression;
 pub mod fri;
+pub mod gadgets;
 pub mod hash;
+pub mod hex;
 pub mod hints;
 pub mod matrix;
 pub mod merkle;
+pub mod optimizer;
 pub mod proof;
+#[cfg(feature = "prover")]
 pub mod prover;
 pub mod random;
+pub mod recursion;
+pub mod security;
+pub mod solidity;
 pub mod stark;
 pub mod trace;
 pub mod utils;
@@ -58,8 +71,12 @@ pub mod verifier;
 extern crate alloc;
 pub use air::Air;
 use alloc::vec::Vec;
+use ark_ff::CubicExtConfig;
+use ark_ff::CubicExtField;
 use ark_ff::FftField;
 use ark_ff::Field;
+use ark_ff::Fp;
+use ark_ff::FpConfig;
 use ark_poly::domain::DomainCoeff;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
@@ -71,6 +88,7 @@ use core::ops::Sub;
 use core::ops::SubAssign;
 use fri::FriOptions;
 pub use matrix::Matrix;
+use ministark_gpu::fields::p18446744069414584321::ark::Fq3;
 use ministark_gpu::GpuAdd;
 use ministark_gpu::GpuFftField;
 use ministark_gpu::GpuField;
@@ -84,12 +102,15 @@ pub use trace::Trace;
 // - extension field
 // - hashing function
 #[derive(Debug, Clone, Copy, CanonicalSerialize, CanonicalDeserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProofOptions {
     pub num_queries: u8,
     pub lde_blowup_factor: u8,
     pub grinding_factor: u8,
     pub fri_folding_factor: u8,
     pub fri_max_remainder_coeffs: u8,
+    /// Height of the merkle caps that are committed to instead of merkle roots
+    pub merkle_cap_height: u8,
 }
 
 impl ProofOptions {
@@ -98,6 +119,23 @@ impl ProofOptions {
     pub const MIN_BLOWUP_FACTOR: u8 = 1;
     pub const MAX_BLOWUP_FACTOR: u8 = 128;
     pub const MAX_GRINDING_FACTOR: u8 = 50;
+    pub const MAX_MERKLE_CAP_HEIGHT: u8 = 16;
+
+    // NOTE: presets assume the (extension) field is large enough to not limit
+    // the security level e.g. a cubic extension of a 64 bit field. The lde
+    // blowup factor must be at least the AIR's `ce_blowup_factor` so the fast
+    // prover presets (blowup factor 4) can't be used with AIRs whose
+    // constraints have a degree above 4. `Air::try_new` returns an error for
+    // options that can't be used with an AIR.
+
+    /// 96 bit conjectured security optimised for prover time
+    pub const FAST_PROVER_96: Self = Self::new(38, 4, 20, 8, 64);
+    /// 96 bit conjectured security optimised for proof size
+    pub const SMALL_PROOF_96: Self = Self::new(19, 16, 20, 16, 16);
+    /// 128 bit conjectured security optimised for prover time
+    pub const FAST_PROVER_128: Self = Self::new(54, 4, 20, 8, 64);
+    /// 128 bit conjectured security optimised for proof size
+    pub const SMALL_PROOF_128: Self = Self::new(27, 16, 20, 16, 16);
 
     pub const fn new(
         num_queries: u8,
@@ -118,9 +156,20 @@ impl ProofOptions {
             grinding_factor,
             fri_folding_factor,
             fri_max_remainder_coeffs,
+            merkle_cap_height: 0,
         }
     }
 
+    /// Commit to merkle caps of height `merkle_cap_height` rather than merkle
+    /// roots. Each merkle path in the proof is `merkle_cap_height` levels
+    /// shorter at the cost of committing to every node in the cap (i.e.
+    /// `2^merkle_cap_height` digests for a binary merkle tree).
+    pub const fn with_merkle_cap_height(mut self, merkle_cap_height: u8) -> Self {
+        assert!(merkle_cap_height <= Self::MAX_MERKLE_CAP_HEIGHT);
+        self.merkle_cap_height = merkle_cap_height;
+        self
+    }
+
     pub fn into_fri_options(self) -> FriOptions {
         // TODO: move fri params into struct
         FriOptions::new(
@@ -128,6 +177,7 @@ impl ProofOptions {
             self.fri_folding_factor.into(),
             self.fri_max_remainder_coeffs.into(),
         )
+        .with_merkle_cap_height(self.merkle_cap_height.into())
     }
 }
 
@@ -178,3 +228,20 @@ where
         + From<F>,
 {
 }
+
+/// Marker for field elements that are plain old data. Allows elements to be
+/// stored as raw bytes e.g. in memory-mapped files (see `MmapMatrix`).
+///
+/// # Safety
+/// All zero bytes must be a valid value and the type must not contain
+/// pointers or references.
+pub unsafe trait Pod: Copy + 'static {}
+
+// SAFETY: prime field elements are arrays of limbs
+unsafe impl<P: FpConfig<N>, const N: usize> Pod for Fp<P, N> {}
+
+// SAFETY: cubic extension field elements are three base field elements
+unsafe impl<P: CubicExtConfig> Pod for CubicExtField<P> where P::BaseField: Pod {}
+
+// SAFETY: only wraps a cubic extension field element
+unsafe impl Pod for Fq3 {}
@@ -1,3 +1,6 @@
+#[cfg(feature = "mmap")]
+pub mod mmap;
+
 use crate::constraints::ExecutionTraceColumn;
 use crate::hash::ElementHashFn;
 use crate::utils::horner_evaluate;
@@ -19,10 +22,13 @@ use core::ops::Index;
 use core::ops::IndexMut;
 use ministark_gpu::prelude::*;
 use ministark_gpu::utils::bit_reverse;
+#[cfg(feature = "mmap")]
+pub use mmap::MmapMatrix;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-/// Matrix is an array of columns.
+/// Matrix is an array of columns. See `MmapMatrix` (`mmap` feature) for
+/// matrices stored on disk.
 pub struct Matrix<F>(pub Vec<GpuVec<F>>);
 
 impl<F: Field> Matrix<F> {
@@ -82,6 +88,17 @@ impl<F: Field> Matrix<F> {
         }
     }
 
+    /// Splits the matrix into matrices with `num_cols[0]`, `num_cols[1]`, ...
+    /// many columns. Inverse of [`Matrix::join`].
+    pub fn split(self, num_cols: &[usize]) -> Vec<Self> {
+        assert_eq!(self.num_cols(), num_cols.iter().sum::<usize>());
+        let mut cols = self.0.into_iter();
+        num_cols
+            .iter()
+            .map(|&n| Self::new(cols.by_ref().take(n).collect()))
+            .collect()
+    }
+
     pub fn join(mut matrices: Vec<Self>) -> Self {
         let mut accumulator = Vec::new();
         for matrix in &mut matrices {
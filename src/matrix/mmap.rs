@@ -0,0 +1,347 @@
+//! Disk-backed matrices.
+//!
+//! Columns are stored in memory-mapped files so matrices larger than memory
+//! (e.g. low degree extensions of large traces) can be produced and committed
+//! to. The operating system pages columns in and out of memory as they're used.
+//!
+//! Field elements are stored in their in-memory representation so they must be
+//! [`Pod`]. The files are temporary scratch space. They're unlinked as soon as
+//! they're created so they never show up in the directory and their space is
+//! freed once the matrix is dropped.
+
+use crate::hash::ElementHashFn;
+use crate::utils::bit_reversed_chunk_domain;
+use crate::utils::GpuAllocator;
+use crate::Matrix;
+use crate::Pod;
+use alloc::vec::Vec;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_poly::domain::DomainCoeff;
+use ark_poly::domain::Radix2EvaluationDomain;
+use ark_poly::EvaluationDomain;
+use core::iter::zip;
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use memmap2::MmapMut;
+use ministark_gpu::utils::bit_reverse;
+use ministark_gpu::GpuField;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::io;
+use std::path::Path;
+
+/// Column of field elements stored in a memory-mapped file
+pub struct MmapColumn<F> {
+    mmap: MmapMut,
+    len: usize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: Pod> MmapColumn<F> {
+    /// Creates a column of `len` zeros stored in a temporary file in `dir`
+    ///
+    /// # Panics
+    /// Panics if `len` is zero since empty files can't be memory-mapped
+    pub fn zeroed(len: usize, dir: &Path) -> io::Result<Self> {
+        assert_ne!(len, 0, "column must have at least one value");
+        let file = tempfile::tempfile_in(dir)?;
+        file.set_len((len * size_of::<F>()) as u64)?;
+        // SAFETY: the file is unlinked so no other process can modify it
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        Ok(Self {
+            mmap,
+            len,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<F: Pod> Deref for MmapColumn<F> {
+    type Target = [F];
+
+    fn deref(&self) -> &[F] {
+        // SAFETY: the mapping is page aligned and large enough for `len` values.
+        // Its bytes are either zero (a valid value of any `Pod` type) or were
+        // written as values through `deref_mut`.
+        unsafe { core::slice::from_raw_parts(self.mmap.as_ptr().cast(), self.len) }
+    }
+}
+
+impl<F: Pod> DerefMut for MmapColumn<F> {
+    fn deref_mut(&mut self) -> &mut [F] {
+        // SAFETY: see `deref`
+        unsafe { core::slice::from_raw_parts_mut(self.mmap.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+/// Matrix with columns stored in memory-mapped files. Disk-backed
+/// alternative to [`Matrix`]. Operations hold at most one column (or one
+/// chunk of a column) in memory per thread.
+pub struct MmapMatrix<F>(pub Vec<MmapColumn<F>>);
+
+impl<F: Field + Pod> MmapMatrix<F> {
+    /// Creates a matrix of zeros with columns stored in temporary files in
+    /// `dir`
+    pub fn zeroed(num_cols: usize, num_rows: usize, dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        (0..num_cols)
+            .map(|_| MmapColumn::zeroed(num_rows, dir))
+            .collect::<io::Result<_>>()
+            .map(Self)
+    }
+
+    /// Copies an in-memory matrix to temporary files in `dir`
+    pub fn from_matrix(matrix: &Matrix<F>, dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut res = Self::zeroed(matrix.num_cols(), matrix.num_rows(), dir)?;
+        for (column, values) in zip(&mut res.0, &matrix.0) {
+            column.copy_from_slice(values);
+        }
+        Ok(res)
+    }
+
+    /// Copies the matrix into memory
+    pub fn to_matrix(&self) -> Matrix<F> {
+        Matrix::new(
+            self.0
+                .iter()
+                .map(|column| column.to_vec_in(GpuAllocator))
+                .collect(),
+        )
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.0.first().map_or(0, |column| column.len())
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Interpolates the columns of the matrix over the domain. The result is
+    /// stored in temporary files in `dir`.
+    pub fn interpolate(
+        &self,
+        domain: Radix2EvaluationDomain<F::FftField>,
+        dir: impl AsRef<Path>,
+    ) -> io::Result<Self>
+    where
+        F: GpuField + DomainCoeff<F::FftField>,
+        F::FftField: FftField,
+    {
+        assert_eq!(domain.size(), self.num_rows());
+        let mut res = Self::zeroed(self.num_cols(), self.num_rows(), dir)?;
+        ark_std::cfg_iter_mut!(res.0)
+            .zip(ark_std::cfg_iter!(self.0))
+            .for_each(|(coeffs, evals)| {
+                let mut values = evals.to_vec();
+                domain.ifft_in_place(&mut values);
+                coeffs.copy_from_slice(&values);
+            });
+        Ok(res)
+    }
+
+    /// Evaluates the columns of the matrix over the domain in bit-reversed
+    /// order. The result is stored in temporary files in `dir`.
+    pub fn bit_reversed_evaluate(
+        &self,
+        domain: Radix2EvaluationDomain<F::FftField>,
+        dir: impl AsRef<Path>,
+    ) -> io::Result<Self>
+    where
+        F: GpuField + DomainCoeff<F::FftField>,
+        F::FftField: FftField,
+    {
+        bit_reversed_evaluations(&self.0, domain, dir.as_ref())
+    }
+
+    /// Evaluates the columns of an in-memory matrix over the domain in
+    /// bit-reversed order. The result is stored in temporary files in `dir`.
+    pub fn from_bit_reversed_evaluations(
+        matrix: &Matrix<F>,
+        domain: Radix2EvaluationDomain<F::FftField>,
+        dir: impl AsRef<Path>,
+    ) -> io::Result<Self>
+    where
+        F: GpuField + DomainCoeff<F::FftField>,
+        F::FftField: FftField,
+    {
+        bit_reversed_evaluations(&matrix.0, domain, dir.as_ref())
+    }
+
+    pub fn get_row(&self, row: usize) -> Option<Vec<F>> {
+        if row < self.num_rows() {
+            Some(self.0.iter().map(|column| column[row]).collect())
+        } else {
+            None
+        }
+    }
+
+    pub fn read_row(&self, row_idx: usize, row: &mut [F]) {
+        for (column, value) in self.0.iter().zip(row) {
+            *value = column[row_idx];
+        }
+    }
+
+    /// Copies the matrix into memory `chunk_size` rows at a time
+    pub fn row_chunks(&self, chunk_size: usize) -> impl Iterator<Item = Matrix<F>> + '_ {
+        let num_rows = self.num_rows();
+        (0..num_rows).step_by(chunk_size).map(move |start| {
+            let end = num_rows.min(start + chunk_size);
+            Matrix::new(
+                self.0
+                    .iter()
+                    .map(|column| column[start..end].to_vec_in(GpuAllocator))
+                    .collect(),
+            )
+        })
+    }
+
+    pub fn hash_rows<H: ElementHashFn<F>>(&self) -> Vec<H::Digest> {
+        let num_rows = self.num_rows();
+        let mut row_hashes = vec![H::Digest::default(); num_rows];
+
+        #[cfg(not(feature = "parallel"))]
+        let chunk_size = row_hashes.len();
+        #[cfg(feature = "parallel")]
+        let chunk_size = core::cmp::max(
+            row_hashes.len() / rayon::current_num_threads().next_power_of_two(),
+            128,
+        );
+
+        ark_std::cfg_chunks_mut!(row_hashes, chunk_size)
+            .enumerate()
+            .for_each(|(chunk_offset, chunk)| {
+                let offset = chunk_size * chunk_offset;
+                let mut row_buffer = vec![F::zero(); self.num_cols()];
+                for (i, row_hash) in chunk.iter_mut().enumerate() {
+                    self.read_row(offset + i, &mut row_buffer);
+                    *row_hash = H::hash_elements(row_buffer.iter().copied());
+                }
+            });
+
+        row_hashes
+    }
+}
+
+/// Evaluates polynomials over `domain` in bit-reversed order. Each column is
+/// evaluated one coset of the polynomial's domain at a time so only a single
+/// polynomial's worth of values is in memory per thread.
+fn bit_reversed_evaluations<F, C>(
+    columns: &[C],
+    domain: Radix2EvaluationDomain<F::FftField>,
+    dir: &Path,
+) -> io::Result<MmapMatrix<F>>
+where
+    F: Field + GpuField + DomainCoeff<F::FftField> + Pod,
+    F::FftField: FftField,
+    C: Deref<Target = [F]> + Sync,
+{
+    let num_coeffs = columns.first().map_or(0, |column| column.len());
+    assert!(num_coeffs.is_power_of_two());
+    assert_eq!(0, domain.size() % num_coeffs);
+    let num_chunks = domain.size() / num_coeffs;
+    let mut res = MmapMatrix::zeroed(columns.len(), domain.size(), dir)?;
+    ark_std::cfg_iter_mut!(res.0)
+        .zip(ark_std::cfg_iter!(columns))
+        .for_each(|(evals, coeffs)| {
+            for (i, chunk) in evals.chunks_mut(num_coeffs).enumerate() {
+                let chunk_domain = bit_reversed_chunk_domain(domain, num_chunks, i);
+                let mut values = coeffs.to_vec();
+                chunk_domain.fft_in_place(&mut values);
+                bit_reverse(&mut values);
+                chunk.copy_from_slice(&values);
+            }
+        });
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MmapMatrix;
+    use crate::hash::Sha256HashFn;
+    use crate::utils::GpuAllocator;
+    use crate::Matrix;
+    use ark_poly::domain::Radix2EvaluationDomain;
+    use ark_poly::EvaluationDomain;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    fn matrix(num_cols: usize, num_rows: usize) -> Matrix<Fp> {
+        Matrix::new(
+            (0..num_cols)
+                .map(|i| {
+                    (0..num_rows)
+                        .map(|j| Fp::from((i * num_rows + j) as u64))
+                        .collect::<Vec<_>>()
+                        .to_vec_in(GpuAllocator)
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn round_trips_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let expected = matrix(3, 16);
+
+        let actual = MmapMatrix::from_matrix(&expected, &dir).unwrap();
+
+        assert_eq!(expected.rows(), actual.to_matrix().rows());
+        assert_eq!(expected.get_row(5), actual.get_row(5));
+        assert_eq!(None, actual.get_row(16));
+    }
+
+    #[test]
+    fn matches_in_memory_matrix() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace_xs = Radix2EvaluationDomain::<Fp>::new(16).unwrap();
+        let lde_xs = Radix2EvaluationDomain::new_coset(128, Fp::from(7u8)).unwrap();
+        let trace = matrix(3, 16);
+        let mmap_trace = MmapMatrix::from_matrix(&trace, &dir).unwrap();
+
+        let polys = trace.interpolate(trace_xs);
+        let mmap_polys = mmap_trace.interpolate(trace_xs, &dir).unwrap();
+        let lde = polys.bit_reversed_evaluate(lde_xs);
+        let mmap_lde = mmap_polys.bit_reversed_evaluate(lde_xs, &dir).unwrap();
+
+        assert_eq!(polys.rows(), mmap_polys.to_matrix().rows());
+        assert_eq!(lde.rows(), mmap_lde.to_matrix().rows());
+        assert_eq!(
+            lde.hash_rows::<Sha256HashFn>(),
+            mmap_lde.hash_rows::<Sha256HashFn>()
+        );
+        assert_eq!(
+            lde.rows(),
+            MmapMatrix::from_bit_reversed_evaluations(&polys, lde_xs, &dir)
+                .unwrap()
+                .to_matrix()
+                .rows()
+        );
+    }
+
+    #[test]
+    fn columns_leave_no_files_in_dir() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mmap_matrix = MmapMatrix::from_matrix(&matrix(3, 16), &dir).unwrap();
+
+        assert_eq!(3, mmap_matrix.num_cols());
+        assert_eq!(0, std::fs::read_dir(&dir).unwrap().count());
+    }
+
+    #[test]
+    fn row_chunks_cover_all_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let expected = matrix(2, 32);
+        let mmap_matrix = MmapMatrix::from_matrix(&expected, &dir).unwrap();
+
+        let rows = mmap_matrix
+            .row_chunks(8)
+            .flat_map(|chunk| chunk.rows())
+            .collect::<Vec<_>>();
+
+        assert_eq!(expected.rows(), rows);
+    }
+}
@@ -1,18 +1,20 @@
 use crate::hash::Digest;
 use crate::hash::ElementHashFn;
 use crate::hash::HashFn;
+use crate::utils::SerdeBound;
 use crate::Matrix;
+use alloc::string::String;
 use alloc::vec::Vec;
 use ark_ff::Field;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use core::fmt::Debug;
+use core::iter::zip;
+use core::marker::PhantomData;
+use core::ops::Deref;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use snafu::Snafu;
-use std::collections::VecDeque;
-use std::fmt::Debug;
-use std::iter::zip;
-use std::marker::PhantomData;
 
 /// Merkle tree error
 #[derive(Debug, Snafu)]
@@ -25,31 +27,112 @@ pub enum Error {
     LeafIndexOutOfBounds { i: usize, n: usize },
     #[snafu(display("proof is invalid"))]
     InvalidProof,
+    #[snafu(display("merkle cap with `{n}` nodes does not match a level of the tree"))]
+    CapSizeMismatch { n: usize },
+    #[snafu(display("opened row `{i}` does not hash to its leaf in the proof"))]
+    LeafMismatch { i: usize },
+    #[snafu(display("merkle path of leaf `{i}` does not resolve to the merkle cap"))]
+    CapMismatch { i: usize },
+    #[snafu(display("merkle trees with arity `{arity}` aren't supported"))]
+    UnsupportedArity { arity: usize },
 }
 
 pub trait MerkleTree: Sized + Send + Sync + Clone {
-    type Proof: CanonicalSerialize + CanonicalDeserialize + Clone + Send + Sync;
+    type Proof: CanonicalSerialize + CanonicalDeserialize + Clone + Send + Sync + SerdeBound;
     type Root: Digest;
 
+    /// Number of children of each internal node
+    const ARITY: usize = 2;
+
     /// Returns the root of the merkle tree
-    fn root(&self) -> Self::Root;
+    fn root(&self) -> Self::Root {
+        self.cap(0).root().unwrap()
+    }
+
+    /// Returns the merkle cap at height `cap_height` i.e. the nodes
+    /// `cap_height` levels below the root. If the tree is too short the
+    /// cap is taken from the lowest level of internal nodes.
+    fn cap(&self, cap_height: u32) -> MerkleCap<Self::Root>;
+
+    /// Returns the number of nodes in the merkle cap at height `cap_height` of
+    /// a tree with `num_leaves` leaves
+    fn cap_size(num_leaves: usize, cap_height: u32) -> usize;
 
     /// Generates a merkle proof
     ///
     /// # Errors
     ///
     /// Returns an error if the leaf index is out of bounds.
-    fn prove(&self, indices: &[usize]) -> Result<Self::Proof, Error>;
+    fn prove(&self, indices: &[usize]) -> Result<Self::Proof, Error> {
+        self.prove_to_cap(indices, 0)
+    }
+
+    /// Generates a merkle proof with paths that terminate at the merkle cap
+    /// with height `cap_height`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the leaf index is out of bounds.
+    fn prove_to_cap(&self, indices: &[usize], cap_height: u32) -> Result<Self::Proof, Error>;
 
     /// Verifies a merkle proof
     ///
     /// # Errors
     ///
     /// This function returns an error if the proof fails verification.
-    fn verify(root: &Self::Root, proof: Self::Proof, indices: &[usize]) -> Result<(), Error>;
+    fn verify(root: &Self::Root, proof: Self::Proof, indices: &[usize]) -> Result<(), Error> {
+        Self::verify_to_cap(&MerkleCap::from_root(root.clone()), proof, indices)
+    }
+
+    /// Verifies a merkle proof against a merkle cap
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the proof fails verification.
+    fn verify_to_cap(
+        cap: &MerkleCap<Self::Root>,
+        proof: Self::Proof,
+        indices: &[usize],
+    ) -> Result<(), Error>;
 
     /// Returns the number of security bits
     fn security_level_bits() -> u32;
+
+    /// Returns a stable identifier of the hash function and arity
+    fn id() -> String;
+}
+
+/// The nodes `k` levels below the root of a merkle tree (the merkle cap).
+///
+/// Committing to a merkle cap rather than the root allows every merkle path
+/// to be `k` levels shorter. A cap of height `0` is just the merkle root.
+#[derive(Debug, Clone, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent, bound = ""))]
+pub struct MerkleCap<D: Digest>(
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))] pub Vec<D>,
+);
+
+impl<D: Digest> MerkleCap<D> {
+    pub fn from_root(root: D) -> Self {
+        Self(vec![root])
+    }
+
+    /// Returns the root if this is a cap of height `0`
+    pub fn root(&self) -> Option<D> {
+        match self.0.as_slice() {
+            [root] => Some(root.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<D: Digest> Deref for MerkleCap<D> {
+    type Target = [D];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
 }
 
 // TODO: all these merkle tree abstractions are way out of control. need to
@@ -58,11 +141,19 @@ pub trait MerkleTreeConfig: Send + Sync + Sized + 'static {
     type Digest: Digest;
     type Leaf: CanonicalDeserialize + CanonicalSerialize + Clone + Send + Sync + Sized + 'static;
 
-    fn hash_leaves(depth: u32, l0: &Self::Leaf, l1: &Self::Leaf) -> Self::Digest;
+    /// Number of children of each internal node. Must be a power of two.
+    /// If the number of leaves is not a power of `ARITY` the root node has
+    /// fewer than `ARITY` children.
+    const ARITY: usize = 2;
 
-    fn hash_nodes(depth: u32, n0: &Self::Digest, n1: &Self::Digest) -> Self::Digest;
+    fn hash_leaves(depth: u32, leaves: &[Self::Leaf]) -> Self::Digest;
+
+    fn hash_nodes(depth: u32, nodes: &[Self::Digest]) -> Self::Digest;
 
     fn security_level_bits() -> u32;
+
+    /// Returns a stable identifier of the hash functions and arity
+    fn id() -> String;
 }
 
 /// Merkle View contains information needed to verify multiple Merkle paths.
@@ -70,12 +161,17 @@ pub trait MerkleTreeConfig: Send + Sync + Sized + 'static {
 /// Inspired by Starkware's Solidity verifier
 /// <https://etherscan.io/address/0xe9664D230490d5A515ef7Ef30033d8075a8D0E24#code#F24#L1>
 #[derive(Debug, Clone, PartialEq, Eq, CanonicalDeserialize, CanonicalSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct MerkleView<
     N: CanonicalDeserialize + CanonicalSerialize + Clone,
     L: CanonicalDeserialize + CanonicalSerialize + Clone,
 > {
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub nodes: Vec<N>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub initial_leaves: Vec<L>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub sibling_leaves: Vec<L>,
     pub height: u32,
 }
@@ -89,6 +185,9 @@ pub struct MerkleView<
 ///  / \     / \
 /// +   +   +   +  <- leaves
 /// ```
+///
+/// Nodes are stored level by level. The `w` nodes of a level are stored at
+/// indices `w..2*w` so the root node is at index `1`.
 pub struct MerkleTreeImpl<C: MerkleTreeConfig> {
     pub nodes: Vec<C::Digest>,
     pub leaves: Vec<C::Leaf>,
@@ -112,6 +211,11 @@ impl<C: MerkleTreeConfig> MerkleTreeImpl<C> {
     pub fn new(leaves: Vec<C::Leaf>) -> Result<Self, Error> {
         const MIN_LEAVES: usize = 2;
 
+        assert!(
+            C::ARITY >= 2 && C::ARITY.is_power_of_two(),
+            "arity must be a power of two greater than one"
+        );
+
         let n = leaves.len();
         if n < MIN_LEAVES {
             return Err(Error::TooFewLeaves {
@@ -126,7 +230,7 @@ impl<C: MerkleTreeConfig> MerkleTreeImpl<C> {
         Ok(Self { nodes, leaves })
     }
 
-    /// Returns the height of the merkle tree
+    /// Returns the height of the merkle tree if it were a binary tree
     /// i.e. for the merkle tree below `height=1`
     /// ```text
     ///   +
@@ -136,17 +240,58 @@ impl<C: MerkleTreeConfig> MerkleTreeImpl<C> {
     fn height(&self) -> u32 {
         self.leaves.len().ilog2()
     }
+
+    /// Returns the number of levels below the root of a tree with
+    /// `2^height` leaves. The leaves are at level `num_levels`.
+    const fn num_levels(height: u32) -> u32 {
+        height.div_ceil(C::ARITY.ilog2())
+    }
+
+    /// Returns the number of nodes at `level` of a tree with `2^height`
+    /// leaves. The root is at level `0`.
+    const fn level_width(height: u32, level: u32) -> usize {
+        if level == 0 {
+            1
+        } else {
+            let levels_to_leaves = Self::num_levels(height) - level;
+            1 << (height - C::ARITY.ilog2() * levels_to_leaves)
+        }
+    }
+
+    /// Returns the level of the merkle cap that is used for `cap_height`.
+    /// The cap can't contain leaves so it's limited to the lowest level of
+    /// internal nodes.
+    fn cap_level(height: u32, cap_height: u32) -> u32 {
+        cap_height.min(Self::num_levels(height).saturating_sub(1))
+    }
 }
 
 impl<C: MerkleTreeConfig> MerkleTree for MerkleTreeImpl<C> {
     type Proof = MerkleView<C::Digest, C::Leaf>;
     type Root = C::Digest;
 
+    const ARITY: usize = C::ARITY;
+
     fn root(&self) -> C::Digest {
         self.nodes[1].clone()
     }
 
-    fn prove(&self, indices: &[usize]) -> Result<MerkleView<C::Digest, C::Leaf>, Error> {
+    fn cap(&self, cap_height: u32) -> MerkleCap<C::Digest> {
+        let cap_size = Self::cap_size(self.leaves.len(), cap_height);
+        MerkleCap(self.nodes[cap_size..2 * cap_size].to_vec())
+    }
+
+    fn cap_size(num_leaves: usize, cap_height: u32) -> usize {
+        // empty and single leaf trees have a cap of one node
+        let height = num_leaves.checked_ilog2().unwrap_or(0);
+        Self::level_width(height, Self::cap_level(height, cap_height))
+    }
+
+    fn prove_to_cap(
+        &self,
+        indices: &[usize],
+        cap_height: u32,
+    ) -> Result<MerkleView<C::Digest, C::Leaf>, Error> {
         let num_leaves = self.leaves.len();
         for &i in indices {
             if i >= num_leaves {
@@ -158,60 +303,47 @@ impl<C: MerkleTreeConfig> MerkleTree for MerkleTreeImpl<C> {
         indices.sort_unstable();
         indices.dedup();
 
-        let mut initial_leaves = Vec::new();
-        let mut sibling_leaves = Vec::new();
+        let height = self.height();
+        let num_levels = Self::num_levels(height);
 
-        // handle leaves and specify the internal node indices
-        let mut node_queue = VecDeque::new();
-        let mut leaf_queue = VecDeque::from_iter(indices);
-        while let Some(index) = leaf_queue.pop_front() {
-            initial_leaves.push(self.leaves[index].clone());
-            node_queue.push_back((num_leaves + index) >> 1);
-
-            if let Some(&next_index) = leaf_queue.front() {
-                let are_siblings = index ^ 1 == next_index;
-                if are_siblings {
-                    initial_leaves.push(self.leaves[next_index].clone());
-                    leaf_queue.pop_front();
-                    continue;
-                }
-            }
-
-            sibling_leaves.push(self.leaves[index ^ 1].clone());
-        }
+        // handle leaves
+        let initial_leaves = indices.iter().map(|&i| self.leaves[i].clone()).collect();
+        let mut sibling_leaves = Vec::new();
+        let leaf_arity = num_leaves / Self::level_width(height, num_levels - 1);
+        let mut positions = open_siblings(&indices, &self.leaves, leaf_arity, &mut sibling_leaves);
 
-        // handle internal nodes
+        // handle internal nodes up until the cap
         let mut nodes = Vec::new();
-        while let Some(index) = node_queue.pop_front() {
-            if index > 2 {
-                node_queue.push_back(index >> 1);
-            }
-
-            if let Some(next_index) = node_queue.front() {
-                let are_siblings = index ^ 1 == *next_index;
-                if are_siblings {
-                    node_queue.pop_front();
-                    continue;
-                }
-            }
-
-            nodes.push(self.nodes[index ^ 1].clone());
+        for level in (Self::cap_level(height, cap_height) + 1..num_levels).rev() {
+            let width = Self::level_width(height, level);
+            let arity = width / Self::level_width(height, level - 1);
+            let level_nodes = &self.nodes[width..2 * width];
+            positions = open_siblings(&positions, level_nodes, arity, &mut nodes);
         }
 
         Ok(MerkleView {
             nodes,
             initial_leaves,
             sibling_leaves,
-            height: self.height(),
+            height,
         })
     }
 
-    fn verify(
-        root: &C::Digest,
+    fn verify_to_cap(
+        cap: &MerkleCap<C::Digest>,
         proof: MerkleView<C::Digest, C::Leaf>,
         indices: &[usize],
     ) -> Result<(), Error> {
         let height = proof.height;
+        if height == 0 || height >= usize::BITS {
+            return Err(Error::InvalidProof);
+        }
+
+        let num_levels = Self::num_levels(height);
+        let cap_level = (0..num_levels)
+            .find(|&level| Self::level_width(height, level) == cap.len())
+            .ok_or(Error::CapSizeMismatch { n: cap.len() })?;
+
         let num_leaves = 1 << height;
         for &i in indices {
             if i >= num_leaves {
@@ -223,73 +355,105 @@ impl<C: MerkleTreeConfig> MerkleTree for MerkleTreeImpl<C> {
         indices.sort_unstable();
         indices.dedup();
 
-        // handle leaves and specify the internal node indices
-        let mut node_queue = VecDeque::new();
-        let mut siblings = VecDeque::from_iter(proof.sibling_leaves);
-        let mut leaf_queue = zip(indices, proof.initial_leaves).collect::<VecDeque<_>>();
-        while let Some((index, leaf)) = leaf_queue.pop_front() {
-            let node_index = (num_leaves + index) >> 1;
-
-            if let Some((next_index, next_leaf)) = leaf_queue.front() {
-                let are_siblings = index ^ 1 == *next_index;
-                if are_siblings {
-                    let running_hash = C::hash_leaves(height - 1, &leaf, next_leaf);
-                    node_queue.push_back((node_index, running_hash));
-                    leaf_queue.pop_front();
-                    continue;
-                }
-            }
-
-            let sibling = siblings.pop_front().unwrap();
-            let running_hash = if index % 2 == 0 {
-                C::hash_leaves(height - 1, &leaf, &sibling)
-            } else {
-                C::hash_leaves(height - 1, &sibling, &leaf)
-            };
-            node_queue.push_back((node_index, running_hash));
-        }
-        assert!(siblings.is_empty());
-
-        // handle internal nodes
-        let mut nodes = VecDeque::from_iter(proof.nodes);
-        while let Some((index, hash)) = node_queue.pop_front() {
-            let depth = index.ilog2();
-
-            if depth == 0 {
-                assert!(node_queue.is_empty());
-                // compare against the root
-                return if *root == hash {
-                    Ok(())
-                } else {
-                    Err(Error::InvalidProof)
-                };
-            }
+        if indices.len() != proof.initial_leaves.len() {
+            return Err(Error::InvalidProof);
+        }
 
-            if let Some((next_index, next_hash)) = node_queue.front() {
-                let are_siblings = index ^ 1 == *next_index;
-                if are_siblings {
-                    let running_hash = C::hash_nodes(depth - 1, &hash, next_hash);
-                    node_queue.push_back((index >> 1, running_hash));
-                    node_queue.pop_front();
-                    continue;
-                }
-            }
+        // handle leaves
+        let leaf_arity = num_leaves / Self::level_width(height, num_levels - 1);
+        let mut siblings = proof.sibling_leaves.into_iter();
+        let leaves = zip(indices.iter().copied(), proof.initial_leaves).collect();
+        let mut layer = hash_siblings(leaves, leaf_arity, &mut siblings, |leaves| {
+            C::hash_leaves(num_levels - 1, leaves)
+        })?;
+        if siblings.next().is_some() {
+            return Err(Error::InvalidProof);
+        }
 
-            let sibling = nodes.pop_front().unwrap();
-            let running_hash = if index % 2 == 0 {
-                C::hash_nodes(depth - 1, &hash, &sibling)
-            } else {
-                C::hash_nodes(depth - 1, &sibling, &hash)
-            };
-            node_queue.push_back((index >> 1, running_hash));
+        // handle internal nodes up until the cap
+        let mut siblings = proof.nodes.into_iter();
+        for level in (cap_level + 1..num_levels).rev() {
+            let width = Self::level_width(height, level);
+            let arity = width / Self::level_width(height, level - 1);
+            layer = hash_siblings(layer, arity, &mut siblings, |nodes| {
+                C::hash_nodes(level - 1, nodes)
+            })?;
+        }
+        if siblings.next().is_some() {
+            return Err(Error::InvalidProof);
         }
 
-        Ok(())
+        // compare against the cap
+        match layer.into_iter().find(|(i, node)| cap[*i] != *node) {
+            None => Ok(()),
+            Some((i, _)) => {
+                // report the first queried leaf under the mismatched cap node
+                let leaves_per_node = num_leaves / cap.len();
+                let leaf = indices.into_iter().find(|leaf| leaf / leaves_per_node == i);
+                Err(Error::CapMismatch {
+                    i: leaf.unwrap_or(i * leaves_per_node),
+                })
+            }
+        }
     }
 
     fn security_level_bits() -> u32 {
         C::security_level_bits()
     }
+
+    fn id() -> String {
+        C::id()
+    }
+}
+
+/// Collects the siblings of the nodes at `positions` (sorted and deduplicated)
+/// that aren't themselves in `positions`. Returns the positions of the parents.
+fn open_siblings<T: Clone>(
+    positions: &[usize],
+    level: &[T],
+    arity: usize,
+    siblings: &mut Vec<T>,
+) -> Vec<usize> {
+    positions
+        .chunk_by(|a, b| a / arity == b / arity)
+        .map(|group| {
+            let parent = group[0] / arity;
+            let start = parent * arity;
+            let mut group = group.iter().peekable();
+            for (i, node) in zip(start.., &level[start..start + arity]) {
+                if group.next_if_eq(&&i).is_none() {
+                    siblings.push(node.clone());
+                }
+            }
+            parent
+        })
+        .collect()
+}
+
+/// Hashes the `known` nodes (sorted by position) together with their missing
+/// siblings. Returns the parent nodes and their positions.
+fn hash_siblings<T, D>(
+    known: Vec<(usize, T)>,
+    arity: usize,
+    siblings: &mut impl Iterator<Item = T>,
+    hash: impl Fn(&[T]) -> D,
+) -> Result<Vec<(usize, D)>, Error> {
+    let mut parents = Vec::new();
+    let mut children = Vec::with_capacity(arity);
+    let mut known = known.into_iter().peekable();
+    while let Some(&(position, _)) = known.peek() {
+        let parent = position / arity;
+        children.clear();
+        for i in parent * arity..(parent + 1) * arity {
+            let child = match known.next_if(|(position, _)| *position == i) {
+                Some((_, child)) => child,
+                None => siblings.next().ok_or(Error::InvalidProof)?,
+            };
+            children.push(child);
+        }
+        parents.push((parent, hash(&children)));
+    }
+    Ok(parents)
 }
 
 /// Merkle tree that supports proving/verifying rows of a matrix
@@ -299,23 +463,54 @@ impl<C: MerkleTreeConfig> MerkleTree for MerkleTreeImpl<C> {
 pub trait MatrixMerkleTree<T>: MerkleTree + Sized {
     fn from_matrix(m: &Matrix<T>) -> Self;
 
+    /// Builds the tree of the matrix with the rows of `chunks` stacked on top
+    /// of each other. Implementations can hash the rows of each chunk and drop
+    /// it so the whole matrix never needs to be in memory. The default joins
+    /// the chunks and calls [`Self::from_matrix`].
+    fn from_row_chunks(chunks: impl IntoIterator<Item = Matrix<T>>) -> Self {
+        let mut chunks = chunks.into_iter();
+        let mut matrix = chunks.next().expect("matrix must have rows");
+        for chunk in chunks {
+            for (column, mut chunk_column) in zip(&mut matrix.0, chunk.0) {
+                column.append(&mut chunk_column);
+            }
+        }
+        Self::from_matrix(&matrix)
+    }
+
     fn prove_rows(&self, row_ids: &[usize]) -> Result<Self::Proof, Error> {
         self.prove(row_ids)
     }
 
+    fn prove_rows_to_cap(&self, row_ids: &[usize], cap_height: u32) -> Result<Self::Proof, Error> {
+        self.prove_to_cap(row_ids, cap_height)
+    }
+
     fn verify_rows(
         root: &Self::Root,
         row_ids: &[usize],
         rows: &[impl AsRef<[T]>],
         proof: Self::Proof,
+    ) -> Result<(), Error> {
+        let cap = MerkleCap::from_root(root.clone());
+        Self::verify_rows_to_cap(&cap, row_ids, rows, proof)
+    }
+
+    fn verify_rows_to_cap(
+        cap: &MerkleCap<Self::Root>,
+        row_ids: &[usize],
+        rows: &[impl AsRef<[T]>],
+        proof: Self::Proof,
     ) -> Result<(), Error>;
 }
 
-pub struct MatrixMerkleTreeImpl<H: HashFn> {
-    merkle_tree: MerkleTreeImpl<HashedLeafConfig<H>>,
+/// Merkle tree over the rows of a matrix. Each internal node has `ARITY`
+/// children.
+pub struct MatrixMerkleTreeImpl<H: HashFn, const ARITY: usize = 2> {
+    merkle_tree: MerkleTreeImpl<HashedLeafConfig<H, ARITY>>,
 }
 
-impl<H: HashFn> Clone for MatrixMerkleTreeImpl<H> {
+impl<H: HashFn, const ARITY: usize> Clone for MatrixMerkleTreeImpl<H, ARITY> {
     fn clone(&self) -> Self {
         Self {
             merkle_tree: self.merkle_tree.clone(),
@@ -323,7 +518,7 @@ impl<H: HashFn> Clone for MatrixMerkleTreeImpl<H> {
     }
 }
 
-impl<H: HashFn> MatrixMerkleTreeImpl<H> {
+impl<H: HashFn, const ARITY: usize> MatrixMerkleTreeImpl<H, ARITY> {
     fn new(leaves: Vec<H::Digest>) -> Result<Self, Error> {
         assert!(leaves.len().is_power_of_two());
         Ok(Self {
@@ -332,42 +527,68 @@ impl<H: HashFn> MatrixMerkleTreeImpl<H> {
     }
 }
 
-impl<H: HashFn> MerkleTree for MatrixMerkleTreeImpl<H> {
+impl<H: HashFn, const ARITY: usize> MerkleTree for MatrixMerkleTreeImpl<H, ARITY> {
     type Proof = MerkleView<H::Digest, H::Digest>;
     type Root = H::Digest;
 
+    const ARITY: usize = ARITY;
+
     fn root(&self) -> Self::Root {
         self.merkle_tree.root()
     }
 
-    fn prove(&self, indices: &[usize]) -> Result<Self::Proof, Error> {
-        self.merkle_tree.prove(indices)
+    fn cap(&self, cap_height: u32) -> MerkleCap<Self::Root> {
+        self.merkle_tree.cap(cap_height)
     }
 
-    fn verify(root: &Self::Root, proof: Self::Proof, indices: &[usize]) -> Result<(), Error> {
-        MerkleTreeImpl::<HashedLeafConfig<H>>::verify(root, proof, indices)
+    fn cap_size(num_leaves: usize, cap_height: u32) -> usize {
+        MerkleTreeImpl::<HashedLeafConfig<H, ARITY>>::cap_size(num_leaves, cap_height)
+    }
+
+    fn prove_to_cap(&self, indices: &[usize], cap_height: u32) -> Result<Self::Proof, Error> {
+        self.merkle_tree.prove_to_cap(indices, cap_height)
+    }
+
+    fn verify_to_cap(
+        cap: &MerkleCap<Self::Root>,
+        proof: Self::Proof,
+        indices: &[usize],
+    ) -> Result<(), Error> {
+        MerkleTreeImpl::<HashedLeafConfig<H, ARITY>>::verify_to_cap(cap, proof, indices)
     }
 
     fn security_level_bits() -> u32 {
         H::COLLISION_RESISTANCE
     }
+
+    fn id() -> String {
+        MerkleTreeImpl::<HashedLeafConfig<H, ARITY>>::id()
+    }
 }
 
-impl<F: Field, H: ElementHashFn<F> + Send + Sync + 'static> MatrixMerkleTree<F>
-    for MatrixMerkleTreeImpl<H>
+impl<F: Field, H: ElementHashFn<F> + Send + Sync + 'static, const ARITY: usize> MatrixMerkleTree<F>
+    for MatrixMerkleTreeImpl<H, ARITY>
 {
     fn from_matrix(m: &Matrix<F>) -> Self {
         Self::new(hash_rows::<F, H>(m)).unwrap()
     }
 
-    fn verify_rows(
-        root: &Self::Root,
+    fn from_row_chunks(chunks: impl IntoIterator<Item = Matrix<F>>) -> Self {
+        let leaves = chunks
+            .into_iter()
+            .flat_map(|chunk| hash_rows::<F, H>(&chunk))
+            .collect();
+        Self::new(leaves).unwrap()
+    }
+
+    fn verify_rows_to_cap(
+        cap: &MerkleCap<Self::Root>,
         row_ids: &[usize],
         rows: &[impl AsRef<[F]>],
         proof: Self::Proof,
     ) -> Result<(), Error> {
         // remove duplicates and sort
-        let mut instances = zip(row_ids, rows).collect::<Vec<_>>();
+        let mut instances = zip(row_ids.iter().copied(), rows).collect::<Vec<_>>();
         instances.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
         instances.dedup_by(|(a, _), (b, _)| a == b);
 
@@ -376,37 +597,70 @@ impl<F: Field, H: ElementHashFn<F> + Send + Sync + 'static> MatrixMerkleTree<F>
             .iter()
             .map(|r| H::hash_elements(r.as_ref().iter().copied()))
             .collect::<Vec<_>>();
-        if proof.initial_leaves == initial_leaves {
-            Self::verify(root, proof, &indices)
-        } else {
-            Err(Error::InvalidProof)
+        if proof.initial_leaves.len() != initial_leaves.len() {
+            return Err(Error::InvalidProof);
         }
+        let mismatch = zip(&proof.initial_leaves, &initial_leaves).position(|(a, b)| a != b);
+        if let Some(j) = mismatch {
+            return Err(Error::LeafMismatch { i: indices[j] });
+        }
+        Self::verify_to_cap(cap, proof, &indices)
     }
 }
 
-pub struct HashedLeafConfig<H: HashFn>(PhantomData<H>);
+pub struct HashedLeafConfig<H: HashFn, const ARITY: usize = 2>(PhantomData<H>);
 
-impl<H: HashFn> Clone for HashedLeafConfig<H> {
+impl<H: HashFn, const ARITY: usize> Clone for HashedLeafConfig<H, ARITY> {
     fn clone(&self) -> Self {
         Self(PhantomData)
     }
 }
 
-impl<H: HashFn> MerkleTreeConfig for HashedLeafConfig<H> {
+impl<H: HashFn, const ARITY: usize> MerkleTreeConfig for HashedLeafConfig<H, ARITY> {
     type Digest = H::Digest;
     type Leaf = H::Digest;
 
-    fn hash_leaves(_: u32, l0: &H::Digest, l1: &H::Digest) -> H::Digest {
-        H::merge(l0, l1)
+    const ARITY: usize = ARITY;
+
+    fn hash_leaves(depth: u32, leaves: &[H::Digest]) -> H::Digest {
+        Self::hash_nodes(depth, leaves)
     }
 
-    fn hash_nodes(_: u32, n0: &Self::Digest, n1: &Self::Digest) -> Self::Digest {
-        H::merge(n0, n1)
+    fn hash_nodes(_: u32, nodes: &[Self::Digest]) -> Self::Digest {
+        match nodes {
+            [n0, n1] => H::merge(n0, n1),
+            nodes => H::merge_many(nodes),
+        }
     }
 
     fn security_level_bits() -> u32 {
         H::COLLISION_RESISTANCE
     }
+
+    fn id() -> String {
+        format!("{}-{ARITY}", H::ID)
+    }
+}
+
+/// Returns the number of nodes on each level of a [`MerkleTreeImpl`] with
+/// `num_leaves` leaves and `arity` children per node. Levels are ordered from
+/// the merkle cap at `cap_height` down to the leaves.
+pub fn level_widths(num_leaves: usize, arity: usize, cap_height: u32) -> Vec<usize> {
+    let arity_bits = arity.ilog2();
+    let height = num_leaves.checked_ilog2().unwrap_or(0);
+    let num_levels = height.div_ceil(arity_bits);
+    // the cap can't contain leaves
+    let cap_level = cap_height.min(num_levels.saturating_sub(1));
+    (cap_level..=num_levels)
+        .map(|level| {
+            // the root has fewer children if the height isn't a multiple of the arity
+            if level == 0 {
+                1
+            } else {
+                1 << (height - arity_bits * (num_levels - level))
+            }
+        })
+        .collect()
 }
 
 pub fn hash_rows<F: Field, H: ElementHashFn<F>>(matrix: &Matrix<F>) -> Vec<H::Digest> {
@@ -435,72 +689,75 @@ pub fn hash_rows<F: Field, H: ElementHashFn<F>>(matrix: &Matrix<F>) -> Vec<H::Di
     row_hashes
 }
 
-#[cfg(feature = "parallel")]
 pub fn build_merkle_nodes<C: MerkleTreeConfig>(leaves: &[C::Leaf]) -> Vec<C::Digest> {
     let n = leaves.len();
-    let num_subtrees = core::cmp::min(rayon::current_num_threads().next_power_of_two(), n / 2);
-    let mut nodes = vec![C::Digest::default(); n];
-
+    assert!(n >= 2 && n.is_power_of_two());
+    let height = n.ilog2();
+    let num_levels = MerkleTreeImpl::<C>::num_levels(height);
+    let level_width = |level| MerkleTreeImpl::<C>::level_width(height, level);
+    let mut nodes = vec![C::Digest::default(); 2 * level_width(num_levels - 1)];
+
+    // The tree is split into independent subtrees (one per thread) that are
+    // built without synchronizing between levels. The roots of the subtrees
+    // are at `subtree_level` and the tip of the tree above them is built last.
     // code adapted from winterfell
     // https://github.com/facebook/winterfell
-    rayon::scope(|s| {
-        for i in 0..num_subtrees {
-            let nodes = unsafe { &mut *core::ptr::addr_of_mut!(nodes[..]) };
-            s.spawn(move |_| {
-                // generate first layer of nodes from leaf nodes
-                let batch_size = n / num_subtrees;
-                let leaf_offset = batch_size * i;
-                let depth = (n / 2).ilog2();
-                for j in (0..batch_size).step_by(2) {
-                    let lhs = &leaves[leaf_offset + j];
-                    let rhs = &leaves[leaf_offset + j + 1];
-                    nodes[(n + leaf_offset + j) / 2] = C::hash_leaves(depth, lhs, rhs);
-                }
-
-                // generate remaining nodes
-                let mut batch_size = n / num_subtrees / 4;
-                let mut start_idx = n / 4 + batch_size * i;
-                let mut depth = (n / 4).ilog2();
-                while start_idx >= num_subtrees {
-                    for k in (start_idx..(start_idx + batch_size)).rev() {
-                        nodes[k] = C::hash_nodes(depth, &nodes[k * 2], &nodes[k * 2 + 1]);
-                    }
-                    start_idx /= 2;
-                    batch_size /= 2;
-                    depth -= 1;
-                }
-            });
+    #[cfg(feature = "parallel")]
+    let max_subtrees = rayon::current_num_threads().next_power_of_two();
+    #[cfg(not(feature = "parallel"))]
+    let max_subtrees = 1;
+    let subtree_level = (0..num_levels)
+        .take_while(|&level| level_width(level) <= max_subtrees)
+        .last()
+        .unwrap();
+    let num_subtrees = level_width(subtree_level);
+
+    // collect the nodes of each subtree level by level
+    let mut subtrees = (0..num_subtrees).map(|_| Vec::new()).collect::<Vec<_>>();
+    let mut rest = &mut nodes[num_subtrees..];
+    let mut offset = num_subtrees;
+    for level in subtree_level..num_levels {
+        let width = level_width(level);
+        let (_, level_nodes) = core::mem::take(&mut rest).split_at_mut(width - offset);
+        let (level_nodes, tail) = level_nodes.split_at_mut(width);
+        for (subtree, nodes) in zip(&mut subtrees, level_nodes.chunks_mut(width / num_subtrees)) {
+            subtree.push(nodes);
         }
-    });
-
-    // finish the tip of the tree
-    for i in (1..num_subtrees).rev() {
-        let layer = i.ilog2();
-        nodes[i] = C::hash_nodes(layer, &nodes[i * 2], &nodes[i * 2 + 1]);
+        rest = tail;
+        offset = 2 * width;
     }
 
-    nodes
-}
-
-#[cfg(not(feature = "parallel"))]
-pub fn build_merkle_nodes<C: MerkleTreeConfig>(leaves: &[C::Leaf]) -> Vec<C::Digest> {
-    let n = leaves.len();
-    assert!(n.is_power_of_two());
-    let mut nodes = vec![C::Digest::default(); n];
+    ark_std::cfg_iter_mut!(subtrees)
+        .zip(ark_std::cfg_chunks!(leaves, n / num_subtrees))
+        .for_each(|(levels, leaves)| {
+            // generate first layer of nodes from leaf nodes
+            let depth = num_levels - 1;
+            let bottom = levels.last_mut().unwrap();
+            let leaf_arity = leaves.len() / bottom.len();
+            for (node, leaves) in zip(bottom.iter_mut(), leaves.chunks(leaf_arity)) {
+                *node = C::hash_leaves(depth, leaves);
+            }
 
-    // generate first layer of nodes from leaf nodes
-    let depth = (n / 2).ilog2();
-    for i in 0..n / 2 {
-        nodes[n / 2 + i] = C::hash_leaves(depth, &leaves[i * 2], &leaves[i * 2 + 1]);
-    }
+            // generate remaining nodes of the subtree
+            for depth in (subtree_level..num_levels - 1).rev() {
+                let i = (depth - subtree_level) as usize;
+                let (parents, children) = levels.split_at_mut(i + 1);
+                let (parents, children) = (&mut parents[i], &children[0]);
+                let arity = children.len() / parents.len();
+                for (node, children) in zip(parents.iter_mut(), children.chunks(arity)) {
+                    *node = C::hash_nodes(depth, children);
+                }
+            }
+        });
 
-    // generate remaining nodes
-    let num_remaining_layers = (n / 2).ilog2();
-    for depth in (0..num_remaining_layers).rev() {
-        let size = 1 << depth;
-        let offset = size;
-        for i in offset..offset + size {
-            nodes[i] = C::hash_nodes(depth, &nodes[i * 2], &nodes[i * 2 + 1]);
+    // finish the tip of the tree
+    for depth in (0..subtree_level).rev() {
+        let width = level_width(depth);
+        let child_width = level_width(depth + 1);
+        let (parents, children) = nodes.split_at_mut(2 * width);
+        let children = &children[child_width - 2 * width..][..child_width];
+        for (node, children) in zip(&mut parents[width..], children.chunks(child_width / width)) {
+            *node = C::hash_nodes(depth, children);
         }
     }
 
@@ -515,12 +772,14 @@ mod tests {
     use super::MerkleTree;
     use super::MerkleTreeConfig;
     use super::MerkleTreeImpl;
+    use crate::hash::Blake3HashFn;
     use crate::hash::HashFn;
     use crate::hash::Sha256HashFn;
     use crate::utils::GpuAllocator;
     use crate::utils::SerdeOutput;
     use crate::Matrix;
     use ark_ff::MontFp as Fp;
+    use ark_ff::One;
     use digest::Digest;
     use ministark_gpu::fields::p3618502788666131213697322783095070105623107215331596699973092056135872020481::ark::Fp;
     use sha2::Sha256;
@@ -551,6 +810,20 @@ mod tests {
         MatrixMerkleTreeImpl::<Sha256HashFn>::verify_rows(&commitment, &row_ids, &rows, proof)
     }
 
+    #[test]
+    fn prove_rows_blake3() -> Result<(), Error> {
+        let column = (0..64u64).map(Fp::from).collect::<Vec<Fp>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let tree = MatrixMerkleTreeImpl::<Blake3HashFn>::from_matrix(&matrix);
+        let commitment = tree.root();
+        let row_ids = [0, 31, 63];
+        let rows = row_ids.map(|i| [column[i]]);
+
+        let proof = MatrixMerkleTree::<Fp>::prove_rows(&tree, &row_ids)?;
+
+        MatrixMerkleTreeImpl::<Blake3HashFn>::verify_rows(&commitment, &row_ids, &rows, proof)
+    }
+
     #[test]
     fn verify_hashed_leaves() -> Result<(), Error> {
         let leaves = [1u32, 2, 3, 4, 5, 6, 7, 8];
@@ -580,47 +853,197 @@ mod tests {
         MerkleTreeImpl::<UnhashedLeafConfig>::verify(&commitment, proof, &[i])
     }
 
+    #[test]
+    fn verify_to_cap() -> Result<(), Error> {
+        let leaves = (0..1 << 10).collect::<Vec<u32>>();
+        let tree = MerkleTreeImpl::<UnhashedLeafConfig>::new(leaves)?;
+        let indices = [3, 378, 379, 1000];
+
+        for cap_height in 0..tree.height() {
+            let cap = tree.cap(cap_height);
+            assert_eq!(1 << cap_height, cap.len());
+            let proof = tree.prove_to_cap(&indices, cap_height)?;
+            MerkleTreeImpl::<UnhashedLeafConfig>::verify_to_cap(&cap, proof, &indices)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn cap_height_is_clamped() {
+        let leaves = vec![1u32, 2, 3, 4, 5, 6, 7, 8];
+        let tree = MerkleTreeImpl::<UnhashedLeafConfig>::new(leaves).unwrap();
+
+        assert_eq!(tree.cap(tree.height() - 1), tree.cap(u32::MAX));
+    }
+
+    #[test]
+    fn cap_size_of_tiny_trees() {
+        assert_eq!(1, MerkleTreeImpl::<UnhashedLeafConfig>::cap_size(0, 3));
+        assert_eq!(1, MerkleTreeImpl::<UnhashedLeafConfig>::cap_size(1, 3));
+        assert_eq!(1, MerkleTreeImpl::<UnhashedLeafConfig<4>>::cap_size(2, 3));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn build_is_independent_of_thread_count() {
+        let leaves = (0..1 << 9).collect::<Vec<u32>>();
+        let build = |num_threads| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                (
+                    super::build_merkle_nodes::<UnhashedLeafConfig>(&leaves),
+                    super::build_merkle_nodes::<UnhashedLeafConfig<4>>(&leaves),
+                    super::build_merkle_nodes::<UnhashedLeafConfig<8>>(&leaves),
+                )
+            })
+        };
+
+        let expected = build(1);
+
+        for num_threads in [2, 3, 8, 64] {
+            assert_eq!(expected, build(num_threads));
+        }
+    }
+
+    #[test]
+    fn verify_to_cap_fails_with_wrong_cap() {
+        let leaves = (0..1 << 10).collect::<Vec<u32>>();
+        let tree = MerkleTreeImpl::<UnhashedLeafConfig>::new(leaves).unwrap();
+        let mut cap = tree.cap(4);
+        cap.0.swap(0, 1);
+        let proof = tree.prove_to_cap(&[3], 4).unwrap();
+
+        let result = MerkleTreeImpl::<UnhashedLeafConfig>::verify_to_cap(&cap, proof, &[3]);
+
+        assert!(matches!(result, Err(Error::CapMismatch { i: 3 })));
+    }
+
+    #[test]
+    fn verify_higher_arity() -> Result<(), Error> {
+        // 2^9 leaves so the root of the 8-ary tree has 8 children and the root
+        // of the 4-ary tree has only 2 children
+        let leaves = (0..1 << 9).collect::<Vec<u32>>();
+        let indices = [0, 1, 7, 8, 100, 511];
+
+        let tree = MerkleTreeImpl::<UnhashedLeafConfig<8>>::new(leaves.clone())?;
+        let proof = tree.prove(&indices)?;
+        MerkleTreeImpl::<UnhashedLeafConfig<8>>::verify(&tree.root(), proof, &indices)?;
+
+        let tree = MerkleTreeImpl::<UnhashedLeafConfig<4>>::new(leaves)?;
+        let proof = tree.prove(&indices)?;
+        MerkleTreeImpl::<UnhashedLeafConfig<4>>::verify(&tree.root(), proof, &indices)
+    }
+
+    #[test]
+    fn higher_arity_caps() -> Result<(), Error> {
+        let leaves = (0..1 << 9).collect::<Vec<u32>>();
+        let tree = MerkleTreeImpl::<UnhashedLeafConfig<4>>::new(leaves)?;
+        let indices = [3, 378, 379];
+
+        for (cap_height, cap_size) in [(0, 1), (1, 2), (2, 8), (3, 32), (4, 128), (5, 128)] {
+            let cap = tree.cap(cap_height);
+            assert_eq!(cap_size, cap.len());
+            let proof = tree.prove_to_cap(&indices, cap_height)?;
+            MerkleTreeImpl::<UnhashedLeafConfig<4>>::verify_to_cap(&cap, proof, &indices)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn prove_rows_higher_arity() -> Result<(), Error> {
+        let column = (0..64u64).map(Fp::from).collect::<Vec<Fp>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let tree = MatrixMerkleTreeImpl::<Sha256HashFn, 4>::from_matrix(&matrix);
+        let commitment = tree.root();
+        let row_ids = [5, 6, 60];
+        let rows = row_ids.map(|i| [column[i]]);
+
+        let proof = MatrixMerkleTree::<Fp>::prove_rows(&tree, &row_ids)?;
+
+        MatrixMerkleTreeImpl::<Sha256HashFn, 4>::verify_rows(&commitment, &row_ids, &rows, proof)
+    }
+
+    #[test]
+    fn verify_rows_reports_the_tampered_row() {
+        let column = (0..64u64).map(Fp::from).collect::<Vec<Fp>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let tree = MatrixMerkleTreeImpl::<Sha256HashFn>::from_matrix(&matrix);
+        let cap = tree.cap(2);
+        let row_ids = [60, 5, 6];
+        let mut rows = row_ids.map(|i| [column[i]]);
+        rows[2][0] += Fp::one();
+        let proof = MatrixMerkleTree::<Fp>::prove_rows_to_cap(&tree, &row_ids, 2).unwrap();
+
+        let result =
+            MatrixMerkleTreeImpl::<Sha256HashFn>::verify_rows_to_cap(&cap, &row_ids, &rows, proof);
+
+        assert!(matches!(result, Err(Error::LeafMismatch { i: 6 })));
+    }
+
+    #[test]
+    fn from_row_chunks_matches_from_matrix() {
+        let column = (0..64u64).map(Fp::from).collect::<Vec<Fp>>();
+        let matrix = Matrix::new(vec![column.to_vec_in(GpuAllocator)]);
+        let chunks = column
+            .chunks(16)
+            .map(|chunk| Matrix::new(vec![chunk.to_vec_in(GpuAllocator)]));
+
+        let expected = MatrixMerkleTreeImpl::<Sha256HashFn>::from_matrix(&matrix);
+        let actual = MatrixMerkleTreeImpl::<Sha256HashFn>::from_row_chunks(chunks);
+
+        assert_eq!(expected.root(), actual.root());
+    }
+
     struct HashedLeafConfig;
 
     impl MerkleTreeConfig for HashedLeafConfig {
         type Digest = SerdeOutput<Sha256>;
         type Leaf = SerdeOutput<Sha256>;
 
-        fn hash_leaves(
-            _: u32,
-            l0: &SerdeOutput<Sha256>,
-            l1: &SerdeOutput<Sha256>,
-        ) -> SerdeOutput<Sha256> {
-            Sha256HashFn::merge(l0, l1)
+        fn hash_leaves(_: u32, leaves: &[SerdeOutput<Sha256>]) -> SerdeOutput<Sha256> {
+            Sha256HashFn::merge_many(leaves)
         }
 
-        fn hash_nodes(_: u32, n0: &Self::Digest, n1: &Self::Digest) -> Self::Digest {
-            Sha256HashFn::merge(n0, n1)
+        fn hash_nodes(_: u32, nodes: &[Self::Digest]) -> Self::Digest {
+            Sha256HashFn::merge_many(nodes)
         }
 
         fn security_level_bits() -> u32 {
             Sha256HashFn::COLLISION_RESISTANCE
         }
+
+        fn id() -> String {
+            "test".into()
+        }
     }
 
-    struct UnhashedLeafConfig;
+    struct UnhashedLeafConfig<const ARITY: usize = 2>;
 
-    impl MerkleTreeConfig for UnhashedLeafConfig {
+    impl<const ARITY: usize> MerkleTreeConfig for UnhashedLeafConfig<ARITY> {
         type Digest = SerdeOutput<Sha256>;
         type Leaf = u32;
 
-        fn hash_leaves(_: u32, l0: &u32, l1: &u32) -> SerdeOutput<Sha256> {
-            let l0_bytes = l0.to_be_bytes();
-            let l1_bytes = l1.to_be_bytes();
-            Sha256HashFn::hash_chunks([&l0_bytes[..], &l1_bytes[..]])
+        const ARITY: usize = ARITY;
+
+        fn hash_leaves(_: u32, leaves: &[u32]) -> SerdeOutput<Sha256> {
+            Sha256HashFn::hash(leaves.iter().flat_map(|l| l.to_be_bytes()))
         }
 
-        fn hash_nodes(_: u32, n0: &Self::Digest, n1: &Self::Digest) -> Self::Digest {
-            Sha256HashFn::merge(n0, n1)
+        fn hash_nodes(_: u32, nodes: &[Self::Digest]) -> Self::Digest {
+            Sha256HashFn::merge_many(nodes)
         }
 
         fn security_level_bits() -> u32 {
             Sha256HashFn::COLLISION_RESISTANCE
         }
+
+        fn id() -> String {
+            "test".into()
+        }
     }
 }
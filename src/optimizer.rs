@@ -0,0 +1,263 @@
+use crate::merkle::level_widths;
+use crate::merkle::MerkleTree;
+use crate::random::PublicCoin;
+use crate::security::conjectured_security_bits;
+use crate::stark::Stark;
+use crate::utils::field_bits;
+use crate::ProofOptions;
+
+/// What the [`ProofOptionsOptimizer`] minimises
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizationGoal {
+    /// Minimise the estimated size of the proof
+    ProofSize,
+    /// Minimise the estimated time to generate the proof
+    ProverTime,
+}
+
+/// Searches for the [`ProofOptions`] that reach a target (conjectured)
+/// security level with the smallest estimated proof size or prover time.
+///
+/// The cost models are rough estimates and only intended to compare
+/// different parameters for the same AIR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofOptionsOptimizer {
+    pub target_security_bits: u32,
+    pub trace_len: usize,
+    pub ce_blowup_factor: usize,
+    /// Size of the extension field in bits
+    pub extension_field_bits: u32,
+    /// Collision resistance of the hash functions in bits
+    pub collision_resistance: u32,
+    /// Upper bound on the proof-of-work bits. Each bit of grinding doubles
+    /// the grinding time.
+    pub max_grinding_factor: u8,
+    /// Number of children of each internal node of the merkle trees
+    pub merkle_tree_arity: usize,
+    /// Height of the merkle caps used by the optimized options
+    pub merkle_cap_height: u8,
+}
+
+impl ProofOptionsOptimizer {
+    const DEFAULT_MAX_GRINDING_FACTOR: u8 = 20;
+    const FRI_FOLDING_FACTORS: [u8; 4] = [2, 4, 8, 16];
+    const FRI_MAX_REMAINDER_COEFFS: [u8; 5] = [8, 16, 32, 64, 128];
+    const DIGEST_BYTES: usize = 32;
+
+    pub fn new<S: Stark>(
+        target_security_bits: u32,
+        trace_len: usize,
+        ce_blowup_factor: usize,
+    ) -> Self {
+        let merkle_tree_security = S::MerkleTree::security_level_bits();
+        let public_coin_security = S::PublicCoin::security_level_bits();
+        Self {
+            target_security_bits,
+            trace_len,
+            ce_blowup_factor,
+            extension_field_bits: field_bits::<S::Fq>(),
+            collision_resistance: merkle_tree_security.min(public_coin_security),
+            max_grinding_factor: Self::DEFAULT_MAX_GRINDING_FACTOR,
+            merkle_tree_arity: S::MerkleTree::ARITY,
+            merkle_cap_height: 0,
+        }
+    }
+
+    pub const fn with_max_grinding_factor(mut self, max_grinding_factor: u8) -> Self {
+        self.max_grinding_factor = max_grinding_factor;
+        self
+    }
+
+    /// Optimize options that commit to merkle caps of height
+    /// `merkle_cap_height`. See [`ProofOptions::with_merkle_cap_height`].
+    pub const fn with_merkle_cap_height(mut self, merkle_cap_height: u8) -> Self {
+        assert!(merkle_cap_height <= ProofOptions::MAX_MERKLE_CAP_HEIGHT);
+        self.merkle_cap_height = merkle_cap_height;
+        self
+    }
+
+    /// Returns the options that minimise `goal` or `None` if the target
+    /// security level can't be reached (e.g. the field or hash function is too
+    /// small).
+    pub fn optimize(&self, goal: OptimizationGoal) -> Option<ProofOptions> {
+        let cost = |options: &ProofOptions| match goal {
+            OptimizationGoal::ProofSize => self.estimate_proof_size(*options),
+            OptimizationGoal::ProverTime => self.estimate_prover_time(*options),
+        };
+        self.candidates().min_by_key(cost)
+    }
+
+    /// Returns all options that reach the target security level with the
+    /// fewest queries for their blowup factor and grinding factor
+    fn candidates(&self) -> impl Iterator<Item = ProofOptions> + '_ {
+        let min_blowup_factor = self.ce_blowup_factor.next_power_of_two().max(2);
+        let blowup_factors = (1..=ProofOptions::MAX_BLOWUP_FACTOR.ilog2())
+            .map(|i| 1u8 << i)
+            .filter(move |&b| usize::from(b) >= min_blowup_factor);
+        let max_grinding_factor = self
+            .max_grinding_factor
+            .min(ProofOptions::MAX_GRINDING_FACTOR);
+        let merkle_cap_height = self.merkle_cap_height;
+        blowup_factors
+            .flat_map(move |b| (0..=max_grinding_factor).map(move |g| (b, g)))
+            .filter_map(|(b, g)| Some((b, g, self.min_num_queries(b, g)?)))
+            .flat_map(move |(b, g, q)| {
+                Self::FRI_FOLDING_FACTORS.into_iter().flat_map(move |f| {
+                    Self::FRI_MAX_REMAINDER_COEFFS.into_iter().map(move |r| {
+                        ProofOptions::new(q, b, g, f, r).with_merkle_cap_height(merkle_cap_height)
+                    })
+                })
+            })
+            .filter(|options| self.security_bits(*options) >= self.target_security_bits)
+    }
+
+    /// Returns the minimum number of queries needed to reach the target
+    /// security level
+    fn min_num_queries(&self, lde_blowup_factor: u8, grinding_factor: u8) -> Option<u8> {
+        let bits_per_query = lde_blowup_factor.ilog2();
+        let remaining_bits = self
+            .target_security_bits
+            .saturating_sub(u32::from(grinding_factor));
+        let num_queries = remaining_bits.div_ceil(bits_per_query).max(1);
+        u8::try_from(num_queries)
+            .ok()
+            .filter(|&q| q <= ProofOptions::MAX_NUM_QUERIES)
+    }
+
+    fn security_bits(&self, options: ProofOptions) -> u32 {
+        conjectured_security_bits(
+            options,
+            self.trace_len,
+            self.extension_field_bits,
+            self.collision_resistance,
+        )
+    }
+
+    /// Estimates the proof size in bytes. Only the parts of the proof that
+    /// depend on the options are included i.e. trace values are ignored.
+    fn estimate_proof_size(&self, options: ProofOptions) -> usize {
+        const NUM_TRACE_COMMITMENTS: usize = 3;
+        let field_bytes = self.extension_field_bits.div_ceil(8) as usize;
+        let num_queries = usize::from(options.num_queries);
+        let lde_domain_size = self.trace_len * usize::from(options.lde_blowup_factor);
+        let merkle_bytes =
+            |num_leaves: usize| self.merkle_digests(num_leaves, num_queries) * Self::DIGEST_BYTES;
+
+        let trace_bytes = NUM_TRACE_COMMITMENTS * merkle_bytes(lde_domain_size);
+
+        let fri_options = options.into_fri_options();
+        let folding_factor = usize::from(options.fri_folding_factor);
+        let mut fri_bytes = 0;
+        let mut domain_size = lde_domain_size;
+        for _ in 0..fri_options.num_layers(lde_domain_size) {
+            let num_rows = domain_size / folding_factor;
+            fri_bytes += merkle_bytes(num_rows) + num_queries * folding_factor * field_bytes;
+            domain_size = num_rows;
+        }
+        let remainder_size = fri_options.remainder_size(lde_domain_size);
+        let remainder_bytes = remainder_size / usize::from(options.lde_blowup_factor) * field_bytes;
+
+        trace_bytes + fri_bytes + remainder_bytes
+    }
+
+    /// Returns the number of digests in the merkle cap and the merkle paths of
+    /// `num_queries` queries for a tree with `num_leaves` leaves. Assumes the
+    /// paths don't overlap.
+    fn merkle_digests(&self, num_leaves: usize, num_queries: usize) -> usize {
+        let cap_height = u32::from(self.merkle_cap_height);
+        let widths = level_widths(num_leaves, self.merkle_tree_arity, cap_height);
+        let siblings_per_query = widths
+            .windows(2)
+            .map(|pair| pair[1] / pair[0] - 1)
+            .sum::<usize>();
+        widths[0] + num_queries * siblings_per_query
+    }
+
+    /// Estimates the prover time in arbitrary units. Accounts for the low
+    /// degree extension, hashing the LDE, building the FRI layers and grinding.
+    fn estimate_prover_time(&self, options: ProofOptions) -> usize {
+        let lde_domain_size = self.trace_len * usize::from(options.lde_blowup_factor);
+        let lde_cost = lde_domain_size * lde_domain_size.ilog2() as usize;
+        let hashing_cost = lde_domain_size;
+        let folding_factor = usize::from(options.fri_folding_factor);
+        let fri_cost = lde_domain_size / (folding_factor - 1) * folding_factor.ilog2() as usize;
+        let grinding_cost = 1usize << options.grinding_factor;
+        lde_cost + hashing_cost + fri_cost + grinding_cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OptimizationGoal;
+    use super::ProofOptionsOptimizer;
+    use crate::security::conjectured_security_bits;
+
+    const OPTIMIZER: ProofOptionsOptimizer = ProofOptionsOptimizer {
+        target_security_bits: 100,
+        trace_len: 1 << 16,
+        ce_blowup_factor: 4,
+        extension_field_bits: 192,
+        collision_resistance: 128,
+        max_grinding_factor: 20,
+        merkle_tree_arity: 2,
+        merkle_cap_height: 0,
+    };
+
+    #[test]
+    fn optimized_options_reach_target_security() {
+        for goal in [OptimizationGoal::ProofSize, OptimizationGoal::ProverTime] {
+            let options = OPTIMIZER.optimize(goal).unwrap();
+            let security_bits = conjectured_security_bits(options, 1 << 16, 192, 128);
+
+            assert!(security_bits >= 100);
+            assert!(usize::from(options.lde_blowup_factor) >= 4);
+        }
+    }
+
+    #[test]
+    fn small_proofs_use_larger_blowup_factor() {
+        let small_proof_options = OPTIMIZER.optimize(OptimizationGoal::ProofSize).unwrap();
+        let fast_prover_options = OPTIMIZER.optimize(OptimizationGoal::ProverTime).unwrap();
+
+        assert!(small_proof_options.lde_blowup_factor > fast_prover_options.lde_blowup_factor);
+        assert!(small_proof_options.num_queries < fast_prover_options.num_queries);
+    }
+
+    #[test]
+    fn size_model_uses_merkle_tree_arity_and_cap() {
+        let options = OPTIMIZER.optimize(OptimizationGoal::ProofSize).unwrap();
+        let quaternary = ProofOptionsOptimizer {
+            merkle_tree_arity: 4,
+            ..OPTIMIZER
+        };
+        let capped = OPTIMIZER.with_merkle_cap_height(4);
+
+        let binary_size = OPTIMIZER.estimate_proof_size(options);
+        let quaternary_size = quaternary.estimate_proof_size(options);
+        let capped_size = capped.estimate_proof_size(options);
+
+        // a 4-ary path has half as many levels but 3 siblings on each level
+        assert!(binary_size < quaternary_size);
+        // a cap of 16 nodes saves 4 levels on each path
+        assert!(capped_size < binary_size);
+        assert_eq!(16 + 2 * 6, capped.merkle_digests(1 << 10, 2));
+        assert_eq!(1 + 2 * 3 * 5, quaternary.merkle_digests(1 << 10, 2));
+        assert_eq!(
+            4,
+            capped
+                .optimize(OptimizationGoal::ProofSize)
+                .unwrap()
+                .merkle_cap_height
+        );
+    }
+
+    #[test]
+    fn unreachable_security_level() {
+        let optimizer = ProofOptionsOptimizer {
+            target_security_bits: 200,
+            ..OPTIMIZER
+        };
+
+        assert!(optimizer.optimize(OptimizationGoal::ProofSize).is_none());
+    }
+}
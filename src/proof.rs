@@ -1,25 +1,42 @@
+use crate::air::AirConfig;
 use crate::fri::FriProof;
+use crate::merkle::level_widths;
+use crate::merkle::MerkleCap;
 use crate::merkle::MerkleTree;
 use crate::random::PublicCoin;
+use crate::security::conjectured_security_bits;
 use crate::stark::Stark;
 use crate::trace::Queries;
 use crate::utils::field_bits;
+use crate::Air;
 use crate::ProofOptions;
+use alloc::vec::Vec;
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
 use ark_serialize::Valid;
+use core::fmt::Display;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 /// A proof generated by a mini-stark prover
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct Proof<C: Stark> {
     pub options: ProofOptions,
     pub trace_len: usize,
-    pub base_trace_commitment: C::Digest,
-    pub extension_trace_commitment: Option<C::Digest>,
-    pub composition_trace_commitment: C::Digest,
+    /// Commitments to each base column group
+    pub base_trace_commitments: Vec<MerkleCap<C::Digest>>,
+    pub extension_trace_commitment: Option<MerkleCap<C::Digest>>,
+    pub composition_trace_commitment: MerkleCap<C::Digest>,
     pub fri_proof: FriProof<C::Fq, C::Digest, C::MerkleTree>,
     pub pow_nonce: u64,
     pub trace_queries: Queries<C>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub execution_trace_ood_evals: Vec<C::Fq>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub composition_trace_ood_evals: Vec<C::Fq>,
 }
 
@@ -28,7 +45,7 @@ impl<C: Stark> Clone for Proof<C> {
         Self {
             options: self.options,
             trace_len: self.trace_len,
-            base_trace_commitment: self.base_trace_commitment.clone(),
+            base_trace_commitments: self.base_trace_commitments.clone(),
             extension_trace_commitment: self.extension_trace_commitment.clone(),
             composition_trace_commitment: self.composition_trace_commitment.clone(),
             fri_proof: self.fri_proof.clone(),
@@ -48,7 +65,7 @@ impl<C: Stark> CanonicalSerialize for Proof<C> {
     ) -> Result<(), ark_serialize::SerializationError> {
         self.options.serialize_with_mode(&mut writer, compress)?;
         self.trace_len.serialize_with_mode(&mut writer, compress)?;
-        self.base_trace_commitment
+        self.base_trace_commitments
             .serialize_with_mode(&mut writer, compress)?;
         self.extension_trace_commitment
             .serialize_with_mode(&mut writer, compress)?;
@@ -68,7 +85,7 @@ impl<C: Stark> CanonicalSerialize for Proof<C> {
     fn serialized_size(&self, compress: ark_serialize::Compress) -> usize {
         self.options.serialized_size(compress)
             + self.trace_len.serialized_size(compress)
-            + self.base_trace_commitment.serialized_size(compress)
+            + self.base_trace_commitments.serialized_size(compress)
             + self.extension_trace_commitment.serialized_size(compress)
             + self.composition_trace_commitment.serialized_size(compress)
             + self.fri_proof.serialized_size(compress)
@@ -95,7 +112,7 @@ impl<C: Stark> CanonicalDeserialize for Proof<C> {
         Ok(Self {
             options: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             trace_len: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
-            base_trace_commitment: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            base_trace_commitments: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             extension_trace_commitment: <_>::deserialize_with_mode(
                 &mut reader,
                 compress,
@@ -120,29 +137,330 @@ impl<C: Stark> CanonicalDeserialize for Proof<C> {
 }
 
 impl<C: Stark> Proof<C> {
-    // adapted from Winterfell
-    // also https://github.com/starkware-libs/ethSTARK/blob/master/README.md#7-Measuring-Security
-    // https://eprint.iacr.org/2020/654.pdf section 7.2 for proven security
+    /// Returns the conjectured security level in bits. The proven security
+    /// level depends on the AIR so is available from [`SecurityParams`].
+    ///
+    /// [`SecurityParams`]: crate::security::SecurityParams
     pub fn security_level_bits(&self) -> u32 {
-        let field_security = {
-            let lde_domain_size = self.trace_len * self.options.lde_blowup_factor as usize;
-            let extension_field_bits = field_bits::<C::Fq>();
-            extension_field_bits - lde_domain_size.ilog2()
-        };
+        let merkle_tree_security = C::MerkleTree::security_level_bits();
+        let public_coin_security = C::PublicCoin::security_level_bits();
+        conjectured_security_bits(
+            self.options,
+            self.trace_len,
+            field_bits::<C::Fq>(),
+            merkle_tree_security.min(public_coin_security),
+        )
+    }
+}
+
+impl<C: Stark> Proof<C> {
+    /// Returns the number of bytes each component of the proof occupies when
+    /// serialized. The components sum to
+    /// [`CanonicalSerialize::serialized_size`].
+    pub fn size_breakdown(&self, compress: Compress) -> ProofSizeBreakdown {
+        let queries = &self.trace_queries;
+        let fri_layers = &self.fri_proof.layers;
+        let len_prefix_size = 0usize.serialized_size(compress);
+        let option_tag_size = None::<u8>.serialized_size(compress);
+        ProofSizeBreakdown {
+            metadata: self.options.serialized_size(compress)
+                + self.trace_len.serialized_size(compress)
+                + self.pow_nonce.serialized_size(compress),
+            commitments: self.base_trace_commitments.serialized_size(compress)
+                + self.extension_trace_commitment.serialized_size(compress)
+                + self.composition_trace_commitment.serialized_size(compress)
+                + fri_layers
+                    .iter()
+                    .map(|layer| layer.commitment.serialized_size(compress))
+                    .sum::<usize>(),
+            trace_query_values: queries
+                .base_trace_groups
+                .iter()
+                .map(|group| group.values.serialized_size(compress))
+                .sum::<usize>()
+                + queries.extension_trace_values.serialized_size(compress)
+                + queries.composition_trace_values.serialized_size(compress),
+            base_trace_merkle_proofs: queries
+                .base_trace_groups
+                .iter()
+                .map(|group| group.proof.serialized_size(compress))
+                .collect(),
+            extension_trace_merkle_proof: queries
+                .extension_trace_proof
+                .as_ref()
+                .map_or(0, |proof| proof.serialized_size(compress)),
+            composition_trace_merkle_proof: queries
+                .composition_trace_proof
+                .serialized_size(compress),
+            fri_layer_rows: fri_layers
+                .iter()
+                .map(|layer| layer.flattenend_rows.serialized_size(compress))
+                .collect(),
+            fri_layer_merkle_proofs: fri_layers
+                .iter()
+                .map(|layer| layer.merkle_proof.serialized_size(compress))
+                .collect(),
+            fri_remainder: self.fri_proof.remainder_coeffs.serialized_size(compress),
+            ood_evals: self.execution_trace_ood_evals.serialized_size(compress)
+                + self.composition_trace_ood_evals.serialized_size(compress),
+            // base trace groups, extension trace proof and FRI layers
+            overhead: len_prefix_size + option_tag_size + len_prefix_size,
+        }
+    }
+}
+
+/// Number of bytes each component of a [`Proof`] occupies when serialized
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProofSizeBreakdown {
+    /// Proof options, trace length and proof-of-work nonce
+    pub metadata: usize,
+    /// Merkle caps of the trace and FRI layer commitments
+    pub commitments: usize,
+    /// Base, extension and composition trace values at the query positions
+    pub trace_query_values: usize,
+    /// Merkle proofs for each base column group
+    pub base_trace_merkle_proofs: Vec<usize>,
+    pub extension_trace_merkle_proof: usize,
+    pub composition_trace_merkle_proof: usize,
+    /// Queried rows of each FRI layer
+    pub fri_layer_rows: Vec<usize>,
+    /// Merkle proofs for the queried rows of each FRI layer
+    pub fri_layer_merkle_proofs: Vec<usize>,
+    pub fri_remainder: usize,
+    /// Out-of-domain evaluations of the execution and composition trace
+    pub ood_evals: usize,
+    /// Length prefixes and option tags that aren't part of any component
+    pub overhead: usize,
+}
+
+impl ProofSizeBreakdown {
+    /// Predicts the serialized size of each component of a proof for `air`.
+    ///
+    /// Merkle proof sizes assume trees with [`MerkleTree::ARITY`] children per
+    /// node whose proofs are [`MerkleView`]s of digests. Query positions are
+    /// assumed to be uniformly distributed so the sizes of merkle proofs and
+    /// FRI layer rows are expected values. All other components are exact.
+    ///
+    /// [`MerkleView`]: crate::merkle::MerkleView
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn estimate<S: Stark>(air: &Air<S::AirConfig>, compress: Compress) -> Self {
+        let options = air.options();
+        let cap_height = u32::from(options.merkle_cap_height);
+        let num_queries = air.num_queries();
+        let lde_domain_size = air.lde_domain().size();
+        let ce_blowup_factor = air.ce_blowup_factor();
+        let num_extension_cols = S::AirConfig::NUM_EXTENSION_COLUMNS;
 
-        let fri_query_security = {
-            let grinding_factor = u32::from(self.options.grinding_factor);
-            let security_per_query = self.options.lde_blowup_factor.ilog2();
-            let num_fri_quiries = u32::from(self.options.num_queries);
-            security_per_query * num_fri_quiries + grinding_factor
+        let len_prefix_size = 0usize.serialized_size(compress);
+        let option_tag_size = None::<u8>.serialized_size(compress);
+        let digest_size = S::Digest::default().serialized_size(compress);
+        let base_element_size = S::Fp::zero().serialized_size(compress);
+        let extension_element_size = S::Fq::zero().serialized_size(compress);
+        let cap_size = |num_leaves: usize| {
+            len_prefix_size + S::MerkleTree::cap_size(num_leaves, cap_height) * digest_size
+        };
+        let merkle_proof_size = |num_leaves: usize| {
+            let num_digests = expected_merkle_proof_digests(
+                lde_domain_size,
+                num_queries,
+                num_leaves,
+                S::MerkleTree::ARITY,
+                cap_height,
+            );
+            // nodes, initial leaves and sibling leaves are length prefixed
+            3 * len_prefix_size + 0u32.serialized_size(compress) + num_digests * digest_size
         };
 
-        let merkle_tree_security = C::MerkleTree::security_level_bits();
-        let public_coin_security = C::PublicCoin::security_level_bits();
+        // trace
+        let num_base_groups = S::AirConfig::BASE_COLUMN_GROUPS.len();
+        let has_extension_trace = num_extension_cols != 0;
+        let trace_commitments = num_base_groups * cap_size(lde_domain_size)
+            + len_prefix_size
+            + option_tag_size
+            + usize::from(has_extension_trace) * cap_size(lde_domain_size)
+            + cap_size(lde_domain_size);
+        let trace_merkle_proof_size = merkle_proof_size(lde_domain_size);
+        let trace_query_values = (num_base_groups + 2) * len_prefix_size
+            + num_queries * S::AirConfig::NUM_BASE_COLUMNS * base_element_size
+            + num_queries * (num_extension_cols + ce_blowup_factor) * extension_element_size;
+
+        // FRI
+        let fri_options = options.into_fri_options();
+        let folding_factor = usize::from(options.fri_folding_factor);
+        let mut fri_commitments = 0;
+        let mut fri_layer_rows = Vec::new();
+        let mut fri_layer_merkle_proofs = Vec::new();
+        let mut domain_size = lde_domain_size;
+        for _ in 0..fri_options.num_layers(lde_domain_size) {
+            let num_rows = domain_size / folding_factor;
+            // expected number of distinct rows hit by the queries
+            let num_row_queries =
+                expected_num_ancestors(lde_domain_size, num_queries, num_rows).round() as usize;
+            fri_commitments += cap_size(num_rows);
+            fri_layer_rows
+                .push(len_prefix_size + num_row_queries * folding_factor * extension_element_size);
+            fri_layer_merkle_proofs.push(merkle_proof_size(num_rows));
+            domain_size = num_rows;
+        }
+        let remainder_size = fri_options.remainder_size(lde_domain_size);
+        let num_remainder_coeffs = remainder_size / air.lde_blowup_factor();
+
+        // OOD evals
+        let num_ood_evals = air.trace_arguments().len() + ce_blowup_factor;
+
+        Self {
+            metadata: options.serialized_size(compress)
+                + air.trace_len().serialized_size(compress)
+                + 0u64.serialized_size(compress),
+            commitments: trace_commitments + fri_commitments,
+            trace_query_values,
+            base_trace_merkle_proofs: vec![trace_merkle_proof_size; num_base_groups],
+            extension_trace_merkle_proof: usize::from(has_extension_trace)
+                * trace_merkle_proof_size,
+            composition_trace_merkle_proof: trace_merkle_proof_size,
+            fri_layer_rows,
+            fri_layer_merkle_proofs,
+            fri_remainder: len_prefix_size + num_remainder_coeffs * extension_element_size,
+            ood_evals: 2 * len_prefix_size + num_ood_evals * extension_element_size,
+            // base trace groups, extension trace proof and FRI layers
+            overhead: len_prefix_size + option_tag_size + len_prefix_size,
+        }
+    }
+
+    /// Returns the total size of the proof in bytes
+    pub fn total(&self) -> usize {
+        self.metadata
+            + self.commitments
+            + self.trace_query_values
+            + self.base_trace_merkle_proofs.iter().sum::<usize>()
+            + self.extension_trace_merkle_proof
+            + self.composition_trace_merkle_proof
+            + self.fri_layer_rows.iter().sum::<usize>()
+            + self.fri_layer_merkle_proofs.iter().sum::<usize>()
+            + self.fri_remainder
+            + self.ood_evals
+            + self.overhead
+    }
+}
+
+impl Display for ProofSizeBreakdown {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "metadata: {} bytes", self.metadata)?;
+        writeln!(f, "commitments: {} bytes", self.commitments)?;
+        writeln!(f, "trace query values: {} bytes", self.trace_query_values)?;
+        for (i, size) in self.base_trace_merkle_proofs.iter().enumerate() {
+            writeln!(f, "base trace group {i} merkle proof: {size} bytes")?;
+        }
+        if self.extension_trace_merkle_proof != 0 {
+            let size = self.extension_trace_merkle_proof;
+            writeln!(f, "extension trace merkle proof: {size} bytes")?;
+        }
+        let size = self.composition_trace_merkle_proof;
+        writeln!(f, "composition trace merkle proof: {size} bytes")?;
+        let fri_layers = self
+            .fri_layer_rows
+            .iter()
+            .zip(&self.fri_layer_merkle_proofs);
+        for (i, (rows, proof)) in fri_layers.enumerate() {
+            writeln!(
+                f,
+                "FRI layer {i}: {rows} bytes of rows, {proof} bytes of merkle proof"
+            )?;
+        }
+        writeln!(f, "FRI remainder: {} bytes", self.fri_remainder)?;
+        writeln!(f, "OOD evaluations: {} bytes", self.ood_evals)?;
+        writeln!(f, "overhead: {} bytes", self.overhead)?;
+        write!(f, "total: {} bytes", self.total())
+    }
+}
+
+/// Returns the expected number of nodes on a level of width `width` that are
+/// ancestors of `num_queries` distinct uniformly random positions of a
+/// domain of size `domain_size`.
+#[allow(clippy::cast_precision_loss)]
+fn expected_num_ancestors(domain_size: usize, num_queries: usize, width: usize) -> f64 {
+    let span = domain_size / width;
+    // probability none of the queries are below a node
+    let p_unqueried = (0..num_queries)
+        .map(|i| (domain_size - span).saturating_sub(i) as f64 / (domain_size - i) as f64)
+        .product::<f64>();
+    width as f64 * (1.0 - p_unqueried)
+}
+
+/// Returns the expected number of digests in a merkle proof for a tree with
+/// `num_leaves` leaves and `arity` children per node when querying the
+/// ancestors of `num_queries` distinct uniformly random positions of a domain
+/// of size `domain_size`. Includes the queried leaves, their siblings and the
+/// nodes needed to reach the cap.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    clippy::suboptimal_flops
+)]
+fn expected_merkle_proof_digests(
+    domain_size: usize,
+    num_queries: usize,
+    num_leaves: usize,
+    arity: usize,
+    cap_height: u32,
+) -> usize {
+    let num_known = |width: usize| expected_num_ancestors(domain_size, num_queries, width);
+    let mut num_digests = num_known(num_leaves);
+    for pair in level_widths(num_leaves, arity, cap_height).windows(2) {
+        let (parent_width, child_width) = (pair[0], pair[1]);
+        let num_children = (child_width / parent_width) as f64;
+        // each known parent requires the siblings of its known children
+        num_digests += num_children * num_known(parent_width) - num_known(child_width);
+    }
+    num_digests.round() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expected_merkle_proof_digests;
+
+    const N: usize = 1 << 10;
+
+    #[test]
+    fn merkle_proof_digests_single_query() {
+        assert_eq!(1 + 10, expected_merkle_proof_digests(N, 1, N, 2, 0));
+        assert_eq!(1 + 6, expected_merkle_proof_digests(N, 1, N, 2, 4));
+    }
+
+    #[test]
+    fn merkle_proof_digests_all_leaves() {
+        assert_eq!(N, expected_merkle_proof_digests(N, N, N, 2, 0));
+    }
+
+    #[test]
+    fn merkle_proof_digests_folded_layer() {
+        assert_eq!(N / 4, expected_merkle_proof_digests(N, N, N / 4, 2, 0));
+        assert_eq!(1 + 8, expected_merkle_proof_digests(N, 1, N / 4, 2, 0));
+    }
+
+    #[test]
+    fn merkle_proof_digests_cap_is_clamped() {
+        assert_eq!(
+            1 + 1,
+            expected_merkle_proof_digests(1 << 4, 1, 1 << 4, 2, 20)
+        );
+    }
+
+    #[test]
+    fn merkle_proof_digests_tiny_trees() {
+        assert_eq!(1, expected_merkle_proof_digests(N, 1, 1, 2, 0));
+        assert_eq!(2, expected_merkle_proof_digests(N, 1, 2, 2, 3));
+    }
 
-        field_security
-            .min(fri_query_security)
-            .min(merkle_tree_security)
-            .min(public_coin_security)
+    #[test]
+    fn merkle_proof_digests_higher_arity() {
+        assert_eq!(1 + 3 * 5, expected_merkle_proof_digests(N, 1, N, 4, 0));
+        assert_eq!(1 + 3 * 4, expected_merkle_proof_digests(N, 1, N, 4, 1));
+        // the root of a tree with 2^9 leaves only has two children
+        assert_eq!(
+            1 + 1 + 3 * 4,
+            expected_merkle_proof_digests(N, 1, N / 2, 4, 0)
+        );
     }
 }
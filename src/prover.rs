@@ -1,31 +1,77 @@
+use crate::air;
 use crate::air::AirConfig;
 use crate::challenges::Challenges;
 use crate::channel::ProverChannel;
 use crate::composer::DeepPolyComposer;
 use crate::fri::FriProver;
+#[cfg(feature = "mmap")]
+use crate::matrix::MmapMatrix;
 use crate::merkle::MatrixMerkleTree;
 use crate::merkle::MerkleTree;
 use crate::random::draw_multiple;
 use crate::stark::Stark;
 use crate::trace::Queries;
+use crate::utils::bit_reversed_chunk_domain;
 use crate::utils::GpuAllocator;
 use crate::utils::GpuVec;
 use crate::Air;
 use crate::Matrix;
+use crate::Pod;
 use crate::Proof;
 use crate::ProofOptions;
 use crate::Trace;
 use alloc::vec::Vec;
+use ark_ff::FftField;
 use ark_ff::Field;
+use ark_poly::domain::DomainCoeff;
+use ark_poly::domain::Radix2EvaluationDomain;
 use ark_poly::EvaluationDomain;
+use core::iter::zip;
+use core::marker::PhantomData;
+use core::mem::size_of;
 use ministark_gpu::utils::bit_reverse;
+use ministark_gpu::utils::bit_reverse_index;
+use ministark_gpu::GpuField;
+#[cfg(feature = "mmap")]
+use std::path::PathBuf;
 use std::time::Instant;
 
-#[allow(clippy::too_many_lines)]
 pub fn default_prove<S: Stark>(
     this: &S,
     options: ProofOptions,
     witness: S::Witness,
+) -> Result<Proof<S>, ProvingError> {
+    prove_with_lde_storage(this, options, witness, &LdeStorage::InMemory)
+}
+
+/// Where the prover keeps low degree extensions (LDEs)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LdeStorage {
+    /// LDEs are kept in memory
+    InMemory,
+    /// LDEs are evaluated in row chunks of at most `lde_memory_budget` bytes
+    /// that are dropped once their rows are hashed. Rows opened by queries are
+    /// recomputed from the trace polynomials.
+    ///
+    /// The budget only bounds LDEs. The trace, its polynomials, its
+    /// evaluations over the constraint evaluation domain and the LDE of the
+    /// DEEP composition polynomial are still held in memory.
+    Recompute { lde_memory_budget: usize },
+    /// LDEs are written to memory-mapped files in `dir`. Rows opened by
+    /// queries are read back from the files.
+    #[cfg(feature = "mmap")]
+    Mmap { dir: PathBuf },
+}
+
+/// Proves with low degree extensions (LDEs) kept in `lde_storage`. Every
+/// storage produces the same proof as [`default_prove`] but trades prover time
+/// for peak memory differently (see [`LdeStorage`]).
+#[allow(clippy::too_many_lines)]
+pub fn prove_with_lde_storage<S: Stark>(
+    this: &S,
+    options: ProofOptions,
+    witness: S::Witness,
+    lde_storage: &LdeStorage,
 ) -> Result<Proof<S>, ProvingError> {
     let now = Instant::now();
     let trace = this.generate_trace(witness);
@@ -37,7 +83,8 @@ pub fn default_prove<S: Stark>(
     );
 
     let now = Instant::now();
-    let air = Air::new(trace.len(), this.get_public_inputs(), options);
+    let air = Air::try_new(trace.len(), this.get_public_inputs(), options)
+        .map_err(ProvingError::InvalidOptions)?;
     let public_coin = this.gen_public_coin(&air);
     let mut channel = ProverChannel::<S>::new(&air, public_coin);
     println!("Init air: {:?}", now.elapsed());
@@ -47,27 +94,44 @@ pub fn default_prove<S: Stark>(
     let lde_xs = air.lde_domain();
     let base_trace = trace.base_columns();
     assert_eq!(S::AirConfig::NUM_BASE_COLUMNS, base_trace.num_cols());
-    let base_trace_polys = base_trace.interpolate(trace_xs);
-    let mut base_trace_lde = base_trace_polys.bit_reversed_evaluate(lde_xs);
-    let base_trace_tree = S::MerkleTree::from_matrix(&base_trace_lde);
+    let base_trace_group_polys = base_trace
+        .interpolate(trace_xs)
+        .split(S::AirConfig::BASE_COLUMN_GROUPS);
+    let (base_trace_trees, mut base_trace_ldes): (Vec<S::MerkleTree>, Vec<_>) =
+        base_trace_group_polys
+            .iter()
+            .map(|polys| CommittedLde::commit(polys, lde_xs, lde_storage))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
     println!("Base trace commitment: {:?}", now.elapsed());
 
-    channel.commit_base_trace(base_trace_tree.root());
+    let merkle_cap_height = u32::from(options.merkle_cap_height);
+    channel.commit_base_trace(
+        base_trace_trees
+            .iter()
+            .map(|t| t.cap(merkle_cap_height))
+            .collect(),
+    );
     let num_challenges = air.num_challenges();
     let challenges = Challenges::new(draw_multiple(&mut channel.public_coin, num_challenges));
-    let hints = air.gen_hints(&challenges);
+    let hints = air
+        .gen_hints(&challenges)
+        .map_err(ProvingError::InvalidPublicInputs)?;
 
     let now = Instant::now();
     let extension_trace = trace.build_extension_columns(&challenges);
     let num_extension_cols = extension_trace.as_ref().map_or(0, Matrix::num_cols);
     assert_eq!(S::AirConfig::NUM_EXTENSION_COLUMNS, num_extension_cols);
     let extension_trace_polys = extension_trace.as_ref().map(|t| t.interpolate(trace_xs));
-    let mut extension_trace_lde = extension_trace_polys
-        .as_ref()
-        .map(|p| p.bit_reversed_evaluate(lde_xs));
-    let extension_trace_tree = extension_trace_lde.as_ref().map(S::MerkleTree::from_matrix);
+    let (extension_trace_tree, mut extension_trace_lde): (Option<S::MerkleTree>, Option<_>) =
+        extension_trace_polys
+            .as_ref()
+            .map(|polys| CommittedLde::commit(polys, lde_xs, lde_storage))
+            .transpose()?
+            .unzip();
     if let Some(t) = extension_trace_tree.as_ref() {
-        channel.commit_extension_trace(t.root());
+        channel.commit_extension_trace(t.cap(merkle_cap_height));
     }
     println!("Extension trace commitment: {:?}", now.elapsed());
 
@@ -76,23 +140,27 @@ pub fn default_prove<S: Stark>(
     drop((trace, extension_trace));
 
     let composition_trace_polys: Matrix<S::Fq>;
-    let composition_trace_lde: Matrix<S::Fq>;
     let composition_trace_tree: S::MerkleTree;
+    let composition_trace_lde: CommittedLde<S::Fq>;
     {
-        // To prevent allocating more memory, just re-order the values in the trace to
-        // be in natural order. Note that for the remainder of the protocol the trace
-        // should entirely be in bit-reversed order hence why this function is
-        // called again at the end of the block.
         let ce_lde_xs = air.ce_domain();
-        let ce_domain_size = ce_lde_xs.size();
-        let base_trace_ce_cols = bit_reverse_ce_trace(ce_domain_size, &mut base_trace_lde);
-        let extension_trace_ce_cols = extension_trace_lde
+        let base_trace_ce = zip(&mut base_trace_ldes, &base_trace_group_polys)
+            .map(|(lde, polys)| lde.ce_evaluations(polys, ce_lde_xs))
+            .collect::<Vec<_>>();
+        let extension_trace_ce = extension_trace_lde
             .as_mut()
-            .map(|t| bit_reverse_ce_trace(ce_domain_size, t));
+            .zip(extension_trace_polys.as_ref())
+            .map(|(lde, polys)| lde.ce_evaluations(polys, ce_lde_xs));
+        let base_trace_ce_cols = base_trace_ce
+            .iter()
+            .flat_map(CeEvaluations::columns)
+            .collect::<Vec<_>>();
+        let extension_trace_ce_cols = extension_trace_ce.as_ref().map(CeEvaluations::columns);
 
         let num_composition_coeffs = air.num_composition_constraint_coeffs();
         let composition_coeffs = draw_multiple(&mut channel.public_coin, num_composition_coeffs);
-        let x_lde = ce_lde_xs.elements().collect::<Vec<_>>();
+        let mut x_lde = Vec::with_capacity_in(ce_lde_xs.size(), GpuAllocator);
+        x_lde.extend(ce_lde_xs.elements());
 
         let now = Instant::now();
         let composition_evals = S::AirConfig::eval_constraint(
@@ -101,15 +169,19 @@ pub fn default_prove<S: Stark>(
             &hints,
             &composition_coeffs,
             air.ce_blowup_factor(),
-            x_lde.to_vec_in(GpuAllocator),
+            x_lde,
             &base_trace_ce_cols,
             extension_trace_ce_cols.as_deref(),
         );
+        base_trace_ce.into_iter().for_each(CeEvaluations::restore);
+        if let Some(extension_trace_ce) = extension_trace_ce {
+            extension_trace_ce.restore();
+        }
         println!("Constraint eval: {:?}", now.elapsed());
 
         let now = Instant::now();
         let composition_poly =
-            GpuVec::try_from(composition_evals.into_polynomials(air.ce_domain())).unwrap();
+            GpuVec::try_from(composition_evals.into_polynomials(ce_lde_xs)).unwrap();
         let mut composition_trace_cols = (0..air.ce_blowup_factor())
             .map(|_| Vec::with_capacity_in(air.trace_len(), GpuAllocator))
             .collect::<Vec<_>>();
@@ -119,15 +191,10 @@ pub fn default_prove<S: Stark>(
             }
         }
         composition_trace_polys = Matrix::new(composition_trace_cols);
-        composition_trace_lde = composition_trace_polys.bit_reversed_evaluate(air.lde_domain());
-        composition_trace_tree = S::MerkleTree::from_matrix(&composition_trace_lde);
-        channel.commit_composition_trace(composition_trace_tree.root());
+        (composition_trace_tree, composition_trace_lde) =
+            CommittedLde::commit(&composition_trace_polys, lde_xs, lde_storage)?;
+        channel.commit_composition_trace(composition_trace_tree.cap(merkle_cap_height));
         println!("Composition trace commitment: {:?}", now.elapsed());
-
-        bit_reverse_ce_trace(ce_domain_size, &mut base_trace_lde);
-        extension_trace_lde
-            .as_mut()
-            .map(|t| bit_reverse_ce_trace(ce_domain_size, t));
     }
 
     let now = Instant::now();
@@ -135,7 +202,7 @@ pub fn default_prove<S: Stark>(
     let mut deep_poly_composer = DeepPolyComposer::new(
         &air,
         z,
-        base_trace_polys,
+        Matrix::join(base_trace_group_polys),
         extension_trace_polys,
         composition_trace_polys,
     );
@@ -143,9 +210,11 @@ pub fn default_prove<S: Stark>(
     channel.send_ood_evals(execution_trace_oods, composition_trace_oods);
 
     let deep_coeffs = this.gen_deep_coeffs(&mut channel.public_coin, &air);
-    let deep_composition_poly = deep_poly_composer.into_deep_poly(deep_coeffs);
-    // let deep_xs = Radix2EvaluationDomain::new(lde_xs.size());
+    let deep_composition_poly = deep_poly_composer.deep_poly(deep_coeffs);
     let deep_composition_lde = deep_composition_poly.into_bit_reversed_evaluations(lde_xs);
+    // polynomials are kept to recompute the rows opened by queries
+    let (base_trace_polys, extension_trace_polys, composition_trace_polys) =
+        deep_poly_composer.into_trace_polys();
     println!("Deep composition: {:?}", now.elapsed());
 
     let now = Instant::now();
@@ -161,11 +230,20 @@ pub fn default_prove<S: Stark>(
     let query_positions = Vec::from_iter(channel.get_fri_query_positions());
     let fri_proof = fri_prover.into_proof(&query_positions);
 
-    let queries = Queries::new(
-        &base_trace_lde,
-        extension_trace_lde.as_ref(),
-        &composition_trace_lde,
-        &base_trace_tree,
+    let base_trace_group_polys = base_trace_polys.split(S::AirConfig::BASE_COLUMN_GROUPS);
+    let queries = Queries::from_row_fns(
+        &air,
+        |position| {
+            zip(&base_trace_group_polys, &base_trace_ldes)
+                .flat_map(|(polys, lde)| lde.get_row(polys, lde_xs, position))
+                .collect()
+        },
+        extension_trace_polys
+            .as_ref()
+            .zip(extension_trace_lde.as_ref())
+            .map(|(polys, lde)| move |position| lde.get_row(polys, lde_xs, position)),
+        |position| composition_trace_lde.get_row(&composition_trace_polys, lde_xs, position),
+        &base_trace_trees,
         extension_trace_tree.as_ref(),
         &composition_trace_tree,
         &query_positions,
@@ -177,18 +255,150 @@ pub fn default_prove<S: Stark>(
 #[derive(Debug)]
 pub enum ProvingError {
     Fail,
+    /// Reading or writing a memory-mapped LDE failed
+    Io(std::io::Error),
+    /// The proof options can't be used with the AIR
+    InvalidOptions(air::Error),
+    /// Hints can't be generated for the public inputs
+    InvalidPublicInputs(air::Error),
     // TODO
 }
 
-/// Bit reverses the first ce_domain_size many values of the matrix columns.
-/// Returns a slice to the portion of the columns that were bit reversed
-fn bit_reverse_ce_trace<F: Field>(ce_domain_size: usize, trace: &mut Matrix<F>) -> Vec<&[F]> {
-    trace
-        .0
-        .iter_mut()
-        .map(|column| {
-            bit_reverse(&mut column[0..ce_domain_size]);
-            &column[0..ce_domain_size]
-        })
-        .collect()
+/// Bit reverses the first `ce_domain_size` many values of the matrix columns
+fn bit_reverse_ce_trace<F: Field>(ce_domain_size: usize, trace: &mut Matrix<F>) {
+    for column in trace.iter_mut() {
+        bit_reverse(&mut column[0..ce_domain_size]);
+    }
+}
+
+/// Commits to the bit-reversed evaluations of `polys` over `lde_xs` without
+/// holding them all in memory. Rows are evaluated and hashed in chunks of at
+/// most `lde_memory_budget` bytes (or a single coset of the trace domain if the
+/// budget is smaller).
+fn commit_lde_in_chunks<F, M>(
+    polys: &Matrix<F>,
+    lde_xs: Radix2EvaluationDomain<F::FftField>,
+    lde_memory_budget: usize,
+) -> M
+where
+    F: Field + GpuField + DomainCoeff<F::FftField>,
+    F::FftField: FftField,
+    M: MatrixMerkleTree<F>,
+{
+    let lde_domain_size = lde_xs.size();
+    let lde_size_in_bytes = lde_domain_size * polys.num_cols() * size_of::<F>();
+    let blowup_factor = lde_domain_size / polys.num_rows();
+    let num_chunks = lde_size_in_bytes
+        .div_ceil(lde_memory_budget.max(1))
+        .next_power_of_two()
+        .min(blowup_factor);
+    M::from_row_chunks((0..num_chunks).map(|i| {
+        let chunk_xs = bit_reversed_chunk_domain(lde_xs, num_chunks, i);
+        polys.bit_reversed_evaluate(chunk_xs)
+    }))
+}
+
+/// Low degree extension committed to by [`prove_with_lde_storage`]
+enum CommittedLde<F> {
+    /// Rows are kept in memory
+    InMemory(Matrix<F>),
+    /// Rows are recomputed from the polynomials
+    Recompute(PhantomData<F>),
+    #[cfg(feature = "mmap")]
+    Mmap(MmapMatrix<F>),
+}
+
+impl<F> CommittedLde<F>
+where
+    F: Field + GpuField + DomainCoeff<F::FftField> + From<F::FftField> + Pod,
+    F::FftField: FftField,
+{
+    /// Commits to the bit-reversed evaluations of `polys` over `lde_xs`
+    #[cfg_attr(not(feature = "mmap"), allow(clippy::unnecessary_wraps))]
+    fn commit<M: MatrixMerkleTree<F>>(
+        polys: &Matrix<F>,
+        lde_xs: Radix2EvaluationDomain<F::FftField>,
+        lde_storage: &LdeStorage,
+    ) -> Result<(M, Self), ProvingError> {
+        match lde_storage {
+            LdeStorage::InMemory => {
+                let lde = polys.bit_reversed_evaluate(lde_xs);
+                let tree = M::from_matrix(&lde);
+                Ok((tree, Self::InMemory(lde)))
+            }
+            LdeStorage::Recompute { lde_memory_budget } => {
+                let tree = commit_lde_in_chunks(polys, lde_xs, *lde_memory_budget);
+                Ok((tree, Self::Recompute(PhantomData)))
+            }
+            #[cfg(feature = "mmap")]
+            LdeStorage::Mmap { dir } => {
+                let lde = MmapMatrix::from_bit_reversed_evaluations(polys, lde_xs, dir)
+                    .map_err(ProvingError::Io)?;
+                // only one coset of the trace domain is in memory at a time
+                let tree = M::from_row_chunks(lde.row_chunks(polys.num_rows()));
+                Ok((tree, Self::Mmap(lde)))
+            }
+        }
+    }
+
+    /// Returns the evaluations of `polys` over the constraint evaluation
+    /// domain `ce_xs` in natural order. These are the first rows of a
+    /// bit-reversed LDE so to prevent allocating more memory an in-memory LDE
+    /// is reordered in place until [`CeEvaluations::restore`] is called.
+    fn ce_evaluations(
+        &mut self,
+        polys: &Matrix<F>,
+        ce_xs: Radix2EvaluationDomain<F::FftField>,
+    ) -> CeEvaluations<'_, F> {
+        if let Self::InMemory(lde) = self {
+            bit_reverse_ce_trace(ce_xs.size(), lde);
+            CeEvaluations::Reordered(lde, ce_xs.size())
+        } else {
+            CeEvaluations::Evaluated(polys.evaluate(ce_xs))
+        }
+    }
+
+    /// Returns the row of the LDE at `position`
+    fn get_row(
+        &self,
+        polys: &Matrix<F>,
+        lde_xs: Radix2EvaluationDomain<F::FftField>,
+        position: usize,
+    ) -> Vec<F> {
+        match self {
+            Self::InMemory(lde) => lde.get_row(position).unwrap(),
+            Self::Recompute(_) => {
+                let x = lde_xs.element(bit_reverse_index(lde_xs.size(), position));
+                polys.evaluate_at(F::from(x))
+            }
+            #[cfg(feature = "mmap")]
+            Self::Mmap(lde) => lde.get_row(position).unwrap(),
+        }
+    }
+}
+
+/// Evaluations of trace polynomials over the constraint evaluation domain
+enum CeEvaluations<'a, F> {
+    /// The first `ce_domain_size` rows of an in-memory LDE in natural order
+    Reordered(&'a mut Matrix<F>, usize),
+    Evaluated(Matrix<F>),
+}
+
+impl<F: Field> CeEvaluations<'_, F> {
+    fn columns(&self) -> Vec<&[F]> {
+        match self {
+            Self::Reordered(lde, ce_domain_size) => lde
+                .iter()
+                .map(|column| &column[0..*ce_domain_size])
+                .collect(),
+            Self::Evaluated(evals) => evals.iter().map(|column| &**column).collect(),
+        }
+    }
+
+    /// Restores the bit-reversed order of a reordered LDE
+    fn restore(self) {
+        if let Self::Reordered(lde, ce_domain_size) = self {
+            bit_reverse_ce_trace(ce_domain_size, lde);
+        }
+    }
 }
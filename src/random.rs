@@ -1,15 +1,17 @@
 use crate::hash::Digest;
 use crate::hash::ElementHashFn;
 use crate::hash::HashFn;
+use crate::merkle::MerkleCap;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
 use alloc::vec::Vec;
 use ark_ff::Field;
+use core::fmt::Debug;
+use core::marker::PhantomData;
 use rand::Rng;
 use rand::RngCore;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use std::collections::BTreeSet;
-use std::fmt::Debug;
-use std::marker::PhantomData;
 
 // TODO: alternative approach
 // trait Seedable<T>: Sync + Debug {
@@ -30,8 +32,28 @@ pub trait PublicCoin: Sized + Send + Sync + Debug {
 
     fn new(digest: Self::Digest) -> Self;
 
+    /// Returns a public coin seeded with `seed`. The default reseeds a coin
+    /// with the length of `seed` then each 8 byte chunk of `seed` so
+    /// implementations should override it with a single hash of `seed`.
+    fn from_seed(seed: &[u8]) -> Self {
+        let mut coin = Self::new(Self::Digest::default());
+        coin.reseed_with_int(seed.len() as u64);
+        for chunk in seed.chunks(8) {
+            let mut bytes = [0; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            coin.reseed_with_int(u64::from_le_bytes(bytes));
+        }
+        coin
+    }
+
     fn reseed_with_digest(&mut self, val: &Self::Digest);
 
+    fn reseed_with_merkle_cap(&mut self, cap: &MerkleCap<Self::Digest>) {
+        for digest in cap.iter() {
+            self.reseed_with_digest(digest);
+        }
+    }
+
     fn reseed_with_field_elements(&mut self, vals: &[Self::Field]);
 
     fn reseed_with_field_element_vector(&mut self, vector: &[Self::Field]) {
@@ -42,8 +64,14 @@ pub trait PublicCoin: Sized + Send + Sync + Debug {
 
     fn draw(&mut self) -> Self::Field;
 
-    /// Draws a maximum of n unique queries in the range `[0, domain_size)`
-    fn draw_queries(&mut self, max_n: usize, domain_size: usize) -> BTreeSet<usize>;
+    /// Draws exactly `n` unique queries in the range `[0, domain_size)`.
+    /// Duplicate draws are discarded and drawing continues until there are
+    /// `n` distinct queries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` exceeds `domain_size`.
+    fn draw_queries(&mut self, n: usize, domain_size: usize) -> BTreeSet<usize>;
 
     fn grind_proof_of_work(&self, proof_of_work_bits: u8) -> Option<u64> {
         #[cfg(not(feature = "parallel"))]
@@ -57,6 +85,9 @@ pub trait PublicCoin: Sized + Send + Sync + Debug {
     fn verify_proof_of_work(&self, proof_of_work_bits: u8, nonce: u64) -> bool;
 
     fn security_level_bits() -> u32;
+
+    /// Returns a stable identifier of the hash function
+    fn id() -> String;
 }
 
 pub struct PublicCoinImpl<F: Field, H: HashFn> {
@@ -76,7 +107,7 @@ impl<F: Field, H: ElementHashFn<F>> PublicCoinImpl<F, H> {
 }
 
 impl<F: Field, H: HashFn> Debug for PublicCoinImpl<F, H> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("PublicCoinImpl")
             .field("seed", &self.seed)
             .field("counter", &self.counter)
@@ -108,6 +139,10 @@ impl<F: Field, H: ElementHashFn<F>> PublicCoin for PublicCoinImpl<F, H> {
         }
     }
 
+    fn from_seed(seed: &[u8]) -> Self {
+        Self::new(H::hash_chunks([seed]))
+    }
+
     fn reseed_with_digest(&mut self, val: &H::Digest) {
         self.seed = H::merge(&self.seed, val);
         self.counter = 0;
@@ -135,13 +170,25 @@ impl<F: Field, H: ElementHashFn<F>> PublicCoin for PublicCoinImpl<F, H> {
         F::rand(self)
     }
 
-    fn draw_queries(&mut self, max_n: usize, domain_size: usize) -> BTreeSet<usize> {
-        (0..max_n).map(|_| self.gen_range(0..domain_size)).collect()
+    fn draw_queries(&mut self, n: usize, domain_size: usize) -> BTreeSet<usize> {
+        assert!(
+            n <= domain_size,
+            "can't draw {n} unique queries from {domain_size}"
+        );
+        let mut queries = BTreeSet::new();
+        while queries.len() < n {
+            queries.insert(self.gen_range(0..domain_size));
+        }
+        queries
     }
 
     fn security_level_bits() -> u32 {
         H::COLLISION_RESISTANCE
     }
+
+    fn id() -> String {
+        H::ID.into()
+    }
 }
 
 impl<F: Field, H: HashFn> Iterator for PublicCoinImpl<F, H> {
@@ -194,3 +241,94 @@ pub fn leading_zeros(hash: &[u8]) -> u32 {
 pub fn draw_multiple<P: PublicCoin>(public_coin: &mut P, n: usize) -> Vec<P::Field> {
     (0..n).map(|_| public_coin.draw()).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PublicCoin;
+    use super::PublicCoinImpl;
+    use crate::hash::HashFn;
+    use crate::hash::Sha256HashFn;
+    use alloc::collections::BTreeSet;
+    use alloc::string::String;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    type Digest = <Sha256HashFn as HashFn>::Digest;
+
+    /// Public coin that uses the default [`PublicCoin::from_seed`]
+    #[derive(Debug)]
+    struct DefaultSeedCoin(PublicCoinImpl<Fp, Sha256HashFn>);
+
+    impl PublicCoin for DefaultSeedCoin {
+        type Digest = Digest;
+        type Field = Fp;
+
+        fn new(digest: Digest) -> Self {
+            Self(PublicCoinImpl::new(digest))
+        }
+
+        fn reseed_with_digest(&mut self, val: &Digest) {
+            self.0.reseed_with_digest(val);
+        }
+
+        fn reseed_with_field_elements(&mut self, vals: &[Fp]) {
+            self.0.reseed_with_field_elements(vals);
+        }
+
+        fn reseed_with_int(&mut self, val: u64) {
+            self.0.reseed_with_int(val);
+        }
+
+        fn draw(&mut self) -> Fp {
+            self.0.draw()
+        }
+
+        fn draw_queries(&mut self, n: usize, domain_size: usize) -> BTreeSet<usize> {
+            self.0.draw_queries(n, domain_size)
+        }
+
+        fn verify_proof_of_work(&self, proof_of_work_bits: u8, nonce: u64) -> bool {
+            self.0.verify_proof_of_work(proof_of_work_bits, nonce)
+        }
+
+        fn security_level_bits() -> u32 {
+            PublicCoinImpl::<Fp, Sha256HashFn>::security_level_bits()
+        }
+
+        fn id() -> String {
+            PublicCoinImpl::<Fp, Sha256HashFn>::id()
+        }
+    }
+
+    #[test]
+    fn default_from_seed_binds_the_seed_length() {
+        let seed = DefaultSeedCoin::from_seed(&[1, 2, 3]).0.seed;
+        let padded_seed = DefaultSeedCoin::from_seed(&[1, 2, 3, 0]).0.seed;
+        let other_seed = DefaultSeedCoin::from_seed(&[1, 2, 4]).0.seed;
+
+        assert_eq!(seed, DefaultSeedCoin::from_seed(&[1, 2, 3]).0.seed);
+        assert_ne!(seed, padded_seed);
+        assert_ne!(seed, other_seed);
+    }
+
+    #[test]
+    fn draw_queries_returns_distinct_queries() {
+        let seed = Sha256HashFn::hash([1, 2, 3]);
+        let mut public_coin = PublicCoinImpl::<Fp, Sha256HashFn>::new(seed);
+
+        // collisions are all but guaranteed when drawing 60 of 64 positions
+        let queries = public_coin.draw_queries(60, 64);
+
+        assert_eq!(60, queries.len());
+        assert!(queries.iter().all(|&q| q < 64));
+    }
+
+    #[test]
+    fn draw_queries_can_query_entire_domain() {
+        let seed = Sha256HashFn::hash([1, 2, 3]);
+        let mut public_coin = PublicCoinImpl::<Fp, Sha256HashFn>::new(seed);
+
+        let queries = public_coin.draw_queries(16, 16);
+
+        assert!(queries.into_iter().eq(0..16));
+    }
+}
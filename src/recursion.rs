@@ -0,0 +1,1135 @@
+//! Recursive verification of ministark proofs.
+//!
+//! [`RecursiveStark`] proves that a proof of a fixed inner STARK (an
+//! [`InnerStark`]) is valid. The inner proof must commit to its traces and FRI
+//! layers with [`RescueHashFn`] merkle trees so the outer AIR can check:
+//!
+//! * merkle paths of the base trace, composition trace and FRI layer queries
+//! * the DEEP composition polynomial at each query reconstructed from the
+//!   queried trace values
+//! * FRI folding of each layer and the remainder evaluations
+//! * the inner constraints evaluated at the out-of-domain point against the
+//!   composition trace
+//!
+//! The outer verifier replays the inner public coin natively (out of circuit)
+//! from the [`InnerProofHeader`] i.e. the commitments, out-of-domain
+//! evaluations, FRI remainder and proof-of-work nonce. The challenges, query
+//! positions and DEEP/FRI coefficients derived from the header are supplied to
+//! the outer AIR as hints. Only the (much larger) query decommitments are
+//! verified in the outer AIR.
+//!
+//! ## Layout
+//!
+//! Each row of the outer trace holds a Rescue-Prime state along with a merkle
+//! path bit, a leaf index accumulator and the out-of-domain constraint
+//! evaluation. A permutation (a "job") spans 8 rows: the input state followed
+//! by the state after each round. Every query of the inner proof gets a block
+//! of jobs:
+//!
+//! ```text
+//! ┌──────┬───────────┬──────┬───────────┬──────┬───────────┬─────┬─────────┐
+//! │ base │ base      │ comp │ comp      │ FRI  │ FRI layer │ ... │ padding │
+//! │ leaf │ merges... │ leaf │ merges... │ leaf │ merges... │     │         │
+//! └──────┴───────────┴──────┴───────────┴──────┴───────────┴─────┴─────────┘
+//! ```
+//!
+//! Blocks are padded to a power of two number of jobs and the number of blocks
+//! is padded to a power of two by repeating the first query.
+//!
+//! Requirements on the inner STARK (checked when building the AIR):
+//! * no extension trace and a single base column group of at most 8 columns
+//! * a constraint evaluation blowup factor of at most 8
+//! * a FRI folding factor of 2 and merkle caps of height 0
+
+use crate::air;
+use crate::air::AirConfig;
+use crate::air::PreparedAir;
+use crate::challenges::Challenges;
+use crate::channel::VerifierChannelArtifacts;
+use crate::composer::DeepCompositionCoeffs;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::CompositionConstraint;
+use crate::constraints::CompositionItem;
+use crate::constraints::Constraint;
+use crate::constraints::ExecutionTraceColumn;
+use crate::constraints::PeriodicColumn;
+use crate::expression::Expr;
+use crate::fri;
+use crate::fri::fold_positions;
+use crate::gadgets::merkle::merkle_paths;
+use crate::gadgets::periodic_column;
+use crate::gadgets::rescue::permutation_rows;
+use crate::gadgets::rescue::PERMUTATION_ROWS;
+use crate::gadgets::RescuePermutation;
+use crate::hash::rescue::RescueDigest;
+use crate::hash::rescue::CAPACITY;
+use crate::hash::rescue::DIGEST_SIZE;
+use crate::hash::rescue::RATE;
+use crate::hash::rescue::STATE_WIDTH;
+use crate::hash::RescueHashFn;
+use crate::hash::Sha256HashFn;
+use crate::hints::Hints;
+use crate::merkle::MatrixMerkleTreeImpl;
+use crate::merkle::MerkleCap;
+use crate::merkle::MerkleTree;
+use crate::merkle::MerkleView;
+use crate::random::draw_multiple;
+use crate::random::PublicCoin;
+use crate::random::PublicCoinImpl;
+use crate::security::SecurityModel;
+use crate::security::SecurityParams;
+use crate::stark::Stark;
+use crate::trace::Trace;
+use crate::utils::horner_evaluate;
+use crate::utils::FieldVariant;
+use crate::utils::SerdeOutput;
+use crate::verifier::default_verify_with_prepared_air;
+use crate::verifier::TraceKind;
+use crate::verifier::VerificationError;
+use crate::verifier::VerifierExplanation;
+use crate::Air;
+use crate::Matrix;
+use crate::Proof;
+use crate::ProofOptions;
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use core::iter::zip;
+use core::marker::PhantomData;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use ministark_gpu::utils::bit_reverse_index;
+use num_traits::Pow;
+use sha2::Sha256;
+
+/// Number of rows of each Rescue-Prime permutation
+const JOB_SIZE: usize = PERMUTATION_ROWS;
+
+/// Outer trace columns
+const STATE: usize = 0;
+/// Merkle path direction. `1` if the node hashed by the previous job is the
+/// right child.
+const BIT: usize = STATE + STATE_WIDTH;
+/// Leaf index accumulated from the merkle path directions
+const IDX: usize = BIT + 1;
+/// Inner composition constraint evaluated at the out-of-domain point
+const OOD: usize = IDX + 1;
+const NUM_COLUMNS: usize = OOD + 1;
+
+type InnerPublicInputs<S> = <<S as Stark>::AirConfig as AirConfig>::PublicInputs;
+
+/// A STARK whose proofs can be verified by [`RecursiveStark`]. Proofs must be
+/// generated with [`Self::OPTIONS`] and have a trace length of
+/// [`Self::TRACE_LEN`].
+pub trait InnerStark:
+    Stark<Fp = Fp, Fq = Fp, Digest = RescueDigest, MerkleTree = MatrixMerkleTreeImpl<RescueHashFn>>
+    + 'static
+{
+    const TRACE_LEN: usize;
+    const OPTIONS: ProofOptions;
+
+    /// Returns the claim with the given public inputs. Used by the outer
+    /// verifier to replay the public coin of the inner proof.
+    fn from_public_inputs(public_inputs: &InnerPublicInputs<Self>) -> Self;
+}
+
+/// Parts of an inner proof that the outer verifier reads directly
+#[derive(Debug, Clone, CanonicalSerialize, CanonicalDeserialize)]
+pub struct InnerProofHeader<P: CanonicalSerialize + CanonicalDeserialize> {
+    pub public_inputs: P,
+    pub base_trace_root: RescueDigest,
+    pub composition_trace_root: RescueDigest,
+    pub fri_layer_roots: Vec<RescueDigest>,
+    pub fri_remainder_coeffs: Vec<Fp>,
+    pub execution_trace_ood_evals: Vec<Fp>,
+    pub composition_trace_ood_evals: Vec<Fp>,
+    pub pow_nonce: u64,
+}
+
+impl<P: CanonicalSerialize + CanonicalDeserialize + Clone> InnerProofHeader<P> {
+    /// # Panics
+    /// Panics if the proof wasn't generated with the inner STARK's options and
+    /// trace length.
+    pub fn new<S>(claim: &S, proof: &Proof<S>) -> Self
+    where
+        S: InnerStark,
+        S::AirConfig: AirConfig<PublicInputs = P>,
+    {
+        assert_eq!(S::OPTIONS, proof.options, "proof options don't match");
+        assert_eq!(S::TRACE_LEN, proof.trace_len, "trace length doesn't match");
+        let root = |cap: &MerkleCap<RescueDigest>| cap.root().expect("cap height must be 0");
+        Self {
+            public_inputs: claim.get_public_inputs(),
+            base_trace_root: root(&proof.base_trace_commitments[0]),
+            composition_trace_root: root(&proof.composition_trace_commitment),
+            fri_layer_roots: proof
+                .fri_proof
+                .layers
+                .iter()
+                .map(|layer| root(&layer.commitment))
+                .collect(),
+            fri_remainder_coeffs: proof.fri_proof.remainder_coeffs.clone(),
+            execution_trace_ood_evals: proof.execution_trace_ood_evals.clone(),
+            composition_trace_ood_evals: proof.composition_trace_ood_evals.clone(),
+            pow_nonce: proof.pow_nonce,
+        }
+    }
+
+    fn root(&self, tree: Tree) -> RescueDigest {
+        match tree {
+            Tree::BaseTrace => self.base_trace_root,
+            Tree::CompositionTrace => self.composition_trace_root,
+            Tree::FriLayer(layer) => self.fri_layer_roots[layer],
+        }
+    }
+}
+
+/// Proves an inner proof of `S` is valid. The outer proof uses SHA-256 merkle
+/// trees.
+pub struct RecursiveStark<S: InnerStark>(pub InnerProofHeader<InnerPublicInputs<S>>);
+
+impl<S: InnerStark> RecursiveStark<S> {
+    pub fn new(claim: &S, proof: &Proof<S>) -> Self {
+        Self(InnerProofHeader::new(claim, proof))
+    }
+
+    /// Returns the trace length of the outer proof
+    pub fn trace_len() -> usize {
+        Layout::new::<S>().trace_len()
+    }
+}
+
+impl<S: InnerStark> Stark for RecursiveStark<S>
+where
+    InnerPublicInputs<S>: Send + Sync,
+{
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = RecursiveAirConfig<S>;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = Proof<S>;
+    type Trace = RecursiveTrace;
+
+    fn get_public_inputs(&self) -> InnerProofHeader<InnerPublicInputs<S>> {
+        self.0.clone()
+    }
+
+    /// Returns a trace of zeros if the header is invalid or doesn't match the
+    /// proof. Proving then fails when the hints are generated from the invalid
+    /// header or produces a proof that doesn't verify.
+    fn generate_trace(&self, proof: Proof<S>) -> RecursiveTrace {
+        let layout = Layout::new::<S>();
+        let trace = gen_trace::<S>(&layout, &self.0, &proof).unwrap_or_else(|| {
+            let rows = vec![[Fp::zero(); NUM_COLUMNS]; layout.trace_len()];
+            Matrix::from_arrays(&rows)
+        });
+        RecursiveTrace(trace)
+    }
+
+    /// The constraints depend on the inner AIR so its id is included
+    fn air_id(&self) -> String {
+        format!("{}({})", Self::AirConfig::AIR_ID, S::AirConfig::AIR_ID)
+    }
+
+    /// Checks the inner proof's header natively then verifies the outer proof.
+    /// The inner proof must also satisfy `required_security_bits`.
+    fn verify_with_prepared_air(
+        &self,
+        prepared_air: &Arc<PreparedAir<Self::AirConfig>>,
+        proof: Proof<Self>,
+        required_security_bits: u32,
+        security_model: SecurityModel,
+        explanation: &mut VerifierExplanation<Fp>,
+    ) -> Result<VerifierChannelArtifacts<Fp>, VerificationError> {
+        let inner_air = Air::new(S::TRACE_LEN, self.0.public_inputs.clone(), S::OPTIONS);
+        let security_params = SecurityParams::new::<S>(&inner_air);
+        let inner_security_bits = security_params.security_level_bits(security_model);
+        if inner_security_bits < required_security_bits {
+            return Err(VerificationError::InvalidProofSecurity {
+                required: required_security_bits,
+                actual: inner_security_bits,
+            });
+        }
+        InnerTranscript::replay::<S>(&self.0, &Layout::new::<S>())?;
+        default_verify_with_prepared_air(
+            self,
+            prepared_air,
+            proof,
+            required_security_bits,
+            security_model,
+            explanation,
+        )
+    }
+}
+
+pub struct RecursiveTrace(Matrix<Fp>);
+
+impl Trace for RecursiveTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+pub struct RecursiveAirConfig<S: InnerStark>(PhantomData<S>);
+
+impl<S: InnerStark> AirConfig for RecursiveAirConfig<S> {
+    const AIR_ID: &'static str = "recursive-verifier";
+    const NUM_BASE_COLUMNS: usize = NUM_COLUMNS;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = InnerProofHeader<InnerPublicInputs<S>>;
+
+    fn gen_hints(
+        _trace_len: usize,
+        header: &InnerProofHeader<InnerPublicInputs<S>>,
+        _challenges: &Challenges<Fp>,
+    ) -> Result<Hints<Fp>, air::Error> {
+        let layout = Layout::new::<S>();
+        let transcript = InnerTranscript::replay::<S>(header, &layout).map_err(|error| {
+            air::Error::InvalidPublicInputs {
+                reason: error.to_string(),
+            }
+        })?;
+        let mut hints = vec![Fp::zero(); layout.num_hints()];
+
+        for (t, tree) in layout.trees().enumerate() {
+            let root = header.root(tree);
+            for (k, v) in root.0.into_iter().enumerate() {
+                hints[Layout::root_hint(t, k)] = v;
+            }
+        }
+
+        let num_queries = transcript.query_positions.len();
+        for slot in 0..layout.num_query_slots {
+            // unused slots repeat the first query
+            let query = if slot < num_queries { slot } else { 0 };
+            let position = transcript.query_positions[query];
+            for (t, tree) in layout.trees().enumerate() {
+                let leaf_index = Layout::leaf_index(tree, position);
+                hints[layout.leaf_index_hint(slot, t)] = Fp::from(leaf_index as u64);
+            }
+            for layer in 0..layout.num_fri_layers {
+                let bit = (position >> layer) & 1;
+                hints[layout.bit_hint(slot, layer)] = Fp::from(bit as u64);
+            }
+            let deep = transcript.deep_weights(&layout, header, position);
+            for (c, v) in deep.base_trace.into_iter().enumerate() {
+                hints[layout.deep_base_trace_hint(slot, c)] = v;
+            }
+            for (i, v) in deep.composition_trace.into_iter().enumerate() {
+                hints[layout.deep_composition_trace_hint(slot, i)] = v;
+            }
+            hints[layout.deep_constant_hint(slot)] = deep.constant;
+            let (fold_coeffs, remainder) = transcript.fri_values(&layout, header, position);
+            for (layer, (k0, k1)) in fold_coeffs.into_iter().enumerate() {
+                let (k0_hint, k1_hint) = layout.fold_hints(slot, layer);
+                hints[k0_hint] = k0;
+                hints[k1_hint] = k1;
+            }
+            hints[layout.remainder_hint(slot)] = remainder;
+        }
+
+        hints[layout.z_hint()] = transcript.z;
+        for (i, &v) in transcript.composition_coeffs.iter().enumerate() {
+            hints[layout.composition_coeff_hint(i)] = v;
+        }
+        for (i, &v) in transcript.challenges.iter().enumerate() {
+            hints[layout.challenge_hint(i)] = v;
+        }
+        for i in 0..layout.num_inner_hints {
+            hints[layout.inner_hint(i)] = transcript.hints[i];
+        }
+        for (i, col) in layout.periodic_columns.iter().enumerate() {
+            let point = transcript
+                .z
+                .pow([(S::TRACE_LEN / col.interval_size()) as u64]);
+            let coeffs = col
+                .coeffs()
+                .iter()
+                .map(FieldVariant::as_fq)
+                .collect::<Vec<Fp>>();
+            hints[layout.periodic_hint(i)] = horner_evaluate(&coeffs, &point);
+        }
+        for (j, &v) in header.execution_trace_ood_evals.iter().enumerate() {
+            hints[layout.trace_ood_hint(j)] = v;
+        }
+        for (i, &v) in header.composition_trace_ood_evals.iter().enumerate() {
+            hints[layout.composition_ood_hint(i)] = v;
+        }
+
+        Ok(Hints::new(hints.into_iter().enumerate().collect()))
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        use AlgebraicItem::*;
+        let layout = Layout::new::<S>();
+        assert_eq!(
+            layout.trace_len(),
+            trace_len,
+            "trace length must match the layout"
+        );
+        let trace_domain = Radix2EvaluationDomain::<Fp>::new(trace_len).unwrap();
+        let g = trace_domain.group_gen();
+        let constant = |v: Fp| Expr::from(Constant(FieldVariant::Fp(v)));
+        let hint = |i: usize| Expr::from(Hint(i));
+        let one = constant(Fp::one());
+        let x = Expr::from(X);
+        let every_row = x.clone().pow(trace_len) - &one;
+        let at_row = |row: usize| &x - constant(g.pow([row as u64]));
+
+        let mut constraints = Vec::new();
+
+        // Loading the input of each permutation. Leaves absorb the queried row and
+        // merges absorb two digests with the previous job's digest on the side
+        // given by the path bit.
+        let load = layout.load_column(|job| Fp::from(u8::from(!matches!(job, Job::Padding))));
+        let merge = layout.load_column(|job| Fp::from(u8::from(matches!(job, Job::Merge(..)))));
+        let weight = layout.load_column(|job| match job {
+            Job::Merge(_, level) => Fp::from(1u64 << level),
+            _ => Fp::zero(),
+        });
+        let len = layout.load_column(|job| match job {
+            Job::Leaf(tree) => Fp::from(layout.leaf_width(tree) as u64),
+            Job::Merge(..) => Fp::from(RATE as u64),
+            Job::Padding => Fp::zero(),
+        });
+        let leaf = |width: usize| {
+            layout.load_column(|job| match job {
+                Job::Leaf(tree) => Fp::from(u8::from(layout.leaf_width(tree) == width)),
+                _ => Fp::zero(),
+            })
+        };
+        let mut leaf_widths = layout
+            .trees()
+            .map(|tree| layout.leaf_width(tree))
+            .collect::<Vec<_>>();
+        leaf_widths.sort_unstable();
+        leaf_widths.dedup();
+        let capacity = STATE..STATE + CAPACITY;
+        let rate = |i: usize| STATE + CAPACITY + i;
+        let bit = BIT.curr();
+        constraints.push(&load * ((STATE).curr() - len) / &every_row);
+        for i in capacity.skip(1) {
+            constraints.push(&load * i.curr() / &every_row);
+        }
+        let mut any_leaf = constant(Fp::zero());
+        for width in leaf_widths {
+            let leaf = leaf(width);
+            for i in width..RATE {
+                constraints.push(&leaf * rate(i).curr() / &every_row);
+            }
+            any_leaf += leaf;
+        }
+        constraints.push(any_leaf * IDX.curr() / &every_row);
+        constraints.push(&merge * (&bit * &bit - &bit) / &every_row);
+        for i in 0..DIGEST_SIZE {
+            let prev_digest = rate(i).offset(-1);
+            let left = rate(i).curr() - &prev_digest;
+            let right = rate(DIGEST_SIZE + i).curr() - prev_digest;
+            constraints.push(&merge * (&one - &bit) * left / &every_row);
+            constraints.push(&merge * &bit * right / &every_row);
+        }
+        let prev_idx = IDX.offset(-isize::try_from(JOB_SIZE).unwrap());
+        constraints.push((&merge * (IDX.curr() - prev_idx) - weight * &bit) / &every_row);
+
+        // merkle roots
+        let num_slots = layout.num_query_slots;
+        for (t, tree) in layout.trees().enumerate() {
+            let row = JOB_SIZE * layout.root_job(tree) + JOB_SIZE - 1;
+            let every_block =
+                x.clone().pow(num_slots) - constant(g.pow([(row * num_slots) as u64]));
+            for i in 0..DIGEST_SIZE {
+                let root = hint(Layout::root_hint(t, i));
+                constraints.push((rate(i).curr() - root) / &every_block);
+            }
+        }
+
+        // queries
+        let num_layers = layout.num_fri_layers;
+        for slot in 0..num_slots {
+            let block_row = slot * layout.block_size();
+            for (t, tree) in layout.trees().enumerate() {
+                let row = block_row + JOB_SIZE * layout.root_job(tree);
+                let leaf_index = hint(layout.leaf_index_hint(slot, t));
+                constraints.push((IDX.curr() - leaf_index) / at_row(row));
+            }
+
+            // value of the FRI layer (or remainder) at the query relative to `job`
+            let layer_value = |layer: usize, job: usize| {
+                if layer == num_layers {
+                    hint(layout.remainder_hint(slot))
+                } else {
+                    let offset = JOB_SIZE * (layout.leaf_job(Tree::FriLayer(layer)) - job);
+                    let offset = isize::try_from(offset).unwrap();
+                    let bit = hint(layout.bit_hint(slot, layer));
+                    (&one - &bit) * rate(0).offset(offset) + bit * rate(1).offset(offset)
+                }
+            };
+
+            // DEEP composition
+            let composition_offset = JOB_SIZE * layout.leaf_job(Tree::CompositionTrace);
+            let composition_offset = isize::try_from(composition_offset).unwrap();
+            let base_trace = (0..layout.num_base_columns)
+                .map(|c| hint(layout.deep_base_trace_hint(slot, c)) * rate(c).curr())
+                .sum::<Expr<_>>();
+            let composition_trace = (0..layout.ce_blowup_factor)
+                .map(|i| {
+                    let weight = hint(layout.deep_composition_trace_hint(slot, i));
+                    weight * rate(i).offset(composition_offset)
+                })
+                .sum::<Expr<_>>();
+            let deep = base_trace + composition_trace + hint(layout.deep_constant_hint(slot));
+            constraints.push((deep - layer_value(0, 0)) / at_row(block_row));
+
+            // FRI folding
+            for layer in 0..num_layers {
+                let job = layout.leaf_job(Tree::FriLayer(layer));
+                let (k0, k1) = layout.fold_hints(slot, layer);
+                let folded = hint(k0) * rate(0).curr() + hint(k1) * rate(1).curr();
+                let row = block_row + JOB_SIZE * job;
+                constraints.push((folded - layer_value(layer + 1, job)) / at_row(row));
+            }
+        }
+
+        // inner constraints at the out-of-domain point
+        let ood_evaluation = layout
+            .composition_constraint
+            .map_leaves(&mut |leaf| match *leaf {
+                CompositionItem::Item(X) => Hint(layout.z_hint()),
+                CompositionItem::Item(Constant(v)) => Constant(v),
+                CompositionItem::Item(Challenge(i)) => Hint(layout.challenge_hint(i)),
+                CompositionItem::Item(Hint(i)) => Hint(layout.inner_hint(i)),
+                CompositionItem::Item(Periodic(ref col)) => {
+                    let i = layout.periodic_columns.iter().position(|c| c == col);
+                    Hint(layout.periodic_hint(i.unwrap()))
+                }
+                CompositionItem::Item(Trace(col, offset)) => {
+                    let j = layout
+                        .trace_arguments
+                        .iter()
+                        .position(|a| *a == (col, offset));
+                    Hint(layout.trace_ood_hint(j.unwrap()))
+                }
+                CompositionItem::CompositionCoeff(i) => Hint(layout.composition_coeff_hint(i)),
+            });
+        let z = hint(layout.z_hint());
+        let composition_trace_evaluation = (0..layout.ce_blowup_factor)
+            .map(|i| hint(layout.composition_ood_hint(i)) * (&z).pow(i))
+            .sum::<Expr<_>>();
+        constraints.push((ood_evaluation - OOD.curr()) / at_row(0));
+        constraints.push((composition_trace_evaluation - OOD.curr()) / at_row(0));
+
+        // Rescue-Prime rounds
+        let rounds = RescuePermutation::new(STATE).constraints(trace_len);
+        rounds
+            .into_iter()
+            .chain(constraints.into_iter().map(Constraint::new))
+            .collect()
+    }
+}
+
+/// Merkle tree of the inner proof
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tree {
+    BaseTrace,
+    CompositionTrace,
+    FriLayer(usize),
+}
+
+/// A Rescue-Prime permutation in the outer trace
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Job {
+    /// Hashes the queried row of a tree
+    Leaf(Tree),
+    /// Merges the previous job's digest with its sibling at the given level
+    /// above the leaves
+    Merge(Tree, u32),
+    Padding,
+}
+
+/// Layout of the outer trace and hints for verifying proofs of an inner STARK
+struct Layout {
+    num_base_columns: usize,
+    ce_blowup_factor: usize,
+    lde_domain_size: usize,
+    num_fri_layers: usize,
+    num_queries: usize,
+    num_query_slots: usize,
+    /// Jobs of each query's block
+    jobs: Vec<Job>,
+    composition_constraint: CompositionConstraint<FieldVariant<Fp, Fp>>,
+    num_composition_coeffs: usize,
+    num_challenges: usize,
+    num_inner_hints: usize,
+    periodic_columns: Vec<PeriodicColumn<FieldVariant<Fp, Fp>>>,
+    trace_arguments: Vec<(usize, isize)>,
+}
+
+impl Layout {
+    fn new<S: InnerStark>() -> Self {
+        let options = S::OPTIONS;
+        let trace_len = S::TRACE_LEN;
+        assert_eq!(
+            0,
+            S::AirConfig::NUM_EXTENSION_COLUMNS,
+            "extension columns unsupported"
+        );
+        assert_eq!(
+            1,
+            S::AirConfig::BASE_COLUMN_GROUPS.len(),
+            "column groups unsupported"
+        );
+        assert!(
+            S::AirConfig::NUM_BASE_COLUMNS <= RATE,
+            "too many base columns"
+        );
+        assert_eq!(
+            2, options.fri_folding_factor,
+            "FRI folding factor must be 2"
+        );
+        assert_eq!(0, options.merkle_cap_height, "merkle cap height must be 0");
+
+        let constraints = S::AirConfig::constraints(trace_len);
+        let composition_constraint = S::AirConfig::composition_constraint(trace_len, &constraints);
+        let ce_blowup_factor = composition_constraint.blowup_factor(trace_len);
+        assert!(ce_blowup_factor <= RATE, "constraint degree too high");
+
+        let mut num_composition_coeffs = 0;
+        let mut num_challenges = 0;
+        let mut num_inner_hints = 0;
+        let mut periodic_columns = BTreeSet::new();
+        let mut trace_arguments = BTreeSet::new();
+        composition_constraint.traverse(&mut |node| match *node {
+            Expr::Leaf(CompositionItem::CompositionCoeff(i)) => {
+                num_composition_coeffs = num_composition_coeffs.max(i + 1);
+            }
+            Expr::Leaf(CompositionItem::Item(AlgebraicItem::Challenge(i))) => {
+                num_challenges = num_challenges.max(i + 1);
+            }
+            Expr::Leaf(CompositionItem::Item(AlgebraicItem::Hint(i))) => {
+                num_inner_hints = num_inner_hints.max(i + 1);
+            }
+            Expr::Leaf(CompositionItem::Item(AlgebraicItem::Periodic(ref col))) => {
+                periodic_columns.insert(col.clone());
+            }
+            Expr::Leaf(CompositionItem::Item(AlgebraicItem::Trace(i, j))) => {
+                trace_arguments.insert((i, j));
+            }
+            _ => {}
+        });
+
+        let lde_domain_size = trace_len * usize::from(options.lde_blowup_factor);
+        let num_fri_layers = options.into_fri_options().num_layers(lde_domain_size);
+        let num_queries = usize::from(options.num_queries).min(lde_domain_size);
+        let mut layout = Self {
+            num_base_columns: S::AirConfig::NUM_BASE_COLUMNS,
+            ce_blowup_factor,
+            lde_domain_size,
+            num_fri_layers,
+            num_queries,
+            num_query_slots: num_queries.next_power_of_two(),
+            jobs: Vec::new(),
+            composition_constraint,
+            num_composition_coeffs,
+            num_challenges,
+            num_inner_hints,
+            periodic_columns: periodic_columns.into_iter().collect(),
+            trace_arguments: trace_arguments.into_iter().collect(),
+        };
+
+        let mut jobs = Vec::new();
+        for tree in layout.trees() {
+            jobs.push(Job::Leaf(tree));
+            for level in 0..layout.tree_height(tree) {
+                jobs.push(Job::Merge(tree, level));
+            }
+        }
+        jobs.resize(jobs.len().next_power_of_two(), Job::Padding);
+        layout.jobs = jobs;
+        layout
+    }
+
+    const fn block_size(&self) -> usize {
+        JOB_SIZE * self.jobs.len()
+    }
+
+    const fn trace_len(&self) -> usize {
+        self.block_size() * self.num_query_slots
+    }
+
+    fn trees(&self) -> impl Iterator<Item = Tree> {
+        let fri_layers = (0..self.num_fri_layers).map(Tree::FriLayer);
+        [Tree::BaseTrace, Tree::CompositionTrace]
+            .into_iter()
+            .chain(fri_layers)
+    }
+
+    const fn num_trees(&self) -> usize {
+        2 + self.num_fri_layers
+    }
+
+    /// Returns the number of levels between the leaves and the root of a tree
+    const fn tree_height(&self, tree: Tree) -> u32 {
+        let height = self.lde_domain_size.ilog2();
+        match tree {
+            Tree::BaseTrace | Tree::CompositionTrace => height,
+            #[allow(clippy::cast_possible_truncation)]
+            Tree::FriLayer(layer) => height - 1 - layer as u32,
+        }
+    }
+
+    /// Returns the number of field elements in each leaf of a tree
+    const fn leaf_width(&self, tree: Tree) -> usize {
+        match tree {
+            Tree::BaseTrace => self.num_base_columns,
+            Tree::CompositionTrace => self.ce_blowup_factor,
+            Tree::FriLayer(_) => 2,
+        }
+    }
+
+    /// Returns the index of the leaf of a tree opened for the query at
+    /// `position` in the LDE domain
+    const fn leaf_index(tree: Tree, position: usize) -> usize {
+        match tree {
+            Tree::BaseTrace | Tree::CompositionTrace => position,
+            Tree::FriLayer(layer) => position >> (layer + 1),
+        }
+    }
+
+    fn leaf_job(&self, tree: Tree) -> usize {
+        self.jobs
+            .iter()
+            .position(|&job| job == Job::Leaf(tree))
+            .unwrap()
+    }
+
+    /// Returns the job that outputs the root of a tree
+    fn root_job(&self, tree: Tree) -> usize {
+        self.leaf_job(tree) + self.tree_height(tree) as usize
+    }
+
+    /// Returns a periodic column with the values of `f` on the first row of
+    /// each job in a block and zero elsewhere
+    fn load_column(&self, f: impl Fn(Job) -> Fp) -> Expr<AlgebraicItem<FieldVariant<Fp, Fp>>> {
+        let mut values = vec![Fp::zero(); self.block_size()];
+        for (i, &job) in self.jobs.iter().enumerate() {
+            values[JOB_SIZE * i] = f(job);
+        }
+        periodic_column(values)
+    }
+
+    // Hints are laid out as:
+    // 1. roots of each tree
+    // 2. for each query slot: leaf indices, path bits of the FRI layer values, DEEP
+    //    weights, FRI folding coefficients and the remainder evaluation
+    // 3. values used to evaluate the inner constraints at the OOD point
+
+    const fn root_hint(tree: usize, i: usize) -> usize {
+        DIGEST_SIZE * tree + i
+    }
+
+    const fn query_hints(&self, slot: usize) -> usize {
+        let num_query_hints = self.num_trees()
+            + self.num_fri_layers
+            + self.num_base_columns
+            + self.ce_blowup_factor
+            + 1
+            + 2 * self.num_fri_layers
+            + 1;
+        DIGEST_SIZE * self.num_trees() + slot * num_query_hints
+    }
+
+    const fn leaf_index_hint(&self, slot: usize, tree: usize) -> usize {
+        self.query_hints(slot) + tree
+    }
+
+    const fn bit_hint(&self, slot: usize, layer: usize) -> usize {
+        self.leaf_index_hint(slot, self.num_trees()) + layer
+    }
+
+    const fn deep_base_trace_hint(&self, slot: usize, column: usize) -> usize {
+        self.bit_hint(slot, self.num_fri_layers) + column
+    }
+
+    const fn deep_composition_trace_hint(&self, slot: usize, column: usize) -> usize {
+        self.deep_base_trace_hint(slot, self.num_base_columns) + column
+    }
+
+    const fn deep_constant_hint(&self, slot: usize) -> usize {
+        self.deep_composition_trace_hint(slot, self.ce_blowup_factor)
+    }
+
+    const fn fold_hints(&self, slot: usize, layer: usize) -> (usize, usize) {
+        let i = self.deep_constant_hint(slot) + 1 + 2 * layer;
+        (i, i + 1)
+    }
+
+    const fn remainder_hint(&self, slot: usize) -> usize {
+        self.fold_hints(slot, self.num_fri_layers).0
+    }
+
+    const fn z_hint(&self) -> usize {
+        self.query_hints(self.num_query_slots)
+    }
+
+    const fn composition_coeff_hint(&self, i: usize) -> usize {
+        self.z_hint() + 1 + i
+    }
+
+    const fn challenge_hint(&self, i: usize) -> usize {
+        self.composition_coeff_hint(self.num_composition_coeffs) + i
+    }
+
+    const fn inner_hint(&self, i: usize) -> usize {
+        self.challenge_hint(self.num_challenges) + i
+    }
+
+    const fn periodic_hint(&self, i: usize) -> usize {
+        self.inner_hint(self.num_inner_hints) + i
+    }
+
+    const fn trace_ood_hint(&self, i: usize) -> usize {
+        self.periodic_hint(self.periodic_columns.len()) + i
+    }
+
+    const fn composition_ood_hint(&self, i: usize) -> usize {
+        self.trace_ood_hint(self.trace_arguments.len()) + i
+    }
+
+    const fn num_hints(&self) -> usize {
+        self.composition_ood_hint(self.ce_blowup_factor)
+    }
+}
+
+/// Verifier randomness of the inner proof
+struct InnerTranscript {
+    trace_domain: Radix2EvaluationDomain<Fp>,
+    lde_domain: Radix2EvaluationDomain<Fp>,
+    challenges: Challenges<Fp>,
+    hints: Hints<Fp>,
+    composition_coeffs: Vec<Fp>,
+    z: Fp,
+    deep_coeffs: DeepCompositionCoeffs<Fp>,
+    fri_alphas: Vec<Fp>,
+    query_positions: Vec<usize>,
+}
+
+/// DEEP composition polynomial at a query as a linear combination of the
+/// queried base and composition trace values
+struct DeepWeights {
+    base_trace: Vec<Fp>,
+    composition_trace: Vec<Fp>,
+    constant: Fp,
+}
+
+impl InnerTranscript {
+    /// Replays the public coin of the inner proof. Checks the parts of the
+    /// inner proof that are verified natively i.e. the shape of the header,
+    /// the degree of the FRI remainder and the proof of work.
+    fn replay<S: InnerStark>(
+        header: &InnerProofHeader<InnerPublicInputs<S>>,
+        layout: &Layout,
+    ) -> Result<Self, VerificationError> {
+        use VerificationError::*;
+
+        let num_trace_ood_evals = header.execution_trace_ood_evals.len();
+        if num_trace_ood_evals != layout.trace_arguments.len() {
+            return Err(InvalidNumOodEvals {
+                trace: TraceKind::Base,
+                expected: layout.trace_arguments.len(),
+                actual: num_trace_ood_evals,
+            });
+        }
+        let num_composition_ood_evals = header.composition_trace_ood_evals.len();
+        if num_composition_ood_evals != layout.ce_blowup_factor {
+            return Err(InvalidNumOodEvals {
+                trace: TraceKind::Composition,
+                expected: layout.ce_blowup_factor,
+                actual: num_composition_ood_evals,
+            });
+        }
+        if header.fri_layer_roots.len() != layout.num_fri_layers {
+            let source = fri::VerificationError::NumLayersMismatch {
+                expected: layout.num_fri_layers,
+                actual: header.fri_layer_roots.len(),
+            };
+            return Err(FriVerification { source });
+        }
+        let remainder_domain_size = layout.lde_domain_size >> layout.num_fri_layers;
+        let remainder_degree =
+            remainder_domain_size / usize::from(S::OPTIONS.lde_blowup_factor) - 1;
+        let coeffs = &header.fri_remainder_coeffs;
+        if coeffs
+            .iter()
+            .skip(remainder_degree + 1)
+            .any(|c| !c.is_zero())
+        {
+            let source = fri::VerificationError::RemainderDegreeMismatch {
+                degree: remainder_degree,
+            };
+            return Err(FriVerification { source });
+        }
+
+        let claim = S::from_public_inputs(&header.public_inputs);
+        let air = Air::new(S::TRACE_LEN, header.public_inputs.clone(), S::OPTIONS);
+        let mut public_coin = claim.gen_public_coin(&air);
+        public_coin.reseed_with_digest(&header.base_trace_root);
+        let challenges = Challenges::new(draw_multiple(&mut public_coin, air.num_challenges()));
+        let hints = air
+            .gen_hints(&challenges)
+            .map_err(|source| InvalidHints { source })?;
+        let num_composition_coeffs = air.num_composition_constraint_coeffs();
+        let composition_coeffs = draw_multiple(&mut public_coin, num_composition_coeffs);
+        public_coin.reseed_with_digest(&header.composition_trace_root);
+        let z = public_coin.draw();
+        let ood_evals = [
+            header.execution_trace_ood_evals.clone(),
+            header.composition_trace_ood_evals.clone(),
+        ]
+        .concat();
+        public_coin.reseed_with_field_elements(&ood_evals);
+        let deep_coeffs = claim.gen_deep_coeffs(&mut public_coin, &air);
+        let fri_alphas = header
+            .fri_layer_roots
+            .iter()
+            .map(|root| {
+                public_coin.reseed_with_digest(root);
+                public_coin.draw()
+            })
+            .collect();
+        public_coin.reseed_with_field_element_vector(coeffs);
+
+        let grinding_factor = S::OPTIONS.grinding_factor;
+        if grinding_factor != 0 {
+            let nonce = header.pow_nonce;
+            if !public_coin.verify_proof_of_work(grinding_factor, nonce) {
+                return Err(FriProofOfWork {
+                    grinding_factor,
+                    nonce,
+                });
+            }
+            public_coin.reseed_with_int(nonce);
+        }
+
+        let query_positions = public_coin.draw_queries(layout.num_queries, layout.lde_domain_size);
+        Ok(Self {
+            trace_domain: air.trace_domain(),
+            lde_domain: air.lde_domain(),
+            challenges,
+            hints,
+            composition_coeffs,
+            z,
+            deep_coeffs,
+            fri_alphas,
+            query_positions: query_positions.into_iter().collect(),
+        })
+    }
+
+    /// Returns the weights of the DEEP composition polynomial at the query at
+    /// `position` in the LDE domain
+    fn deep_weights<P: CanonicalSerialize + CanonicalDeserialize>(
+        &self,
+        layout: &Layout,
+        header: &InnerProofHeader<P>,
+        position: usize,
+    ) -> DeepWeights {
+        let g = self.trace_domain.group_gen();
+        let g_inv = self.trace_domain.group_gen_inv();
+        let x = self
+            .lde_domain
+            .element(bit_reverse_index(layout.lde_domain_size, position));
+        let (alpha, beta) = self.deep_coeffs.degree;
+        let degree_adjustment = alpha + beta * x;
+
+        let mut base_trace = vec![Fp::zero(); layout.num_base_columns];
+        let mut constant = Fp::zero();
+        let trace_ood_evals = &header.execution_trace_ood_evals;
+        for (j, &(column, offset)) in layout.trace_arguments.iter().enumerate() {
+            let shift = if offset >= 0 { g } else { g_inv }.pow([offset.unsigned_abs() as u64]);
+            let weight = self.deep_coeffs.execution_trace[j] / (x - self.z * shift);
+            base_trace[column] += weight;
+            constant -= weight * trace_ood_evals[j];
+        }
+
+        let z_n = self.z.pow([layout.ce_blowup_factor as u64]);
+        let composition_ood_evals = &header.composition_trace_ood_evals;
+        let composition_trace = zip(&self.deep_coeffs.composition_trace, composition_ood_evals)
+            .map(|(alpha, ood_eval)| {
+                let weight = *alpha / (x - z_n);
+                constant -= weight * ood_eval;
+                weight * degree_adjustment
+            })
+            .collect();
+
+        DeepWeights {
+            base_trace: base_trace
+                .into_iter()
+                .map(|w| w * degree_adjustment)
+                .collect(),
+            composition_trace,
+            constant: constant * degree_adjustment,
+        }
+    }
+
+    /// Returns the coefficients `(1 + α/o, 1 - α/o)` that fold the pair of
+    /// values opened in each FRI layer and the remainder evaluated at the
+    /// query at `position` in the LDE domain
+    fn fri_values<P: CanonicalSerialize + CanonicalDeserialize>(
+        &self,
+        layout: &Layout,
+        header: &InnerProofHeader<P>,
+        mut position: usize,
+    ) -> (Vec<(Fp, Fp)>, Fp) {
+        let mut domain_size = layout.lde_domain_size;
+        let mut domain_generator = Radix2EvaluationDomain::<Fp>::new(domain_size)
+            .unwrap()
+            .group_gen();
+        let mut fold_coeffs = Vec::new();
+        for &alpha in &self.fri_alphas {
+            position /= 2;
+            let offset =
+                domain_generator.pow([bit_reverse_index(domain_size / 2, position) as u64]);
+            let v = alpha / offset;
+            fold_coeffs.push((Fp::one() + v, Fp::one() - v));
+            domain_generator.square_in_place();
+            domain_size /= 2;
+        }
+        let x = domain_generator.pow([bit_reverse_index(domain_size, position) as u64]);
+        let remainder = horner_evaluate(&header.fri_remainder_coeffs, &x);
+        (fold_coeffs, remainder)
+    }
+}
+
+/// Opened leaf of an inner proof merkle tree and its authentication path
+struct Opening {
+    values: Vec<Fp>,
+    path: Vec<RescueDigest>,
+}
+
+/// Returns the openings of a tree by leaf index or `None` if the proof doesn't
+/// open the leaves at `indices`
+fn openings<'a>(
+    rows: impl Iterator<Item = &'a [Fp]>,
+    proof: &MerkleView<RescueDigest, RescueDigest>,
+    indices: &[usize],
+) -> Option<BTreeMap<usize, Opening>> {
+    // the AIR requires binary trees without merkle caps
+    let arity = MatrixMerkleTreeImpl::<RescueHashFn>::ARITY;
+    let paths = merkle_paths(proof, indices, arity, 0).ok()?;
+    let openings = zip(indices.iter().copied(), zip(rows, paths))
+        .map(|(i, (row, path))| {
+            let values = row.to_vec();
+            (i, Opening { values, path })
+        })
+        .collect();
+    Some(openings)
+}
+
+/// Returns the outer trace or `None` if the header is invalid or the proof's
+/// queries don't match the header
+fn gen_trace<S: InnerStark>(
+    layout: &Layout,
+    header: &InnerProofHeader<InnerPublicInputs<S>>,
+    proof: &Proof<S>,
+) -> Option<Matrix<Fp>> {
+    let transcript = InnerTranscript::replay::<S>(header, layout).ok()?;
+    let positions = &transcript.query_positions;
+    let queries = &proof.trace_queries;
+    if proof.fri_proof.layers.len() != layout.num_fri_layers {
+        return None;
+    }
+
+    let base_trace_values = queries.base_trace_values().ok()?;
+    let base_trace_rows = base_trace_values.chunks(layout.num_base_columns);
+    let composition_trace_rows = queries
+        .composition_trace_values
+        .chunks(layout.ce_blowup_factor);
+    let mut tree_openings = vec![
+        openings(
+            base_trace_rows,
+            &queries.base_trace_groups.first()?.proof,
+            positions,
+        )?,
+        openings(
+            composition_trace_rows,
+            &queries.composition_trace_proof,
+            positions,
+        )?,
+    ];
+    let mut layer_positions = positions.clone();
+    for layer in &proof.fri_proof.layers {
+        layer_positions = fold_positions(&layer_positions, 2);
+        let rows = layer.flattenend_rows.chunks(2);
+        tree_openings.push(openings(rows, &layer.merkle_proof, &layer_positions)?);
+    }
+    let opening = |tree: Tree, position: usize| {
+        let t = layout.trees().position(|t| t == tree).unwrap();
+        tree_openings[t].get(&Layout::leaf_index(tree, position))
+    };
+
+    let z = transcript.z;
+    let ood_evaluation = horner_evaluate(&header.composition_trace_ood_evals, &z);
+    let mut rows = Vec::with_capacity(layout.trace_len());
+    for slot in 0..layout.num_query_slots {
+        let position = positions[if slot < positions.len() { slot } else { 0 }];
+        let mut digest = RescueDigest::default();
+        let mut idx = 0;
+        for &job in &layout.jobs {
+            let mut state = [Fp::zero(); STATE_WIDTH];
+            let mut bit = 0;
+            match job {
+                Job::Leaf(tree) => {
+                    let values = &opening(tree, position)?.values;
+                    if values.len() > RATE {
+                        return None;
+                    }
+                    state[0] = Fp::from(values.len() as u64);
+                    state[CAPACITY..][..values.len()].copy_from_slice(values);
+                    idx = 0;
+                }
+                Job::Merge(tree, level) => {
+                    let sibling = *opening(tree, position)?.path.get(level as usize)?;
+                    bit = (Layout::leaf_index(tree, position) >> level) & 1;
+                    idx += bit << level;
+                    let (left, right) = if bit == 0 {
+                        (digest, sibling)
+                    } else {
+                        (sibling, digest)
+                    };
+                    state[0] = Fp::from(RATE as u64);
+                    state[CAPACITY..][..DIGEST_SIZE].copy_from_slice(&left.0);
+                    state[CAPACITY + DIGEST_SIZE..].copy_from_slice(&right.0);
+                }
+                Job::Padding => {}
+            }
+            let permutation = permutation_rows(state);
+            for state in permutation {
+                let mut row = [Fp::zero(); NUM_COLUMNS];
+                row[STATE..STATE + STATE_WIDTH].copy_from_slice(&state);
+                row[BIT] = Fp::from(bit as u64);
+                row[IDX] = Fp::from(idx as u64);
+                row[OOD] = ood_evaluation;
+                rows.push(row);
+            }
+            let output = permutation[JOB_SIZE - 1];
+            digest = RescueDigest(output[CAPACITY..][..DIGEST_SIZE].try_into().unwrap());
+        }
+    }
+
+    Some(Matrix::from_arrays(&rows))
+}
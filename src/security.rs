@@ -0,0 +1,269 @@
+use crate::merkle::MerkleTree;
+use crate::random::PublicCoin;
+use crate::stark::Stark;
+use crate::utils::field_bits;
+use crate::Air;
+use crate::ProofOptions;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// Model used to estimate the soundness of a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SecurityModel {
+    /// Conjectured soundness as described in the ethSTARK paper. Assumes the
+    /// best attack on FRI is the one described in
+    /// <https://github.com/starkware-libs/ethSTARK/blob/master/README.md#7-Measuring-Security>
+    #[default]
+    Conjectured,
+    /// Proven soundness in the list-decoding regime as described in section
+    /// 7.2 of the ethSTARK documentation <https://eprint.iacr.org/2021/582>.
+    /// FRI soundness uses the Johnson bound proximity gaps of Theorem 1.5 in
+    /// <https://eprint.iacr.org/2020/654>
+    Proven,
+}
+
+/// Parameters that determine the soundness of a STARK proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SecurityParams {
+    pub options: ProofOptions,
+    pub trace_len: usize,
+    /// Size of the extension field in bits
+    pub extension_field_bits: u32,
+    /// Maximum constraint degree (in multiples of the trace degree)
+    pub max_constraint_degree: usize,
+    /// Number of constraints combined into the composition polynomial
+    pub num_composition_terms: usize,
+    /// Number of terms combined into the DEEP composition polynomial
+    pub num_deep_terms: usize,
+    /// Collision resistance of the hash functions in bits
+    pub collision_resistance: u32,
+}
+
+impl SecurityParams {
+    pub fn new<S: Stark>(air: &Air<S::AirConfig>) -> Self {
+        let merkle_tree_security = S::MerkleTree::security_level_bits();
+        let public_coin_security = S::PublicCoin::security_level_bits();
+        Self {
+            options: air.options(),
+            trace_len: air.trace_len(),
+            extension_field_bits: field_bits::<S::Fq>(),
+            max_constraint_degree: air.ce_blowup_factor(),
+            num_composition_terms: air.num_constraints(),
+            num_deep_terms: air.trace_arguments().len() + air.ce_blowup_factor(),
+            collision_resistance: merkle_tree_security.min(public_coin_security),
+        }
+    }
+
+    /// Returns the security level in bits under the given soundness model
+    pub fn security_level_bits(&self, model: SecurityModel) -> u32 {
+        match model {
+            SecurityModel::Conjectured => self.conjectured_security_bits(),
+            SecurityModel::Proven => self.proven_security_bits(),
+        }
+    }
+
+    pub fn conjectured_security_bits(&self) -> u32 {
+        conjectured_security_bits(
+            self.options,
+            self.trace_len,
+            self.extension_field_bits,
+            self.collision_resistance,
+        )
+    }
+
+    /// Returns the proven security level in bits. The Johnson bound proximity
+    /// parameter `m` is chosen to maximise the security level.
+    pub fn proven_security_bits(&self) -> u32 {
+        const MIN_M: usize = 3;
+        const MAX_M: usize = 128;
+        (MIN_M..=MAX_M)
+            .map(|m| self.proven_security_bits_for_m(m))
+            .max()
+            .unwrap()
+            .min(self.collision_resistance)
+    }
+
+    // adapted from Winterfell
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::suboptimal_flops
+    )]
+    fn proven_security_bits_for_m(&self, m: usize) -> u32 {
+        let extension_field_bits = f64::from(self.extension_field_bits);
+        let blowup_factor = f64::from(self.options.lde_blowup_factor);
+        let trace_len = self.trace_len as f64;
+        let lde_domain_size = trace_len * blowup_factor;
+        let max_degree = self.max_constraint_degree as f64;
+        let m = m as f64;
+
+        // the DEEP composition polynomial is also constrained at the two
+        // out-of-domain points (z and gz) so FRI is run on a code with rate
+        // `rho_plus` rather than `1 / blowup_factor`
+        let num_openings = 2.0;
+        let rho_plus = (trace_len + num_openings) / lde_domain_size;
+        let alpha = (1.0 + 0.5 / m) * rho_plus.sqrt();
+        if alpha >= 1.0 {
+            return 0;
+        }
+
+        // list size of the Johnson bound
+        let list_size = (m + 0.5) / rho_plus.sqrt();
+
+        // FRI commit phase error. Theorem 1.5 of eprint 2020/654 bounds the
+        // error of the batching step and each folding step by
+        // `(m + 1/2)^7 / (3 * rho_plus^1.5) * n^2 / |F|`
+        let lde_domain_len = self.trace_len * usize::from(self.options.lde_blowup_factor);
+        let num_fri_layers = self.options.into_fri_options().num_layers(lde_domain_len);
+        let num_fri_steps = num_fri_layers as f64 + 1.0;
+        let proximity_gap_err =
+            (m + 0.5).powi(7) / (3.0 * rho_plus.powf(1.5)) * lde_domain_size.powi(2);
+        let fri_commit_bits = extension_field_bits - (num_fri_steps * proximity_gap_err).log2();
+
+        // FRI query phase error
+        let grinding_bits = f64::from(self.options.grinding_factor);
+        let num_queries = f64::from(self.options.num_queries).min(lde_domain_size);
+        let fri_query_bits = grinding_bits - num_queries * alpha.log2();
+
+        // ALI error. Composition coefficients are drawn independently but we
+        // conservatively use the bound for batching with powers of a single
+        // challenge which grows linearly with the number of terms
+        let num_composition_terms = self.num_composition_terms.max(1) as f64;
+        let ali_bits = extension_field_bits - (num_composition_terms * list_size).log2();
+
+        // DEEP error. Uses |F| - |D ∪ H| ≈ |F| which is fine for practical
+        // domain sizes
+        let num_deep_terms = self.num_deep_terms.max(1) as f64;
+        let deep_err = list_size.powi(2)
+            * (max_degree * (trace_len + num_openings - 1.0) + (trace_len - 1.0))
+            + num_deep_terms * list_size;
+        let deep_bits = extension_field_bits - deep_err.log2();
+
+        // union bound over all the errors costs one bit
+        let min_bits = fri_commit_bits
+            .min(fri_query_bits)
+            .min(ali_bits)
+            .min(deep_bits);
+        (min_bits - 1.0).max(0.0) as u32
+    }
+}
+
+/// Returns the conjectured security level in bits
+// adapted from Winterfell
+// also https://github.com/starkware-libs/ethSTARK/blob/master/README.md#7-Measuring-Security
+pub fn conjectured_security_bits(
+    options: ProofOptions,
+    trace_len: usize,
+    extension_field_bits: u32,
+    collision_resistance: u32,
+) -> u32 {
+    let lde_domain_size = trace_len * usize::from(options.lde_blowup_factor);
+    let field_security = extension_field_bits - lde_domain_size.ilog2();
+
+    let fri_query_security = {
+        let grinding_factor = u32::from(options.grinding_factor);
+        let security_per_query = options.lde_blowup_factor.ilog2();
+        // queries are distinct so there can't be more than the LDE domain size
+        let num_queries = u32::from(options.num_queries);
+        let num_fri_queries =
+            u32::try_from(lde_domain_size).map_or(num_queries, |n| n.min(num_queries));
+        security_per_query * num_fri_queries + grinding_factor
+    };
+
+    field_security
+        .min(fri_query_security)
+        .min(collision_resistance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SecurityModel;
+    use super::SecurityParams;
+    use crate::ProofOptions;
+
+    fn params(num_queries: u8, lde_blowup_factor: u8) -> SecurityParams {
+        SecurityParams {
+            options: ProofOptions::new(num_queries, lde_blowup_factor, 0, 8, 64),
+            trace_len: 1 << 20,
+            extension_field_bits: 192,
+            max_constraint_degree: 2,
+            num_composition_terms: 100,
+            num_deep_terms: 100,
+            collision_resistance: 128,
+        }
+    }
+
+    #[test]
+    fn proven_security_is_below_conjectured() {
+        let params = params(40, 16);
+
+        let conjectured = params.security_level_bits(SecurityModel::Conjectured);
+        let proven = params.security_level_bits(SecurityModel::Proven);
+
+        assert_eq!(128, conjectured);
+        assert!(proven < conjectured);
+    }
+
+    #[test]
+    fn proven_security_snapshot() {
+        // regression snapshot of the current implementation. Changes to the
+        // soundness bound must update these values deliberately.
+        assert_eq!(78, params(40, 16).proven_security_bits());
+        assert_eq!(38, params(40, 4).proven_security_bits());
+        assert_eq!(123, params(80, 16).proven_security_bits());
+    }
+
+    #[test]
+    #[allow(clippy::cast_precision_loss, clippy::suboptimal_flops)]
+    fn proven_security_is_below_query_bound() {
+        // the query error is at least `sqrt(rho_plus)^num_queries` for any `m`
+        // so security can't exceed `-num_queries * log2(sqrt(rho_plus)) - 1`
+        // where `rho_plus = (trace_len + 2) / lde_domain_size`
+        for (num_queries, lde_blowup_factor) in [(40, 16), (40, 4), (80, 16)] {
+            let params = params(num_queries, lde_blowup_factor);
+            let lde_domain_size = params.trace_len as f64 * f64::from(lde_blowup_factor);
+            let rho_plus = (params.trace_len as f64 + 2.0) / lde_domain_size;
+            let query_bound = -f64::from(num_queries) * rho_plus.sqrt().log2() - 1.0;
+
+            assert!(f64::from(params.proven_security_bits()) <= query_bound);
+        }
+    }
+
+    #[test]
+    fn proven_query_security_is_half_of_conjectured() {
+        // in the list-decoding regime each query contributes roughly
+        // `log2(1 / sqrt(rho))` bits rather than the conjectured `log2(1 / rho)`
+        for (num_queries, lde_blowup_factor) in [(20, 16), (40, 4), (30, 8)] {
+            let params = params(num_queries, lde_blowup_factor);
+            let conjectured = params.conjectured_security_bits();
+            let proven = params.proven_security_bits();
+
+            assert!(proven <= conjectured / 2, "{proven} > {conjectured} / 2");
+            assert!(
+                proven + 4 >= conjectured / 2,
+                "{proven} << {conjectured} / 2"
+            );
+        }
+    }
+
+    #[test]
+    fn proven_security_uses_rho_plus() {
+        // the two out-of-domain openings noticeably increase the rate of short
+        // traces which reduces the security per query
+        let mut short_trace = params(40, 4);
+        short_trace.trace_len = 8;
+        let mut long_trace = params(40, 4);
+        long_trace.trace_len = 1 << 12;
+
+        assert!(short_trace.proven_security_bits() < long_trace.proven_security_bits());
+    }
+
+    #[test]
+    fn proven_security_increases_with_queries() {
+        let fewer_queries = params(40, 16).proven_security_bits();
+        let more_queries = params(80, 16).proven_security_bits();
+
+        assert!(fewer_queries < more_queries);
+    }
+}
@@ -0,0 +1,648 @@
+//! Generates Solidity verifier contracts.
+//!
+//! A generated contract verifies proofs for one AIR, trace length and set of
+//! proof options. It includes the composition constraint evaluation at the
+//! out-of-domain point. Supported configurations:
+//!
+//! * the extension field is the base field (`Fq = Fp`) and the modulus fits in
+//!   256 bits
+//! * SHA-256 or Keccak-256 is used by the public coin and the merkle trees
+//! * merkle trees are binary (i.e. [`MatrixMerkleTreeImpl`] with the default
+//!   arity)
+//!
+//! The contract can't know how [`Stark::gen_public_coin`] and
+//! [`AirConfig::gen_hints`] bind the public inputs so it is `abstract` with
+//! `publicCoinSeed` and `hints` functions for the user to implement.
+//!
+//! Proofs are passed to the contract as a flat list of 32 byte words (see
+//! [`SolidityVerifier::encode_proof`]).
+
+use crate::air::AirConfig;
+use crate::constraints::AlgebraicItem;
+use crate::constraints::CompositionItem;
+use crate::expression::Expr;
+use crate::hash::Digest;
+use crate::hash::ElementHashFn;
+use crate::hash::HashFn;
+use crate::hash::Keccak256HashFn;
+use crate::hash::Sha256HashFn;
+use crate::merkle::MatrixMerkleTreeImpl;
+use crate::merkle::MerkleCap;
+use crate::merkle::MerkleView;
+use crate::random::PublicCoinImpl;
+use crate::stark::Stark;
+use crate::verifier::VerificationError;
+use crate::Air;
+use crate::Proof;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use ark_ff::BigInteger;
+use ark_ff::PrimeField;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use core::cell::RefCell;
+use core::fmt::Write;
+use core::ops::Add;
+use core::ops::Div;
+use core::ops::Mul;
+use core::ops::Neg;
+use num_traits::Pow;
+use rand::RngCore;
+use snafu::Snafu;
+
+const TEMPLATE: &str = include_str!("solidity/verifier.sol");
+
+/// Hash functions that are available in Solidity
+pub trait SolidityHashFn: HashFn {
+    /// Name of the Solidity function that computes the hash of `bytes`
+    const SOLIDITY_FN: &'static str;
+}
+
+impl SolidityHashFn for Sha256HashFn {
+    const SOLIDITY_FN: &'static str = "sha256";
+}
+
+impl SolidityHashFn for Keccak256HashFn {
+    const SOLIDITY_FN: &'static str = "keccak256";
+}
+
+/// Errors that can occur when generating a verifier or encoding a proof
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("field modulus has {bits} bits but at most 256 bits are supported"))]
+    ModulusTooLarge { bits: u32 },
+    #[snafu(display(
+        "drawing field elements from the public coin can't be expressed in Solidity"
+    ))]
+    UnsupportedDraw,
+    #[snafu(display("fri folding factor {folding_factor} is not supported"))]
+    UnsupportedFoldingFactor { folding_factor: u8 },
+    #[snafu(display("proof options don't match the options the verifier was generated for"))]
+    OptionsMismatch,
+    #[snafu(display("proof has trace length {actual} but the verifier expects {expected}"))]
+    TraceLenMismatch { expected: usize, actual: usize },
+    #[snafu(display("proof has an extension trace commitment but no extension trace proof"))]
+    MissingExtensionTraceProof,
+    #[snafu(display("proof's trace queries are malformed: {source}"))]
+    InvalidTraceQueries { source: VerificationError },
+}
+
+/// Generates Solidity verifier contracts and calldata for an AIR
+pub struct SolidityVerifier<S: Stark> {
+    air: Air<S::AirConfig>,
+    draw_factor: S::Fq,
+}
+
+impl<F, H, S> SolidityVerifier<S>
+where
+    F: PrimeField,
+    H: SolidityHashFn + ElementHashFn<F>,
+    S: Stark<
+        Fp = F,
+        Fq = F,
+        MerkleTree = MatrixMerkleTreeImpl<H>,
+        PublicCoin = PublicCoinImpl<F, H>,
+    >,
+{
+    /// Creates a verifier for proofs with the trace length and proof options
+    /// of `air`. The public inputs of `air` aren't used.
+    pub fn new(air: Air<S::AirConfig>) -> Result<Self, Error> {
+        let bits = F::MODULUS_BIT_SIZE;
+        if bits > 256 || F::BigInt::NUM_LIMBS > 4 {
+            return Err(Error::ModulusTooLarge { bits });
+        }
+        let folding_factor = air.options().fri_folding_factor;
+        if ![2, 4, 8, 16].contains(&folding_factor) {
+            return Err(Error::UnsupportedFoldingFactor { folding_factor });
+        }
+        let draw_factor = draw_factor::<F>().ok_or(Error::UnsupportedDraw)?;
+        Ok(Self { air, draw_factor })
+    }
+
+    /// Returns the source of an `abstract` verifier contract called `name`
+    pub fn contract(&self, name: &str) -> String {
+        let air = &self.air;
+        let options = air.options();
+        let trace_len = air.trace_len();
+        let lde_domain = air.lde_domain();
+        let lde_domain_size = lde_domain.size();
+        let fri_options = options.into_fri_options();
+        let folding_factor = usize::from(options.fri_folding_factor);
+        let folding_domain = Radix2EvaluationDomain::<F>::new(folding_factor).unwrap();
+        let remainder_domain_size = fri_options.remainder_size(lde_domain_size);
+        // arkworks masks random limbs to the bit size of the modulus
+        let draw_mask = format!("0x{:x}", BitMask(F::MODULUS_BIT_SIZE));
+
+        let replacements = [
+            ("{{NAME}}", name.to_string()),
+            ("{{MODULUS}}", F::MODULUS.to_string()),
+            ("{{TRACE_LEN}}", trace_len.to_string()),
+            (
+                "{{LOG_LDE_DOMAIN_SIZE}}",
+                lde_domain_size.ilog2().to_string(),
+            ),
+            ("{{LDE_DOMAIN_OFFSET}}", element(lde_domain.coset_offset())),
+            ("{{LDE_DOMAIN_GENERATOR}}", element(lde_domain.group_gen())),
+            ("{{NUM_QUERIES}}", air.num_queries().to_string()),
+            ("{{GRINDING_FACTOR}}", options.grinding_factor.to_string()),
+            (
+                "{{MERKLE_CAP_HEIGHT}}",
+                options.merkle_cap_height.to_string(),
+            ),
+            (
+                "{{NUM_BASE_COLUMNS}}",
+                S::AirConfig::NUM_BASE_COLUMNS.to_string(),
+            ),
+            (
+                "{{NUM_BASE_COLUMN_GROUPS}}",
+                S::AirConfig::BASE_COLUMN_GROUPS.len().to_string(),
+            ),
+            (
+                "{{NUM_EXTENSION_COLUMNS}}",
+                S::AirConfig::NUM_EXTENSION_COLUMNS.to_string(),
+            ),
+            ("{{CE_BLOWUP_FACTOR}}", air.ce_blowup_factor().to_string()),
+            (
+                "{{NUM_TRACE_ARGUMENTS}}",
+                air.trace_arguments().len().to_string(),
+            ),
+            ("{{NUM_CHALLENGES}}", air.num_challenges().to_string()),
+            (
+                "{{NUM_COMPOSITION_COEFFS}}",
+                air.num_composition_constraint_coeffs().to_string(),
+            ),
+            ("{{FRI_FOLDING_FACTOR}}", folding_factor.to_string()),
+            (
+                "{{LOG_FRI_FOLDING_FACTOR}}",
+                folding_factor.ilog2().to_string(),
+            ),
+            (
+                "{{FRI_FOLDING_DOMAIN_GENERATOR_INV}}",
+                element(folding_domain.group_gen_inv()),
+            ),
+            (
+                "{{FRI_NUM_LAYERS}}",
+                fri_options.num_layers(lde_domain_size).to_string(),
+            ),
+            (
+                "{{FRI_REMAINDER_DOMAIN_SIZE}}",
+                remainder_domain_size.to_string(),
+            ),
+            (
+                "{{FRI_REMAINDER_MAX_DEGREE}}",
+                (remainder_domain_size / air.lde_blowup_factor() - 1).to_string(),
+            ),
+            ("{{ELEMENT_BYTES}}", F::ZERO.uncompressed_size().to_string()),
+            ("{{NUM_LIMBS}}", F::BigInt::NUM_LIMBS.to_string()),
+            ("{{DRAW_MASK}}", draw_mask),
+            ("{{DRAW_FACTOR}}", element(self.draw_factor)),
+            ("{{HASH_FN}}", H::SOLIDITY_FN.to_string()),
+            ("{{HINTS}}", self.hints_hook()),
+            ("{{BASE_GROUPS}}", self.base_groups()),
+            ("{{Z_SHIFTED}}", self.z_shifted()),
+            ("{{DEEP_TRACE_TERMS}}", self.deep_trace_terms()),
+        ];
+        let (num_temps, composition_constraint, composition_result) = self.composition_constraint();
+        let mut contract = TEMPLATE.to_string();
+        for (placeholder, value) in replacements {
+            contract = contract.replace(placeholder, value.trim_end());
+        }
+        contract
+            .replace("{{NUM_TEMPS}}", &num_temps.to_string())
+            .replace(
+                "{{COMPOSITION_CONSTRAINT}}",
+                composition_constraint.trim_end(),
+            )
+            .replace("{{COMPOSITION_RESULT}}", &composition_result)
+    }
+
+    /// Encodes a proof as the flat list of 32 byte words read by the contract
+    pub fn encode_proof(&self, proof: &Proof<S>) -> Result<Vec<u8>, Error> {
+        if proof.options != self.air.options() {
+            return Err(Error::OptionsMismatch);
+        }
+        if proof.trace_len != self.air.trace_len() {
+            return Err(Error::TraceLenMismatch {
+                expected: self.air.trace_len(),
+                actual: proof.trace_len,
+            });
+        }
+
+        let mut words = Words::default();
+        for commitment in &proof.base_trace_commitments {
+            words.cap(commitment);
+        }
+        if let Some(commitment) = &proof.extension_trace_commitment {
+            words.cap(commitment);
+        }
+        words.cap(&proof.composition_trace_commitment);
+        words.elements(&proof.execution_trace_ood_evals);
+        words.elements(&proof.composition_trace_ood_evals);
+
+        let fri_proof = &proof.fri_proof;
+        for layer in &fri_proof.layers {
+            words.cap(&layer.commitment);
+        }
+        words.int(fri_proof.remainder_coeffs.len() as u64);
+        words.elements(&fri_proof.remainder_coeffs);
+        words.int(proof.pow_nonce);
+
+        let queries = &proof.trace_queries;
+        let base_trace_values = queries
+            .base_trace_values()
+            .map_err(|source| Error::InvalidTraceQueries { source })?;
+        words.elements(&base_trace_values);
+        for group in &queries.base_trace_groups {
+            words.merkle_view(&group.proof);
+        }
+        if S::AirConfig::NUM_EXTENSION_COLUMNS != 0 {
+            let merkle_proof = queries
+                .extension_trace_proof
+                .as_ref()
+                .ok_or(Error::MissingExtensionTraceProof)?;
+            words.elements(&queries.extension_trace_values);
+            words.merkle_view(merkle_proof);
+        }
+        words.elements(&queries.composition_trace_values);
+        words.merkle_view(&queries.composition_trace_proof);
+
+        for layer in &fri_proof.layers {
+            words.int(layer.flattenend_rows.len() as u64);
+            words.elements(&layer.flattenend_rows);
+            words.merkle_view(&layer.merkle_proof);
+        }
+
+        Ok(words.0)
+    }
+
+    /// Returns the calldata for calling `verify(bytes,bytes)` on the contract
+    /// with the encoded proof and public inputs
+    pub fn calldata(&self, proof: &Proof<S>, public_inputs: &[u8]) -> Result<Vec<u8>, Error> {
+        let proof = self.encode_proof(proof)?;
+        let selector = Keccak256HashFn::hash(*b"verify(bytes,bytes)").as_bytes();
+        let padded_len = |bytes: &[u8]| bytes.len().next_multiple_of(32);
+        let mut words = Words::default();
+        words.int(0x40);
+        words.int((0x60 + padded_len(&proof)) as u64);
+        for bytes in [&*proof, public_inputs] {
+            words.int(bytes.len() as u64);
+            words.0.extend_from_slice(bytes);
+            words
+                .0
+                .resize(words.0.len() + padded_len(bytes) - bytes.len(), 0);
+        }
+        Ok([&selector[..4], &words.0[..]].concat())
+    }
+
+    fn hints_hook(&self) -> String {
+        let mut num_hints = 0;
+        self.air.composition_constraint().traverse(&mut |node| {
+            if let Expr::Leaf(CompositionItem::Item(AlgebraicItem::Hint(i))) = node {
+                num_hints = num_hints.max(i + 1);
+            }
+        });
+        if num_hints == 0 {
+            "    /// @dev The AIR doesn't use hints
+    function hints(bytes calldata, uint256[] memory)
+        internal
+        view
+        virtual
+        returns (uint256[] memory)
+    {
+        return new uint256[](0);
+    }"
+            .to_string()
+        } else {
+            format!(
+                "    /// @dev Returns the {num_hints} AIR hints. Must match `AirConfig::gen_hints`.
+    function hints(bytes calldata publicInputs, uint256[] memory challenges)
+        internal
+        view
+        virtual
+        returns (uint256[] memory);"
+            )
+        }
+    }
+
+    fn base_groups(&self) -> String {
+        let mut code = String::new();
+        for (i, columns) in self.air.base_column_groups().into_iter().enumerate() {
+            let start = columns.start;
+            let width = columns.len();
+            writeln!(
+                code,
+                "        verifyMerkleProof(
+            proof,
+            r,
+            ctx.positions,
+            hashRows(ctx.baseRows, NUM_BASE_COLUMNS, {start}, {width}),
+            LOG_LDE_DOMAIN_SIZE,
+            ctx.baseCaps[{i}]
+        );"
+            )
+            .unwrap();
+        }
+        code
+    }
+
+    fn z_shifted(&self) -> String {
+        let trace_domain = self.air.trace_domain();
+        let mut code = String::new();
+        for (i, (_, offset)) in self.air.trace_arguments().into_iter().enumerate() {
+            let generator = if offset >= 0 {
+                trace_domain.group_gen()
+            } else {
+                trace_domain.group_gen_inv()
+            };
+            let shift = generator.pow([offset.unsigned_abs() as u64]);
+            let shift = element(shift);
+            writeln!(code, "        zShifted[{i}] = mulmod(ctx.z, {shift}, P);").unwrap();
+        }
+        code
+    }
+
+    fn deep_trace_terms(&self) -> String {
+        let num_base_columns = S::AirConfig::NUM_BASE_COLUMNS;
+        let mut code = String::new();
+        for (i, (column, _)) in self.air.trace_arguments().into_iter().enumerate() {
+            let value = if column < num_base_columns {
+                format!("ctx.baseRows[q * NUM_BASE_COLUMNS + {column}]")
+            } else {
+                let column = column - num_base_columns;
+                format!("ctx.extensionRows[q * NUM_EXTENSION_COLUMNS + {column}]")
+            };
+            writeln!(
+                code,
+                "            eval = addmod(
+                eval,
+                deepTerm(ctx.deepTraceCoeffs[{i}], {value}, ctx.traceOods[{i}], x, zShifted[{i}]),
+                P
+            );"
+            )
+            .unwrap();
+        }
+        code
+    }
+
+    /// Returns the number of temporaries, the statements and the result of
+    /// evaluating the composition constraint at the out-of-domain point
+    fn composition_constraint(&self) -> (usize, String, String) {
+        use AlgebraicItem::*;
+        use CompositionItem::*;
+        let trace_len = self.air.trace_len();
+        let trace_arguments = self.air.trace_arguments().into_iter().collect::<Vec<_>>();
+        let builder = RefCell::new(CodeBuilder::default());
+        let var = |value: String| SolidityExpr {
+            builder: &builder,
+            value,
+        };
+        let result = self
+            .air
+            .composition_constraint()
+            .graph_eval(&mut |leaf| match leaf {
+                Item(X) => var("ctx.z".into()),
+                &Item(Constant(v)) => var(element(v.as_fq())),
+                &Item(Challenge(i)) => var(format!("ctx.challenges[{i}]")),
+                &Item(Hint(i)) => var(format!("ctx.hints[{i}]")),
+                Item(Periodic(col)) => {
+                    let exponent = trace_len / col.interval_size();
+                    let point = var(format!("expmod(ctx.z, {exponent})")).assign();
+                    let coeffs = col.coeffs().iter().rev().map(|c| element(c.as_fq()));
+                    coeffs.fold(var("0".into()), |acc, coeff| {
+                        acc.assign_value(&format!(
+                            "addmod(mulmod({}, {}, P), {coeff}, P)",
+                            acc.value, point.value
+                        ))
+                    })
+                }
+                &Item(Trace(i, j)) => {
+                    let k = trace_arguments.binary_search(&(i, j)).unwrap();
+                    var(format!("ctx.traceOods[{k}]"))
+                }
+                &CompositionCoeff(i) => var(format!("ctx.compositionCoeffs[{i}]")),
+            });
+        let result = result.value;
+        let builder = builder.into_inner();
+        (builder.num_temps, builder.code, result)
+    }
+}
+
+/// Returns `c` such that drawing a field element from the little-endian u64
+/// limbs `raw` (i.e. with [`UniformRand`]) returns `raw * c`. Arkworks' prime
+/// fields return `raw * R^-1` since `raw` is taken to be in Montgomery form.
+///
+/// [`UniformRand`]: ark_std::UniformRand
+fn draw_factor<F: PrimeField>() -> Option<F> {
+    let num_limbs = F::BigInt::NUM_LIMBS;
+    let factor = |limb: u64| -> Option<F> {
+        let mut rng = ProbeRng {
+            limb,
+            bytes_read: 0,
+        };
+        let drawn = F::rand(&mut rng);
+        if rng.bytes_read != num_limbs * 8 {
+            return None;
+        }
+        let mut raw = F::BigInt::from(0u64);
+        for _ in 0..num_limbs {
+            raw.muln(64);
+            raw.add_with_carry(&F::BigInt::from(limb));
+        }
+        Some(drawn / F::from_bigint(raw)?)
+    };
+    let factor0 = factor(3)?;
+    let factor1 = factor(5)?;
+    (factor0 == factor1).then_some(factor0)
+}
+
+/// Random number generator that returns the same limb for every `u64`
+struct ProbeRng {
+    limb: u64,
+    bytes_read: usize,
+}
+
+impl RngCore for ProbeRng {
+    #[allow(clippy::cast_possible_truncation)]
+    fn next_u32(&mut self) -> u32 {
+        self.bytes_read += 4;
+        self.limb as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.bytes_read += 8;
+        self.limb
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.bytes_read += dest.len();
+        dest.fill(0);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Proof encoded as 32 byte words
+#[derive(Default)]
+struct Words(Vec<u8>);
+
+impl Words {
+    fn word(&mut self, bytes: &[u8]) {
+        assert!(bytes.len() <= 32);
+        self.0.resize(self.0.len() + 32 - bytes.len(), 0);
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn int(&mut self, value: u64) {
+        self.word(&value.to_be_bytes());
+    }
+
+    fn digest(&mut self, digest: &impl Digest) {
+        self.word(&digest.as_bytes());
+    }
+
+    fn elements<F: PrimeField>(&mut self, elements: &[F]) {
+        for element in elements {
+            self.word(&element.into_bigint().to_bytes_be());
+        }
+    }
+
+    fn cap<D: Digest>(&mut self, cap: &MerkleCap<D>) {
+        cap.iter().for_each(|digest| self.digest(digest));
+    }
+
+    fn merkle_view<D: Digest>(&mut self, view: &MerkleView<D, D>) {
+        self.int(view.sibling_leaves.len() as u64);
+        view.sibling_leaves.iter().for_each(|d| self.digest(d));
+        self.int(view.nodes.len() as u64);
+        view.nodes.iter().for_each(|d| self.digest(d));
+    }
+}
+
+/// Solidity statements that evaluate an expression
+#[derive(Default)]
+struct CodeBuilder {
+    code: String,
+    num_temps: usize,
+}
+
+/// Solidity expression for a field element. Operations store their result in
+/// a new temporary so shared nodes of an expression graph are evaluated once.
+#[derive(Clone)]
+struct SolidityExpr<'a> {
+    builder: &'a RefCell<CodeBuilder>,
+    value: String,
+}
+
+impl SolidityExpr<'_> {
+    /// Stores this expression in a new temporary
+    fn assign(self) -> Self {
+        self.assign_value(&self.value)
+    }
+
+    /// Stores `value` in a new temporary
+    fn assign_value(&self, value: &str) -> Self {
+        let mut builder = self.builder.borrow_mut();
+        let temp = format!("t[{}]", builder.num_temps);
+        builder.num_temps += 1;
+        writeln!(builder.code, "        {temp} = {value};").unwrap();
+        Self {
+            builder: self.builder,
+            value: temp,
+        }
+    }
+}
+
+impl Add for SolidityExpr<'_> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.assign_value(&format!("addmod({}, {}, P)", self.value, rhs.value))
+    }
+}
+
+impl Mul for SolidityExpr<'_> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        self.assign_value(&format!("mulmod({}, {}, P)", self.value, rhs.value))
+    }
+}
+
+impl Div for SolidityExpr<'_> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        self.assign_value(&format!("mulmod({}, inv({}), P)", self.value, rhs.value))
+    }
+}
+
+impl Neg for SolidityExpr<'_> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self.assign_value(&format!("(P - {}) % P", self.value))
+    }
+}
+
+impl Pow<usize> for SolidityExpr<'_> {
+    type Output = Self;
+
+    fn pow(self, exponent: usize) -> Self {
+        self.assign_value(&format!("expmod({}, {exponent})", self.value))
+    }
+}
+
+/// Mask with the lowest `n` bits set. Formats as hex.
+struct BitMask(u32);
+
+impl core::fmt::LowerHex for BitMask {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // most significant hex digit followed by `f`s
+        let top = (1u8 << (self.0 % 4)) - 1;
+        if top != 0 {
+            write!(f, "{top:x}")?;
+        }
+        (0..self.0 / 4).try_for_each(|_| f.write_char('f'))
+    }
+}
+
+/// Returns a field element as a decimal Solidity literal
+fn element<F: PrimeField>(v: F) -> String {
+    v.into_bigint().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::draw_factor;
+    use super::BitMask;
+    use super::ProbeRng;
+    use ark_ff::PrimeField;
+    use ark_ff::UniformRand;
+    use ark_ff::Zero;
+    use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+    #[test]
+    fn bit_mask_formats_as_hex() {
+        assert_eq!("ff", format!("{:x}", BitMask(8)));
+        assert_eq!("3fff", format!("{:x}", BitMask(14)));
+        assert_eq!("f".repeat(64), format!("{:x}", BitMask(256)));
+    }
+
+    #[test]
+    fn draw_factor_matches_draw() {
+        let factor = draw_factor::<Fp>().unwrap();
+        let limb = 0x1234_5678_9abc_def0;
+
+        let drawn = Fp::rand(&mut ProbeRng {
+            limb,
+            bytes_read: 0,
+        });
+
+        assert_eq!(drawn, Fp::from_bigint(limb.into()).unwrap() * factor);
+        assert!(!factor.is_zero());
+    }
+}
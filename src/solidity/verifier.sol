@@ -0,0 +1,586 @@
+// SPDX-License-Identifier: MIT
+// Generated by miniSTARK. Do not edit.
+pragma solidity ^0.8.19;
+
+/// @notice Verifies miniSTARK proofs for a single AIR, trace length and set of
+/// proof options.
+/// @dev Implement `publicCoinSeed` (and `hints` if the AIR uses hints) so they
+/// match `Stark::gen_public_coin` and `AirConfig::gen_hints`.
+abstract contract {{NAME}} {
+    uint256 internal constant P = {{MODULUS}};
+    uint256 internal constant TRACE_LEN = {{TRACE_LEN}};
+    uint256 internal constant LOG_LDE_DOMAIN_SIZE = {{LOG_LDE_DOMAIN_SIZE}};
+    uint256 internal constant LDE_DOMAIN_OFFSET = {{LDE_DOMAIN_OFFSET}};
+    uint256 internal constant LDE_DOMAIN_GENERATOR = {{LDE_DOMAIN_GENERATOR}};
+    uint256 internal constant NUM_QUERIES = {{NUM_QUERIES}};
+    uint256 internal constant GRINDING_FACTOR = {{GRINDING_FACTOR}};
+    uint256 internal constant MERKLE_CAP_HEIGHT = {{MERKLE_CAP_HEIGHT}};
+    uint256 internal constant NUM_BASE_COLUMNS = {{NUM_BASE_COLUMNS}};
+    uint256 internal constant NUM_BASE_COLUMN_GROUPS = {{NUM_BASE_COLUMN_GROUPS}};
+    uint256 internal constant NUM_EXTENSION_COLUMNS = {{NUM_EXTENSION_COLUMNS}};
+    uint256 internal constant CE_BLOWUP_FACTOR = {{CE_BLOWUP_FACTOR}};
+    uint256 internal constant NUM_TRACE_ARGUMENTS = {{NUM_TRACE_ARGUMENTS}};
+    uint256 internal constant NUM_CHALLENGES = {{NUM_CHALLENGES}};
+    uint256 internal constant NUM_COMPOSITION_COEFFS = {{NUM_COMPOSITION_COEFFS}};
+    uint256 internal constant FRI_FOLDING_FACTOR = {{FRI_FOLDING_FACTOR}};
+    uint256 internal constant LOG_FRI_FOLDING_FACTOR = {{LOG_FRI_FOLDING_FACTOR}};
+    uint256 internal constant FRI_FOLDING_DOMAIN_GENERATOR_INV = {{FRI_FOLDING_DOMAIN_GENERATOR_INV}};
+    uint256 internal constant FRI_NUM_LAYERS = {{FRI_NUM_LAYERS}};
+    uint256 internal constant FRI_REMAINDER_DOMAIN_SIZE = {{FRI_REMAINDER_DOMAIN_SIZE}};
+    uint256 internal constant FRI_REMAINDER_MAX_DEGREE = {{FRI_REMAINDER_MAX_DEGREE}};
+    // field elements are hashed as their little-endian `CanonicalSerialize` bytes
+    uint256 internal constant ELEMENT_BYTES = {{ELEMENT_BYTES}};
+    // field elements are drawn from the public coin the same way as arkworks'
+    // `UniformRand` i.e. by rejection sampling little-endian u64 limbs
+    uint256 internal constant NUM_LIMBS = {{NUM_LIMBS}};
+    uint256 internal constant DRAW_MASK = {{DRAW_MASK}};
+    uint256 internal constant DRAW_FACTOR = {{DRAW_FACTOR}};
+
+    /// @dev Position of the next word to read from the proof
+    struct Reader {
+        uint256 pos;
+    }
+
+    /// @dev Mirrors `PublicCoinImpl`
+    struct PublicCoin {
+        bytes32 seed;
+        uint64 counter;
+        bytes32 digest;
+        uint256 available;
+    }
+
+    struct Context {
+        bytes32[][] baseCaps;
+        bytes32[] extensionCap;
+        bytes32[] compositionCap;
+        uint256[] challenges;
+        uint256[] hints;
+        uint256[] compositionCoeffs;
+        uint256 z;
+        uint256[] traceOods;
+        uint256[] compositionOods;
+        uint256[] deepTraceCoeffs;
+        uint256[] deepCompositionCoeffs;
+        uint256 degreeAlpha;
+        uint256 degreeBeta;
+        bytes32[][] friCaps;
+        uint256[] friAlphas;
+        uint256[] remainder;
+        uint256[] positions;
+        uint256[] baseRows;
+        uint256[] extensionRows;
+        uint256[] compositionRows;
+        uint256[] evaluations;
+    }
+
+    /// @dev Queries and their evaluations in the current FRI layer
+    struct FriState {
+        uint256[] positions;
+        uint256[] evaluations;
+        uint256 logDomainSize;
+        uint256 generator;
+        uint256[] omegaInvPowers;
+    }
+
+    /// @dev Returns the initial seed of the public coin for the given public
+    /// inputs. Must match `Stark::gen_public_coin`.
+    function publicCoinSeed(bytes calldata publicInputs) internal view virtual returns (bytes32);
+
+{{HINTS}}
+
+    /// @notice Verifies a proof encoded with `SolidityVerifier::encode_proof`.
+    /// Reverts with a reason if the proof is invalid.
+    function verify(bytes calldata proof, bytes calldata publicInputs) external view returns (bool) {
+        Reader memory r = Reader(0);
+        PublicCoin memory coin = PublicCoin(publicCoinSeed(publicInputs), 0, 0, 0);
+        Context memory ctx;
+        readTraceCommitments(proof, r, ctx, coin, publicInputs);
+        readOodEvaluations(proof, r, ctx, coin);
+        require(
+            evaluateCompositionConstraint(ctx) == horner(ctx.compositionOods, ctx.z),
+            "inconsistent ood constraint evaluations"
+        );
+        ctx.deepTraceCoeffs = drawMany(coin, NUM_TRACE_ARGUMENTS);
+        ctx.deepCompositionCoeffs = drawMany(coin, CE_BLOWUP_FACTOR);
+        ctx.degreeAlpha = draw(coin);
+        ctx.degreeBeta = draw(coin);
+        readFriCommitments(proof, r, ctx, coin);
+        verifyProofOfWork(proof, r, coin);
+        ctx.positions = drawQueries(coin);
+        verifyTraceQueries(proof, r, ctx);
+        computeDeepEvaluations(ctx);
+        verifyFri(proof, r, ctx);
+        require(r.pos == proof.length, "proof has trailing data");
+        return true;
+    }
+
+    function readTraceCommitments(
+        bytes calldata proof,
+        Reader memory r,
+        Context memory ctx,
+        PublicCoin memory coin,
+        bytes calldata publicInputs
+    ) private view {
+        uint256 capSize = merkleCapSize(LOG_LDE_DOMAIN_SIZE);
+        ctx.baseCaps = new bytes32[][](NUM_BASE_COLUMN_GROUPS);
+        for (uint256 i = 0; i < NUM_BASE_COLUMN_GROUPS; i++) {
+            ctx.baseCaps[i] = readCap(proof, r, coin, capSize);
+        }
+        ctx.challenges = drawMany(coin, NUM_CHALLENGES);
+        ctx.hints = hints(publicInputs, ctx.challenges);
+        if (NUM_EXTENSION_COLUMNS != 0) {
+            ctx.extensionCap = readCap(proof, r, coin, capSize);
+        }
+        ctx.compositionCoeffs = drawMany(coin, NUM_COMPOSITION_COEFFS);
+        ctx.compositionCap = readCap(proof, r, coin, capSize);
+    }
+
+    function readOodEvaluations(
+        bytes calldata proof,
+        Reader memory r,
+        Context memory ctx,
+        PublicCoin memory coin
+    ) private pure {
+        ctx.z = draw(coin);
+        ctx.traceOods = readElements(proof, r, NUM_TRACE_ARGUMENTS);
+        ctx.compositionOods = readElements(proof, r, CE_BLOWUP_FACTOR);
+        for (uint256 i = 0; i < NUM_TRACE_ARGUMENTS; i++) {
+            reseedWithElement(coin, ctx.traceOods[i]);
+        }
+        for (uint256 i = 0; i < CE_BLOWUP_FACTOR; i++) {
+            reseedWithElement(coin, ctx.compositionOods[i]);
+        }
+    }
+
+    function readFriCommitments(
+        bytes calldata proof,
+        Reader memory r,
+        Context memory ctx,
+        PublicCoin memory coin
+    ) private pure {
+        ctx.friCaps = new bytes32[][](FRI_NUM_LAYERS);
+        ctx.friAlphas = new uint256[](FRI_NUM_LAYERS);
+        for (uint256 i = 0; i < FRI_NUM_LAYERS; i++) {
+            uint256 logNumRows = LOG_LDE_DOMAIN_SIZE - LOG_FRI_FOLDING_FACTOR * (i + 1);
+            ctx.friCaps[i] = readCap(proof, r, coin, merkleCapSize(logNumRows));
+            ctx.friAlphas[i] = draw(coin);
+        }
+        uint256 numCoeffs = readWord(proof, r);
+        require(numCoeffs <= FRI_REMAINDER_DOMAIN_SIZE, "too many fri remainder coefficients");
+        ctx.remainder = readElements(proof, r, numCoeffs);
+        for (uint256 i = 0; i < numCoeffs; i++) {
+            reseedWithElement(coin, ctx.remainder[i]);
+        }
+    }
+
+    function verifyProofOfWork(bytes calldata proof, Reader memory r, PublicCoin memory coin)
+        private
+        pure
+    {
+        uint256 nonce = readWord(proof, r);
+        require(nonce <= type(uint64).max, "invalid proof of work nonce");
+        if (GRINDING_FACTOR != 0) {
+            bytes32 digest = hashBytes(abi.encodePacked(coin.seed, uint64(nonce)));
+            require(uint256(digest) >> (256 - GRINDING_FACTOR) == 0, "insufficient proof of work");
+            // reseed with the nonce
+            coin.seed = digest;
+            coin.counter = 0;
+            coin.available = 0;
+        }
+    }
+
+    /// @dev Draws `NUM_QUERIES` distinct positions in ascending order. Matches
+    /// `rand`'s `gen_range` for a power of two range.
+    function drawQueries(PublicCoin memory coin) private pure returns (uint256[] memory positions) {
+        positions = new uint256[](NUM_QUERIES);
+        uint256 n = 0;
+        while (n < NUM_QUERIES) {
+            uint256 value = nextU64(coin);
+            if ((value << LOG_LDE_DOMAIN_SIZE) & type(uint64).max >= 1 << 63) {
+                continue;
+            }
+            uint256 position = value >> (64 - LOG_LDE_DOMAIN_SIZE);
+            uint256 i = n;
+            while (i > 0 && positions[i - 1] > position) {
+                i--;
+            }
+            if (i > 0 && positions[i - 1] == position) {
+                continue;
+            }
+            for (uint256 j = n; j > i; j--) {
+                positions[j] = positions[j - 1];
+            }
+            positions[i] = position;
+            n++;
+        }
+    }
+
+    function verifyTraceQueries(bytes calldata proof, Reader memory r, Context memory ctx)
+        private
+        pure
+    {
+        ctx.baseRows = readElements(proof, r, NUM_QUERIES * NUM_BASE_COLUMNS);
+{{BASE_GROUPS}}
+        if (NUM_EXTENSION_COLUMNS != 0) {
+            ctx.extensionRows = readElements(proof, r, NUM_QUERIES * NUM_EXTENSION_COLUMNS);
+            bytes32[] memory extensionLeaves =
+                hashRows(ctx.extensionRows, NUM_EXTENSION_COLUMNS, 0, NUM_EXTENSION_COLUMNS);
+            verifyMerkleProof(
+                proof, r, ctx.positions, extensionLeaves, LOG_LDE_DOMAIN_SIZE, ctx.extensionCap
+            );
+        }
+        ctx.compositionRows = readElements(proof, r, NUM_QUERIES * CE_BLOWUP_FACTOR);
+        bytes32[] memory compositionLeaves =
+            hashRows(ctx.compositionRows, CE_BLOWUP_FACTOR, 0, CE_BLOWUP_FACTOR);
+        verifyMerkleProof(
+            proof, r, ctx.positions, compositionLeaves, LOG_LDE_DOMAIN_SIZE, ctx.compositionCap
+        );
+    }
+
+    /// @dev Evaluates the DEEP composition polynomial at each query position
+    function computeDeepEvaluations(Context memory ctx) private view {
+        uint256 zN = expmod(ctx.z, CE_BLOWUP_FACTOR);
+        uint256[] memory zShifted = new uint256[](NUM_TRACE_ARGUMENTS);
+{{Z_SHIFTED}}
+        ctx.evaluations = new uint256[](NUM_QUERIES);
+        for (uint256 q = 0; q < NUM_QUERIES; q++) {
+            uint256 x = mulmod(
+                LDE_DOMAIN_OFFSET,
+                expmod(LDE_DOMAIN_GENERATOR, bitReverse(ctx.positions[q], LOG_LDE_DOMAIN_SIZE)),
+                P
+            );
+            uint256 eval = 0;
+{{DEEP_TRACE_TERMS}}
+            for (uint256 j = 0; j < CE_BLOWUP_FACTOR; j++) {
+                uint256 value = ctx.compositionRows[q * CE_BLOWUP_FACTOR + j];
+                eval = addmod(
+                    eval,
+                    deepTerm(ctx.deepCompositionCoeffs[j], value, ctx.compositionOods[j], x, zN),
+                    P
+                );
+            }
+            ctx.evaluations[q] = mulmod(eval, addmod(ctx.degreeAlpha, mulmod(ctx.degreeBeta, x, P), P), P);
+        }
+    }
+
+    function deepTerm(uint256 coeff, uint256 value, uint256 ood, uint256 x, uint256 point)
+        private
+        view
+        returns (uint256)
+    {
+        return mulmod(coeff, mulmod(addmod(value, P - ood, P), inv(addmod(x, P - point, P)), P), P);
+    }
+
+    function verifyFri(bytes calldata proof, Reader memory r, Context memory ctx) private view {
+        uint256[] memory omegaInvPowers = new uint256[](FRI_FOLDING_FACTOR);
+        omegaInvPowers[0] = 1;
+        for (uint256 i = 1; i < FRI_FOLDING_FACTOR; i++) {
+            omegaInvPowers[i] = mulmod(omegaInvPowers[i - 1], FRI_FOLDING_DOMAIN_GENERATOR_INV, P);
+        }
+        FriState memory s = FriState(
+            ctx.positions, ctx.evaluations, LOG_LDE_DOMAIN_SIZE, LDE_DOMAIN_GENERATOR, omegaInvPowers
+        );
+        for (uint256 layer = 0; layer < FRI_NUM_LAYERS; layer++) {
+            verifyFriLayer(proof, r, s, ctx.friCaps[layer], ctx.friAlphas[layer]);
+        }
+        for (uint256 i = FRI_REMAINDER_MAX_DEGREE + 1; i < ctx.remainder.length; i++) {
+            require(ctx.remainder[i] == 0, "fri remainder degree is too large");
+        }
+        for (uint256 q = 0; q < s.positions.length; q++) {
+            uint256 x = expmod(s.generator, bitReverse(s.positions[q], s.logDomainSize));
+            require(horner(ctx.remainder, x) == s.evaluations[q], "fri remainder is invalid");
+        }
+    }
+
+    function verifyFriLayer(
+        bytes calldata proof,
+        Reader memory r,
+        FriState memory s,
+        bytes32[] memory cap,
+        uint256 alpha
+    ) private view {
+        uint256[] memory folded = foldPositions(s.positions);
+        uint256 numValues = folded.length * FRI_FOLDING_FACTOR;
+        require(readWord(proof, r) == numValues, "invalid number of fri layer values");
+        uint256[] memory rows = readElements(proof, r, numValues);
+        s.logDomainSize -= LOG_FRI_FOLDING_FACTOR;
+        bytes32[] memory leaves = hashRows(rows, FRI_FOLDING_FACTOR, 0, FRI_FOLDING_FACTOR);
+        verifyMerkleProof(proof, r, folded, leaves, s.logDomainSize, cap);
+
+        // the layer must contain the evaluations from the previous layer
+        uint256 row = 0;
+        for (uint256 q = 0; q < s.positions.length; q++) {
+            uint256 position = s.positions[q];
+            while (folded[row] != position / FRI_FOLDING_FACTOR) {
+                row++;
+            }
+            uint256 value = rows[row * FRI_FOLDING_FACTOR + position % FRI_FOLDING_FACTOR];
+            require(value == s.evaluations[q], "invalid degree respecting projection");
+        }
+
+        s.evaluations = new uint256[](folded.length);
+        for (uint256 i = 0; i < folded.length; i++) {
+            uint256 x = expmod(s.generator, bitReverse(folded[i], s.logDomainSize));
+            uint256 alphaOverX = mulmod(alpha, inv(x), P);
+            s.evaluations[i] = foldRow(rows, i * FRI_FOLDING_FACTOR, alphaOverX, s.omegaInvPowers);
+        }
+        s.positions = folded;
+        s.generator = expmod(s.generator, FRI_FOLDING_FACTOR);
+    }
+
+    /// @dev Interpolates a row of evaluations over a coset of the folding
+    /// domain and evaluates the result (scaled by the folding factor) at alpha
+    function foldRow(
+        uint256[] memory rows,
+        uint256 offset,
+        uint256 alphaOverX,
+        uint256[] memory omegaInvPowers
+    ) private pure returns (uint256 result) {
+        uint256 alphaOverXPower = 1;
+        for (uint256 k = 0; k < FRI_FOLDING_FACTOR; k++) {
+            uint256 coeff = 0;
+            for (uint256 j = 0; j < FRI_FOLDING_FACTOR; j++) {
+                // rows store the coset evaluations in bit-reversed order
+                uint256 value = rows[offset + bitReverse(j, LOG_FRI_FOLDING_FACTOR)];
+                coeff = addmod(coeff, mulmod(value, omegaInvPowers[(j * k) % FRI_FOLDING_FACTOR], P), P);
+            }
+            result = addmod(result, mulmod(coeff, alphaOverXPower, P), P);
+            alphaOverXPower = mulmod(alphaOverXPower, alphaOverX, P);
+        }
+    }
+
+    function foldPositions(uint256[] memory positions) private pure returns (uint256[] memory folded) {
+        folded = new uint256[](positions.length);
+        uint256 n = 0;
+        for (uint256 i = 0; i < positions.length; i++) {
+            uint256 position = positions[i] / FRI_FOLDING_FACTOR;
+            if (n == 0 || folded[n - 1] != position) {
+                folded[n] = position;
+                n++;
+            }
+        }
+        assembly ("memory-safe") {
+            mstore(folded, n)
+        }
+    }
+
+    /// @dev Hashes columns `start..start + width` of each row
+    function hashRows(uint256[] memory values, uint256 rowWidth, uint256 start, uint256 width)
+        private
+        pure
+        returns (bytes32[] memory leaves)
+    {
+        leaves = new bytes32[](values.length / rowWidth);
+        for (uint256 i = 0; i < leaves.length; i++) {
+            leaves[i] = hashElements(values, i * rowWidth + start, width);
+        }
+    }
+
+    /// @dev Verifies a binary `MerkleView` encoded as the number of sibling
+    /// leaves, the sibling leaves, the number of nodes and the nodes
+    function verifyMerkleProof(
+        bytes calldata proof,
+        Reader memory r,
+        uint256[] memory positions,
+        bytes32[] memory nodes,
+        uint256 height,
+        bytes32[] memory cap
+    ) private pure {
+        uint256[] memory indices = new uint256[](positions.length);
+        for (uint256 i = 0; i < positions.length; i++) {
+            indices[i] = positions[i];
+        }
+        uint256 capLevel = height - 1 < MERKLE_CAP_HEIGHT ? height - 1 : MERKLE_CAP_HEIGHT;
+        uint256 n = positions.length;
+        uint256 numSiblings = readWord(proof, r);
+        (n, numSiblings) = hashLevel(proof, r, indices, nodes, n, numSiblings);
+        require(numSiblings == 0, "invalid merkle proof");
+        numSiblings = readWord(proof, r);
+        for (uint256 level = height - 1; level > capLevel; level--) {
+            (n, numSiblings) = hashLevel(proof, r, indices, nodes, n, numSiblings);
+        }
+        require(numSiblings == 0, "invalid merkle proof");
+        for (uint256 i = 0; i < n; i++) {
+            require(nodes[i] == cap[indices[i]], "merkle proof does not resolve to the commitment");
+        }
+    }
+
+    /// @dev Hashes the first `n` nodes (sorted by index) with their siblings.
+    /// Replaces them with their parents and returns the number of parents.
+    function hashLevel(
+        bytes calldata proof,
+        Reader memory r,
+        uint256[] memory indices,
+        bytes32[] memory nodes,
+        uint256 n,
+        uint256 numSiblings
+    ) private pure returns (uint256 m, uint256) {
+        uint256 i = 0;
+        while (i < n) {
+            uint256 index = indices[i];
+            bytes32 left = nodes[i];
+            bytes32 right;
+            i++;
+            if (index & 1 == 0 && i < n && indices[i] == index + 1) {
+                right = nodes[i];
+                i++;
+            } else {
+                require(numSiblings != 0, "invalid merkle proof");
+                numSiblings--;
+                bytes32 sibling = bytes32(readWord(proof, r));
+                if (index & 1 == 0) {
+                    right = sibling;
+                } else {
+                    right = left;
+                    left = sibling;
+                }
+            }
+            nodes[m] = hashBytes(abi.encodePacked(left, right));
+            indices[m] = index >> 1;
+            m++;
+        }
+        return (m, numSiblings);
+    }
+
+    /// @dev Returns the number of nodes in the merkle cap of a binary tree
+    function merkleCapSize(uint256 height) private pure returns (uint256) {
+        return 1 << (height - 1 < MERKLE_CAP_HEIGHT ? height - 1 : MERKLE_CAP_HEIGHT);
+    }
+
+    function readCap(bytes calldata proof, Reader memory r, PublicCoin memory coin, uint256 size)
+        private
+        pure
+        returns (bytes32[] memory cap)
+    {
+        cap = new bytes32[](size);
+        for (uint256 i = 0; i < size; i++) {
+            cap[i] = bytes32(readWord(proof, r));
+            reseed(coin, cap[i]);
+        }
+    }
+
+    function readElements(bytes calldata proof, Reader memory r, uint256 n)
+        private
+        pure
+        returns (uint256[] memory elements)
+    {
+        elements = new uint256[](n);
+        for (uint256 i = 0; i < n; i++) {
+            elements[i] = readWord(proof, r);
+            require(elements[i] < P, "invalid field element");
+        }
+    }
+
+    function readWord(bytes calldata proof, Reader memory r) private pure returns (uint256 word) {
+        require(r.pos + 32 <= proof.length, "proof is too short");
+        word = uint256(bytes32(proof[r.pos:r.pos + 32]));
+        r.pos += 32;
+    }
+
+    function reseed(PublicCoin memory coin, bytes32 digest) private pure {
+        coin.seed = hashBytes(abi.encodePacked(coin.seed, digest));
+        coin.counter = 0;
+        coin.available = 0;
+    }
+
+    function reseedWithElement(PublicCoin memory coin, uint256 element) private pure {
+        uint256[] memory elements = new uint256[](1);
+        elements[0] = element;
+        reseed(coin, hashElements(elements, 0, 1));
+    }
+
+    function nextByte(PublicCoin memory coin) private pure returns (uint256) {
+        if (coin.available == 0) {
+            coin.counter++;
+            coin.digest = hashBytes(abi.encodePacked(coin.seed, coin.counter));
+            coin.available = 32;
+        }
+        // bytes are taken from the end of the digest
+        coin.available--;
+        return uint8(coin.digest[coin.available]);
+    }
+
+    function nextU64(PublicCoin memory coin) private pure returns (uint256 value) {
+        for (uint256 i = 0; i < 8; i++) {
+            value = (value << 8) | nextByte(coin);
+        }
+    }
+
+    function draw(PublicCoin memory coin) private pure returns (uint256) {
+        uint256 raw;
+        do {
+            raw = 0;
+            for (uint256 i = 0; i < NUM_LIMBS; i++) {
+                raw |= nextU64(coin) << (64 * i);
+            }
+            raw &= DRAW_MASK;
+        } while (raw >= P);
+        return mulmod(raw, DRAW_FACTOR, P);
+    }
+
+    function drawMany(PublicCoin memory coin, uint256 n) private pure returns (uint256[] memory values) {
+        values = new uint256[](n);
+        for (uint256 i = 0; i < n; i++) {
+            values[i] = draw(coin);
+        }
+    }
+
+    function hashElements(uint256[] memory values, uint256 start, uint256 len)
+        private
+        pure
+        returns (bytes32)
+    {
+        bytes memory buffer = new bytes(len * ELEMENT_BYTES);
+        for (uint256 i = 0; i < len; i++) {
+            uint256 value = values[start + i];
+            for (uint256 j = 0; j < ELEMENT_BYTES; j++) {
+                buffer[i * ELEMENT_BYTES + j] = bytes1(uint8(value >> (8 * j)));
+            }
+        }
+        return hashBytes(buffer);
+    }
+
+    function hashBytes(bytes memory data) private pure returns (bytes32) {
+        return {{HASH_FN}}(data);
+    }
+
+    function horner(uint256[] memory coeffs, uint256 x) private pure returns (uint256 result) {
+        for (uint256 i = coeffs.length; i > 0; i--) {
+            result = addmod(mulmod(result, x, P), coeffs[i - 1], P);
+        }
+    }
+
+    function bitReverse(uint256 index, uint256 numBits) private pure returns (uint256 result) {
+        for (uint256 i = 0; i < numBits; i++) {
+            result = (result << 1) | ((index >> i) & 1);
+        }
+    }
+
+    function inv(uint256 value) private view returns (uint256) {
+        require(value != 0, "division by zero");
+        return expmod(value, P - 2);
+    }
+
+    function expmod(uint256 base, uint256 exponent) private view returns (uint256 result) {
+        assembly ("memory-safe") {
+            let ptr := mload(0x40)
+            mstore(ptr, 0x20)
+            mstore(add(ptr, 0x20), 0x20)
+            mstore(add(ptr, 0x40), 0x20)
+            mstore(add(ptr, 0x60), base)
+            mstore(add(ptr, 0x80), exponent)
+            mstore(add(ptr, 0xa0), P)
+            if iszero(staticcall(gas(), 0x05, ptr, 0xc0, ptr, 0x20)) {
+                revert(0, 0)
+            }
+            result := mload(ptr)
+        }
+    }
+
+    /// @dev Evaluates the composition constraint at the out-of-domain point
+    function evaluateCompositionConstraint(Context memory ctx) private view returns (uint256) {
+        uint256[] memory t = new uint256[]({{NUM_TEMPS}});
+{{COMPOSITION_CONSTRAINT}}
+        return {{COMPOSITION_RESULT}};
+    }
+}
@@ -1,30 +1,54 @@
 use crate::air::AirConfig;
+use crate::air::PreparedAir;
+#[cfg(feature = "prover")]
 use crate::challenges::Challenges;
 use crate::channel::VerifierChannelArtifacts;
 use crate::composer::DeepCompositionCoeffs;
+#[cfg(feature = "prover")]
 use crate::debug::default_validate_constraints;
 use crate::hash::Digest;
+#[cfg(feature = "prover")]
 use crate::hints::Hints;
 use crate::merkle::MatrixMerkleTree;
 use crate::merkle::MerkleTree;
+#[cfg(feature = "prover")]
 use crate::prover::default_prove;
+#[cfg(feature = "prover")]
+use crate::prover::prove_with_lde_storage;
+#[cfg(feature = "prover")]
+use crate::prover::LdeStorage;
+#[cfg(feature = "prover")]
 use crate::prover::ProvingError;
 use crate::random::draw_multiple;
 use crate::random::PublicCoin;
+use crate::security::SecurityModel;
 use crate::verifier::default_verify;
+use crate::verifier::default_verify_and_explain;
+use crate::verifier::default_verify_with_prepared_air;
 use crate::verifier::VerificationError;
+use crate::verifier::VerifierExplanation;
 use crate::Air;
+#[cfg(feature = "prover")]
 use crate::Matrix;
+use crate::Pod;
 use crate::Proof;
 use crate::ProofOptions;
 use crate::StarkExtensionOf;
 use crate::Trace;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 use ark_ff::FftField;
+use ark_serialize::CanonicalSerialize;
 use ministark_gpu::GpuFftField;
 
+/// Prefix of the default public coin seed
+pub const PUBLIC_COIN_DOMAIN_SEPARATOR: &[u8] = b"ministark-public-coin-v1";
+
 pub trait Stark: Sized + Send + Sync {
-    type Fp: GpuFftField + FftField;
-    type Fq: StarkExtensionOf<Self::Fp>;
+    type Fp: GpuFftField + FftField + Pod;
+    type Fq: StarkExtensionOf<Self::Fp> + Pod;
     type AirConfig: AirConfig<Fp = Self::Fp, Fq = Self::Fq>;
     type PublicCoin: PublicCoin<Digest = Self::Digest, Field = Self::Fq>;
     type MerkleTree: MerkleTree<Root = Self::Digest>
@@ -36,7 +60,35 @@ pub trait Stark: Sized + Send + Sync {
 
     fn get_public_inputs(&self) -> <Self::AirConfig as AirConfig>::PublicInputs;
 
-    fn gen_public_coin(&self, air: &Air<Self::AirConfig>) -> Self::PublicCoin;
+    /// Returns the public coin used for Fiat-Shamir. The default seeds the
+    /// coin with [`Self::public_coin_seed`].
+    fn gen_public_coin(&self, air: &Air<Self::AirConfig>) -> Self::PublicCoin {
+        Self::PublicCoin::from_seed(&self.public_coin_seed(air))
+    }
+
+    /// Returns the seed of the default public coin. Binds
+    /// [`PUBLIC_COIN_DOMAIN_SEPARATOR`], [`Self::air_id`], the proof options,
+    /// the trace length and the public inputs.
+    fn public_coin_seed(&self, air: &Air<Self::AirConfig>) -> Vec<u8> {
+        let mut seed = PUBLIC_COIN_DOMAIN_SEPARATOR.to_vec();
+        self.air_id().serialize_compressed(&mut seed).unwrap();
+        air.options().serialize_compressed(&mut seed).unwrap();
+        air.trace_len().serialize_compressed(&mut seed).unwrap();
+        air.public_inputs().serialize_compressed(&mut seed).unwrap();
+        seed
+    }
+
+    /// Identifies the hash functions used by the merkle trees and public coin.
+    /// Stored in proof envelopes.
+    fn hash_id(&self) -> String {
+        format!("{};{}", Self::MerkleTree::id(), Self::PublicCoin::id())
+    }
+
+    /// Identifies the AIR. Bound into the default public coin seed and stored
+    /// in proof envelopes. Defaults to [`AirConfig::AIR_ID`].
+    fn air_id(&self) -> String {
+        Self::AirConfig::AIR_ID.to_string()
+    }
 
     fn gen_deep_coeffs(
         &self,
@@ -54,6 +106,7 @@ pub trait Stark: Sized + Send + Sync {
 
     fn generate_trace(&self, witness: Self::Witness) -> Self::Trace;
 
+    #[cfg(feature = "prover")]
     async fn prove(
         &self,
         options: ProofOptions,
@@ -62,7 +115,36 @@ pub trait Stark: Sized + Send + Sync {
         default_prove(self, options, witness)
     }
 
+    /// Generates the same proof as [`Self::prove`] with lower peak memory.
+    /// Low degree extensions are built in chunks of at most
+    /// `lde_memory_budget` bytes and dropped after hashing. The budget doesn't
+    /// bound the rest of the prover (see [`LdeStorage::Recompute`]).
+    #[cfg(feature = "prover")]
+    async fn prove_with_lde_memory_budget(
+        &self,
+        options: ProofOptions,
+        witness: Self::Witness,
+        lde_memory_budget: usize,
+    ) -> Result<Proof<Self>, ProvingError> {
+        let lde_storage = LdeStorage::Recompute { lde_memory_budget };
+        prove_with_lde_storage(self, options, witness, &lde_storage)
+    }
+
+    /// Generates the same proof as [`Self::prove`] but keeps low degree
+    /// extensions in `lde_storage` rather than in memory e.g. in
+    /// memory-mapped files with `LdeStorage::Mmap` (`mmap` feature).
+    #[cfg(feature = "prover")]
+    async fn prove_with_lde_storage(
+        &self,
+        options: ProofOptions,
+        witness: Self::Witness,
+        lde_storage: &LdeStorage,
+    ) -> Result<Proof<Self>, ProvingError> {
+        prove_with_lde_storage(self, options, witness, lde_storage)
+    }
+
     /// Check the AIR constraints are valid
+    #[cfg(feature = "prover")]
     fn validate_constraints(
         &self,
         challenges: &Challenges<Self::Fq>,
@@ -74,12 +156,96 @@ pub trait Stark: Sized + Send + Sync {
         default_validate_constraints(self, challenges, hints, base_trace, extension_trace);
     }
 
-    #[allow(clippy::too_many_lines)]
+    /// Verifies a proof using the conjectured security model
     fn verify(
         &self,
         proof: Proof<Self>,
         required_security_bits: u32,
     ) -> Result<VerifierChannelArtifacts<Self::Fq>, VerificationError> {
-        default_verify(self, proof, required_security_bits)
+        self.verify_with_security_model(proof, required_security_bits, SecurityModel::Conjectured)
+    }
+
+    /// Verifies a proof. The proof must satisfy `required_security_bits` under
+    /// the given security model.
+    fn verify_with_security_model(
+        &self,
+        proof: Proof<Self>,
+        required_security_bits: u32,
+        security_model: SecurityModel,
+    ) -> Result<VerifierChannelArtifacts<Self::Fq>, VerificationError> {
+        default_verify(self, proof, required_security_bits, security_model)
+    }
+
+    /// Verifies a proof and records the intermediate values computed by the
+    /// verifier in `explanation`
+    fn verify_and_explain(
+        &self,
+        proof: Proof<Self>,
+        required_security_bits: u32,
+        security_model: SecurityModel,
+        explanation: &mut VerifierExplanation<Self::Fq>,
+    ) -> Result<VerifierChannelArtifacts<Self::Fq>, VerificationError> {
+        default_verify_and_explain(
+            self,
+            proof,
+            required_security_bits,
+            security_model,
+            explanation,
+        )
+    }
+
+    /// Verifies a proof using constraints that have already been built for
+    /// the proof's trace length. Every other verification method (and
+    /// [`crate::verifier::PreparedVerifier`]) ends up here so this is the
+    /// method to override to add checks to verification.
+    ///
+    /// # Panics
+    /// Panics if `prepared_air` was built for a different trace length
+    fn verify_with_prepared_air(
+        &self,
+        prepared_air: &Arc<PreparedAir<Self::AirConfig>>,
+        proof: Proof<Self>,
+        required_security_bits: u32,
+        security_model: SecurityModel,
+        explanation: &mut VerifierExplanation<Self::Fq>,
+    ) -> Result<VerifierChannelArtifacts<Self::Fq>, VerificationError> {
+        default_verify_with_prepared_air(
+            self,
+            prepared_air,
+            proof,
+            required_security_bits,
+            security_model,
+            explanation,
+        )
+    }
+}
+
+/// Checks the public coin of each claim depends on its public inputs.
+///
+/// Intended for tests of [`Stark::gen_public_coin`] implementations: a public
+/// coin that misses part of the public inputs breaks Fiat-Shamir soundness.
+///
+/// # Panics
+/// Panics if two claims with different public inputs draw the same challenges
+pub fn assert_public_inputs_bound<S: Stark>(claims: &[S], trace_len: usize, options: ProofOptions) {
+    let draws = claims
+        .iter()
+        .map(|claim| {
+            let public_inputs = claim.get_public_inputs();
+            let mut serialized = Vec::new();
+            public_inputs.serialize_compressed(&mut serialized).unwrap();
+            let air = Air::new(trace_len, public_inputs, options);
+            let mut public_coin = claim.gen_public_coin(&air);
+            let draws: [S::Fq; 2] = [public_coin.draw(), public_coin.draw()];
+            (serialized, draws)
+        })
+        .collect::<Vec<_>>();
+    for (i, (a_public_inputs, a_draws)) in draws.iter().enumerate() {
+        for (j, (b_public_inputs, b_draws)) in draws.iter().enumerate().skip(i + 1) {
+            assert!(
+                a_public_inputs == b_public_inputs || a_draws != b_draws,
+                "claims {i} and {j} have different public inputs but the same public coin"
+            );
+        }
     }
 }
@@ -1,7 +1,13 @@
+use crate::air::AirConfig;
 use crate::challenges::Challenges;
+use crate::merkle;
 use crate::merkle::MatrixMerkleTree;
+use crate::merkle::MerkleCap;
 use crate::merkle::MerkleTree;
 use crate::stark::Stark;
+use crate::verifier::TraceKind;
+use crate::verifier::VerificationError;
+use crate::Air;
 use crate::Matrix;
 use alloc::vec::Vec;
 use ark_ff::FftField;
@@ -9,6 +15,7 @@ use ark_ff::Field;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use ark_serialize::Valid;
+use core::iter::zip;
 
 /// STARK execution trace
 #[allow(clippy::len_without_is_empty)]
@@ -35,11 +42,119 @@ pub trait Trace: Send + Sync {
     }
 }
 
+/// Rows of a base column group opened at a set of positions
+///
+/// Each base column group is committed to with its own merkle tree so the
+/// rows of a group can be opened and verified independently of the other
+/// groups. For example, the rows of a static table can be opened at positions
+/// of the caller's choosing by rebuilding the table's tree and calling
+/// [`BaseGroupQueries::new`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
+pub struct BaseGroupQueries<C: Stark> {
+    /// Opened rows of the group (row-major)
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
+    pub values: Vec<C::Fp>,
+    pub proof: <C::MerkleTree as MerkleTree>::Proof,
+}
+
+impl<C: Stark> CanonicalSerialize for BaseGroupQueries<C> {
+    fn serialize_with_mode<W: ark_serialize::Write>(
+        &self,
+        mut writer: W,
+        compress: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.values.serialize_with_mode(&mut writer, compress)?;
+        self.proof.serialize_with_mode(&mut writer, compress)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: ark_serialize::Compress) -> usize {
+        self.values.serialized_size(compress) + self.proof.serialized_size(compress)
+    }
+}
+
+impl<C: Stark> Valid for BaseGroupQueries<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        Ok(())
+    }
+}
+
+impl<C: Stark> CanonicalDeserialize for BaseGroupQueries<C> {
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        Ok(Self {
+            values: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            proof: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl<C: Stark> Clone for BaseGroupQueries<C> {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            proof: self.proof.clone(),
+        }
+    }
+}
+
+impl<C: Stark> BaseGroupQueries<C> {
+    /// Opens the rows of a group at `positions`. `rows` are the group's rows
+    /// at each position and `tree` is the group's merkle tree.
+    pub fn new(
+        rows: &[&[C::Fp]],
+        tree: &C::MerkleTree,
+        positions: &[usize],
+        merkle_cap_height: u32,
+    ) -> Self {
+        assert_eq!(rows.len(), positions.len());
+        let proof =
+            MatrixMerkleTree::<C::Fp>::prove_rows_to_cap(tree, positions, merkle_cap_height)
+                .unwrap();
+        Self {
+            values: rows.concat(),
+            proof,
+        }
+    }
+
+    /// Returns the opened rows of a group with `num_columns` columns
+    pub fn rows(&self, num_columns: usize) -> Vec<&[C::Fp]> {
+        self.values.chunks(num_columns).collect()
+    }
+
+    /// Verifies the opened rows of a group with `num_columns` columns against
+    /// the group's commitment
+    pub fn verify(
+        &self,
+        commitment: &MerkleCap<C::Digest>,
+        num_columns: usize,
+        positions: &[usize],
+    ) -> Result<(), merkle::Error> {
+        if self.values.len() != positions.len() * num_columns {
+            return Err(merkle::Error::InvalidProof);
+        }
+        C::MerkleTree::verify_rows_to_cap(
+            commitment,
+            positions,
+            &self.rows(num_columns),
+            self.proof.clone(),
+        )
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound = ""))]
 pub struct Queries<C: Stark> {
-    pub base_trace_values: Vec<C::Fp>,
+    /// Opened rows of each base column group
+    pub base_trace_groups: Vec<BaseGroupQueries<C>>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub extension_trace_values: Vec<C::Fq>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::hex::vec"))]
     pub composition_trace_values: Vec<C::Fq>,
-    pub base_trace_proof: <C::MerkleTree as MerkleTree>::Proof,
     pub extension_trace_proof: Option<<C::MerkleTree as MerkleTree>::Proof>,
     pub composition_trace_proof: <C::MerkleTree as MerkleTree>::Proof,
 }
@@ -50,14 +165,12 @@ impl<C: Stark> CanonicalSerialize for Queries<C> {
         mut writer: W,
         compress: ark_serialize::Compress,
     ) -> Result<(), ark_serialize::SerializationError> {
-        self.base_trace_values
+        self.base_trace_groups
             .serialize_with_mode(&mut writer, compress)?;
         self.extension_trace_values
             .serialize_with_mode(&mut writer, compress)?;
         self.composition_trace_values
             .serialize_with_mode(&mut writer, compress)?;
-        self.base_trace_proof
-            .serialize_with_mode(&mut writer, compress)?;
         self.extension_trace_proof
             .serialize_with_mode(&mut writer, compress)?;
         self.composition_trace_proof
@@ -66,10 +179,9 @@ impl<C: Stark> CanonicalSerialize for Queries<C> {
     }
 
     fn serialized_size(&self, compress: ark_serialize::Compress) -> usize {
-        self.base_trace_values.serialized_size(compress)
+        self.base_trace_groups.serialized_size(compress)
             + self.extension_trace_values.serialized_size(compress)
             + self.composition_trace_values.serialized_size(compress)
-            + self.base_trace_proof.serialized_size(compress)
             + self.extension_trace_proof.serialized_size(compress)
             + self.composition_trace_proof.serialized_size(compress)
     }
@@ -88,10 +200,9 @@ impl<C: Stark> CanonicalDeserialize for Queries<C> {
         validate: ark_serialize::Validate,
     ) -> Result<Self, ark_serialize::SerializationError> {
         Ok(Self {
-            base_trace_values: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
+            base_trace_groups: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             extension_trace_values: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             composition_trace_values: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
-            base_trace_proof: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             extension_trace_proof: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
             composition_trace_proof: <_>::deserialize_with_mode(&mut reader, compress, validate)?,
         })
@@ -101,10 +212,9 @@ impl<C: Stark> CanonicalDeserialize for Queries<C> {
 impl<C: Stark> Clone for Queries<C> {
     fn clone(&self) -> Self {
         Self {
-            base_trace_values: self.base_trace_values.clone(),
+            base_trace_groups: self.base_trace_groups.clone(),
             extension_trace_values: self.extension_trace_values.clone(),
             composition_trace_values: self.composition_trace_values.clone(),
-            base_trace_proof: self.base_trace_proof.clone(),
             extension_trace_proof: self.extension_trace_proof.clone(),
             composition_trace_proof: self.composition_trace_proof.clone(),
         }
@@ -113,46 +223,119 @@ impl<C: Stark> Clone for Queries<C> {
 
 impl<C: Stark> Queries<C> {
     pub fn new(
+        air: &Air<C::AirConfig>,
         base_trace_lde: &Matrix<C::Fp>,
         extension_trace_lde: Option<&Matrix<C::Fq>>,
         composition_trace_lde: &Matrix<C::Fq>,
-        base_tree: &C::MerkleTree,
+        base_trees: &[C::MerkleTree],
         extension_tree: Option<&C::MerkleTree>,
         composition_tree: &C::MerkleTree,
         positions: &[usize],
     ) -> Self {
-        let base_trace_proof = MatrixMerkleTree::<C::Fp>::prove_rows(base_tree, positions).unwrap();
+        Self::from_row_fns(
+            air,
+            |position| base_trace_lde.get_row(position).unwrap(),
+            extension_trace_lde.map(|lde| move |position| lde.get_row(position).unwrap()),
+            |position| composition_trace_lde.get_row(position).unwrap(),
+            base_trees,
+            extension_tree,
+            composition_tree,
+            positions,
+        )
+    }
+
+    /// Like [`Queries::new`] but reads the rows of the low degree extensions
+    /// with `base_trace_row`, `extension_trace_row` and
+    /// `composition_trace_row`. Allows the prover to recompute rows rather
+    /// than keep the low degree extensions in memory.
+    #[allow(clippy::too_many_arguments, clippy::needless_pass_by_value)]
+    pub fn from_row_fns(
+        air: &Air<C::AirConfig>,
+        base_trace_row: impl Fn(usize) -> Vec<C::Fp>,
+        extension_trace_row: Option<impl Fn(usize) -> Vec<C::Fq>>,
+        composition_trace_row: impl Fn(usize) -> Vec<C::Fq>,
+        base_trees: &[C::MerkleTree],
+        extension_tree: Option<&C::MerkleTree>,
+        composition_tree: &C::MerkleTree,
+        positions: &[usize],
+    ) -> Self {
+        let merkle_cap_height = u32::from(air.options().merkle_cap_height);
+        // each column group is opened separately
+        let base_trace_rows = positions
+            .iter()
+            .map(|&position| base_trace_row(position))
+            .collect::<Vec<_>>();
+        let base_trace_groups = zip(base_trees, air.base_column_groups())
+            .map(|(tree, columns)| {
+                let rows = base_trace_rows
+                    .iter()
+                    .map(|row| &row[columns.clone()])
+                    .collect::<Vec<_>>();
+                BaseGroupQueries::new(&rows, tree, positions, merkle_cap_height)
+            })
+            .collect();
         let extension_trace_proof = extension_tree.map(|extension_tree| {
-            MatrixMerkleTree::<C::Fq>::prove_rows(extension_tree, positions).unwrap()
+            MatrixMerkleTree::<C::Fq>::prove_rows_to_cap(
+                extension_tree,
+                positions,
+                merkle_cap_height,
+            )
+            .unwrap()
         });
-        let composition_trace_proof =
-            MatrixMerkleTree::<C::Fq>::prove_rows(composition_tree, positions).unwrap();
+        let composition_trace_proof = MatrixMerkleTree::<C::Fq>::prove_rows_to_cap(
+            composition_tree,
+            positions,
+            merkle_cap_height,
+        )
+        .unwrap();
 
-        let mut base_trace_values = Vec::new();
         let mut extension_trace_values = Vec::new();
         let mut composition_trace_values = Vec::new();
         for &position in positions {
-            // execution trace
-            let base_trace_row = base_trace_lde.get_row(position).unwrap();
-            base_trace_values.extend(base_trace_row);
-
-            if let Some(extension_trace_lde) = extension_trace_lde {
+            if let Some(extension_trace_row) = &extension_trace_row {
                 // TODO: suport ark DomainCoeff on evaluate_at
-                let extension_trace_row = extension_trace_lde.get_row(position).unwrap();
-                extension_trace_values.extend(extension_trace_row);
+                extension_trace_values.extend(extension_trace_row(position));
             }
 
             // composition trace
-            let composition_trace_row = composition_trace_lde.get_row(position).unwrap();
-            composition_trace_values.extend(composition_trace_row);
+            composition_trace_values.extend(composition_trace_row(position));
         }
         Self {
-            base_trace_values,
+            base_trace_groups,
             extension_trace_values,
             composition_trace_values,
-            base_trace_proof,
             extension_trace_proof,
             composition_trace_proof,
         }
     }
+
+    /// Returns the opened base trace rows (row-major) i.e. the opened rows of
+    /// all base column groups joined together
+    ///
+    /// # Errors
+    /// Returns [`VerificationError::InvalidNumTraceQueryValues`] if the groups
+    /// don't have the same number of rows
+    pub fn base_trace_values(&self) -> Result<Vec<C::Fp>, VerificationError> {
+        let groups = zip(&self.base_trace_groups, C::AirConfig::BASE_COLUMN_GROUPS);
+        let num_rows = groups
+            .clone()
+            .next()
+            .map_or(0, |(group, &num_columns)| group.values.len() / num_columns);
+        let mut group_rows = Vec::new();
+        for (group, &num_columns) in groups {
+            let expected = num_rows * num_columns;
+            if group.values.len() != expected {
+                return Err(VerificationError::InvalidNumTraceQueryValues {
+                    trace: TraceKind::Base,
+                    expected,
+                    actual: group.values.len(),
+                });
+            }
+            group_rows.push(group.rows(num_columns));
+        }
+        Ok((0..num_rows)
+            .flat_map(|i| group_rows.iter().flat_map(move |rows| rows[i]))
+            .copied()
+            .collect())
+    }
 }
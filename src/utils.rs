@@ -14,20 +14,21 @@ use ark_serialize::Valid;
 use core::alloc::AllocError;
 use core::alloc::Allocator;
 use core::alloc::Layout;
+use core::fmt::Debug;
 use core::fmt::Display;
+use core::iter::zip;
 use core::ops::Add;
 use core::ops::AddAssign;
+use core::ops::Deref;
+use core::ops::DerefMut;
 use core::ops::Div;
 use core::ops::Mul;
 use core::ops::Neg;
 use core::ptr::NonNull;
+use ministark_gpu::utils::bit_reverse_index;
 use num_traits::Pow;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use std::fmt::Debug;
-use std::iter::zip;
-use std::ops::Deref;
-use std::ops::DerefMut;
 
 #[cfg(feature = "std")]
 pub struct Timer<'a> {
@@ -181,6 +182,26 @@ pub fn field_bits<F: Field>() -> u32 {
     extension_field_degree * base_field_bits
 }
 
+/// Splits the bit-reversed evaluations over `domain` into `num_chunks` chunks
+/// of consecutive rows.
+///
+/// Returns the domain of chunk `i`: the chunk holds the bit-reversed
+/// evaluations over this coset of a smaller subgroup.
+pub fn bit_reversed_chunk_domain<F: FftField>(
+    domain: Radix2EvaluationDomain<F>,
+    num_chunks: usize,
+    i: usize,
+) -> Radix2EvaluationDomain<F> {
+    assert!(num_chunks.is_power_of_two());
+    assert!(i < num_chunks);
+    if num_chunks == 1 {
+        return domain;
+    }
+
+    let offset = domain.element(bit_reverse_index(num_chunks, i));
+    Radix2EvaluationDomain::new_coset(domain.size() / num_chunks, offset).unwrap()
+}
+
 // TODO: docs
 pub fn reduce_lde_blowup_factor<T: Copy>(
     lde: &mut GpuVec<T>,
@@ -492,6 +513,20 @@ mod page_aligned_allocator {
     }
 }
 
+/// Requires serde support when the `serde` feature is enabled
+#[cfg(feature = "serde")]
+pub trait SerdeBound: serde::Serialize + serde::de::DeserializeOwned {}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize + serde::de::DeserializeOwned> SerdeBound for T {}
+
+/// Requires serde support when the `serde` feature is enabled
+#[cfg(not(feature = "serde"))]
+pub trait SerdeBound {}
+
+#[cfg(not(feature = "serde"))]
+impl<T> SerdeBound for T {}
+
 /// Wrapper around a digest to implement serialize and deserialize traits
 pub struct SerdeOutput<D: digest::Digest>(digest::Output<D>);
 
@@ -518,7 +553,7 @@ impl<D: digest::Digest> PartialEq for SerdeOutput<D> {
 }
 
 impl<D: digest::Digest> Debug for SerdeOutput<D> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_tuple("SerdeOutput").field(&self.0).finish()
     }
 }
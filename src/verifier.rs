@@ -1,4 +1,6 @@
+use crate::air;
 use crate::air::AirConfig;
+use crate::air::PreparedAir;
 use crate::challenges::Challenges;
 use crate::channel::VerifierChannelArtifacts;
 use crate::composer::DeepCompositionCoeffs;
@@ -7,37 +9,111 @@ use crate::constraints::CompositionItem;
 use crate::fri;
 use crate::fri::FriVerifier;
 use crate::hints::Hints;
+use crate::merkle;
 use crate::merkle::MatrixMerkleTree;
+use crate::merkle::MerkleTree;
 use crate::random::draw_multiple;
 use crate::random::PublicCoin;
+use crate::security::SecurityModel;
+use crate::security::SecurityParams;
 use crate::stark::Stark;
 use crate::utils::horner_evaluate;
 use crate::utils::FieldVariant;
 use crate::Air;
 use crate::Proof;
 use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use ark_ff::Field;
 use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
+use core::fmt::Display;
+use core::iter::zip;
+use core::marker::PhantomData;
 use ministark_gpu::utils::bit_reverse_index;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use snafu::Snafu;
 
-#[allow(clippy::too_many_lines)]
 pub fn default_verify<S: Stark>(
     this: &S,
     proof: Proof<S>,
     required_security_bits: u32,
+    security_model: SecurityModel,
 ) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
-    use VerificationError::*;
+    default_verify_and_explain(
+        this,
+        proof,
+        required_security_bits,
+        security_model,
+        &mut VerifierExplanation::default(),
+    )
+}
 
-    if proof.security_level_bits() < required_security_bits {
-        return Err(InvalidProofSecurity);
-    }
+/// Intermediate values computed by the verifier. Populated in transcript order
+/// so if verification fails it contains every value computed before the
+/// failure.
+#[derive(Debug, Clone, Default)]
+pub struct VerifierExplanation<F: Field> {
+    pub security_level_bits: Option<u32>,
+    pub air_challenges: Vec<F>,
+    pub composition_coeffs: Vec<F>,
+    /// Out-of-domain point
+    pub z: Option<F>,
+    pub calculated_ood_constraint_evaluation: Option<F>,
+    pub provided_ood_constraint_evaluation: Option<F>,
+    /// Term of each constraint in the calculated out-of-domain constraint
+    /// evaluation. Only computed if the evaluations are inconsistent.
+    pub ood_constraint_terms: Vec<F>,
+    pub deep_execution_trace_coeffs: Vec<F>,
+    pub deep_composition_trace_coeffs: Vec<F>,
+    pub fri_alphas: Vec<F>,
+    pub query_positions: Vec<usize>,
+    /// Evaluations of the DEEP composition polynomial at the query positions
+    pub deep_evaluations: Vec<F>,
+}
+
+/// Verifies a proof and records intermediate transcript values in
+/// `explanation`. Useful for debugging integrations.
+pub fn default_verify_and_explain<S: Stark>(
+    this: &S,
+    proof: Proof<S>,
+    required_security_bits: u32,
+    security_model: SecurityModel,
+    explanation: &mut VerifierExplanation<S::Fq>,
+) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
+    let prepared_air = PreparedAir::cached(proof.trace_len)
+        .map_err(|source| VerificationError::InvalidTraceLen { source })?;
+    this.verify_with_prepared_air(
+        &prepared_air,
+        proof,
+        required_security_bits,
+        security_model,
+        explanation,
+    )
+}
+
+/// Verifies a proof using constraints that have already been built for the
+/// proof's trace length
+///
+/// # Panics
+/// Panics if `prepared_air` was built for a different trace length
+#[allow(clippy::too_many_lines)]
+pub fn default_verify_with_prepared_air<S: Stark>(
+    this: &S,
+    prepared_air: &Arc<PreparedAir<S::AirConfig>>,
+    proof: Proof<S>,
+    required_security_bits: u32,
+    security_model: SecurityModel,
+    explanation: &mut VerifierExplanation<S::Fq>,
+) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
+    use VerificationError::*;
 
     let Proof {
         options,
-        base_trace_commitment,
+        base_trace_commitments,
         extension_trace_commitment,
         composition_trace_commitment,
         execution_trace_ood_evals,
@@ -49,24 +125,105 @@ pub fn default_verify<S: Stark>(
         ..
     } = proof;
 
-    let air = Air::new(trace_len, this.get_public_inputs(), options);
+    assert_eq!(
+        prepared_air.trace_len(),
+        trace_len,
+        "AIR has a different trace length"
+    );
+    let prepared_air = Arc::clone(prepared_air);
+    let air = Air::try_from_prepared(prepared_air, this.get_public_inputs(), options)
+        .map_err(|source| InvalidOptions { source })?;
+    let security_params = SecurityParams::new::<S>(&air);
+    let security_level_bits = security_params.security_level_bits(security_model);
+    explanation.security_level_bits = Some(security_level_bits);
+    if security_level_bits < required_security_bits {
+        return Err(InvalidProofSecurity {
+            required: required_security_bits,
+            actual: security_level_bits,
+        });
+    }
+
     let mut public_coin = this.gen_public_coin(&air);
 
-    public_coin.reseed_with_digest(&base_trace_commitment);
+    let base_column_groups = air.base_column_groups();
+    if base_trace_commitments.len() != base_column_groups.len()
+        || trace_queries.base_trace_groups.len() != base_column_groups.len()
+    {
+        return Err(BaseTraceCommitmentsMismatch {
+            expected: base_column_groups.len(),
+            commitments: base_trace_commitments.len(),
+            proofs: trace_queries.base_trace_groups.len(),
+        });
+    }
+
+    let num_extension_columns = S::AirConfig::NUM_EXTENSION_COLUMNS;
+    match (num_extension_columns, &extension_trace_commitment) {
+        (0, Some(_)) => return Err(UnexpectedExtensionTraceCommitment),
+        (1.., None) => {
+            return Err(MissingExtensionTraceCommitment {
+                num_columns: num_extension_columns,
+            })
+        }
+        _ => {}
+    }
+    if extension_trace_commitment.is_none() && trace_queries.extension_trace_proof.is_some() {
+        return Err(UnexpectedExtensionTraceProof);
+    }
+
+    let num_trace_arguments = air.trace_arguments().len();
+    if execution_trace_ood_evals.len() != num_trace_arguments {
+        return Err(InvalidNumOodEvals {
+            trace: TraceKind::Base,
+            expected: num_trace_arguments,
+            actual: execution_trace_ood_evals.len(),
+        });
+    }
+    if composition_trace_ood_evals.len() != air.ce_blowup_factor() {
+        return Err(InvalidNumOodEvals {
+            trace: TraceKind::Composition,
+            expected: air.ce_blowup_factor(),
+            actual: composition_trace_ood_evals.len(),
+        });
+    }
+
+    let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
+    let trace_cap_size = S::MerkleTree::cap_size(lde_domain_size, options.merkle_cap_height.into());
+    if let Some(cap) = base_trace_commitments
+        .iter()
+        .chain(&extension_trace_commitment)
+        .chain([&composition_trace_commitment])
+        .find(|cap| cap.len() != trace_cap_size)
+    {
+        return Err(InvalidMerkleCapSize {
+            expected: trace_cap_size,
+            actual: cap.len(),
+        });
+    }
+
+    for commitment in &base_trace_commitments {
+        public_coin.reseed_with_merkle_cap(commitment);
+    }
     let num_challenges = air.num_challenges();
     let air_challenges = Challenges::new(draw_multiple(&mut public_coin, num_challenges));
-    let air_hints = air.gen_hints(&air_challenges);
+    let air_hints = air
+        .gen_hints(&air_challenges)
+        .map_err(|source| InvalidHints { source })?;
+    explanation.air_challenges = air_challenges.to_vec();
 
     let extension_trace_commitment = extension_trace_commitment.map(|commitment| {
-        public_coin.reseed_with_digest(&commitment);
+        public_coin.reseed_with_merkle_cap(&commitment);
         commitment
     });
 
     let num_composition_coeffs = air.num_composition_constraint_coeffs();
     let composition_coeffs = draw_multiple(&mut public_coin, num_composition_coeffs);
-    public_coin.reseed_with_digest(&composition_trace_commitment);
+    explanation
+        .composition_coeffs
+        .clone_from(&composition_coeffs);
+    public_coin.reseed_with_merkle_cap(&composition_trace_commitment);
 
     let z = public_coin.draw();
+    explanation.z = Some(z);
     let ood_evals = [
         execution_trace_ood_evals.clone(),
         composition_trace_ood_evals.clone(),
@@ -89,32 +246,92 @@ pub fn default_verify<S: Stark>(
     );
 
     let provided_ood_constraint_evaluation = horner_evaluate(&composition_trace_ood_evals, &z);
+    explanation.calculated_ood_constraint_evaluation = Some(calculated_ood_constraint_evaluation);
+    explanation.provided_ood_constraint_evaluation = Some(provided_ood_constraint_evaluation);
 
     if calculated_ood_constraint_evaluation != provided_ood_constraint_evaluation {
-        return Err(InconsistentOodConstraintEvaluations);
+        // the composition trace only commits to the sum of the terms so report
+        // every term to help find the constraint the prover disagrees with
+        let constraint_terms = ood_constraint_terms::<S::AirConfig>(
+            &composition_coeffs,
+            &air_challenges,
+            &air_hints,
+            &trace_ood_eval_map,
+            &air,
+            z,
+        );
+        explanation
+            .ood_constraint_terms
+            .clone_from(&constraint_terms);
+        return Err(InconsistentOodConstraintEvaluations {
+            calculated: calculated_ood_constraint_evaluation.to_string(),
+            provided: provided_ood_constraint_evaluation.to_string(),
+            constraint_terms: constraint_terms.iter().map(ToString::to_string).collect(),
+        });
     }
 
     let deep_coeffs = this.gen_deep_coeffs(&mut public_coin, &air);
+    explanation
+        .deep_execution_trace_coeffs
+        .clone_from(&deep_coeffs.execution_trace);
+    explanation
+        .deep_composition_trace_coeffs
+        .clone_from(&deep_coeffs.composition_trace);
     let fri_verifier = FriVerifier::<S::Fq, S::Digest, S::MerkleTree>::new(
         &mut public_coin,
         options.into_fri_options(),
         fri_proof,
         trace_len - 1,
     )?;
+    explanation
+        .fri_alphas
+        .clone_from(&fri_verifier.layer_alphas);
 
     if options.grinding_factor != 0 {
         if !public_coin.verify_proof_of_work(options.grinding_factor, pow_nonce) {
-            return Err(FriProofOfWork);
+            return Err(FriProofOfWork {
+                grinding_factor: options.grinding_factor,
+                nonce: pow_nonce,
+            });
         }
         public_coin.reseed_with_int(pow_nonce);
     }
 
-    let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
     let query_positions =
-        Vec::from_iter(public_coin.draw_queries(options.num_queries.into(), lde_domain_size));
+        Vec::from_iter(public_coin.draw_queries(air.num_queries(), lde_domain_size));
+    explanation.query_positions.clone_from(&query_positions);
+    let num_queries = query_positions.len();
+
+    let check_num_values = |trace: TraceKind, num_values: usize, row_width: usize| {
+        let expected = num_queries * row_width;
+        if num_values == expected {
+            Ok(())
+        } else {
+            Err(InvalidNumTraceQueryValues {
+                trace,
+                expected,
+                actual: num_values,
+            })
+        }
+    };
+    let num_extension_values = trace_queries.extension_trace_values.len();
+    let num_composition_values = trace_queries.composition_trace_values.len();
+    for (group, columns) in zip(&trace_queries.base_trace_groups, &base_column_groups) {
+        check_num_values(TraceKind::Base, group.values.len(), columns.len())?;
+    }
+    check_num_values(
+        TraceKind::Extension,
+        num_extension_values,
+        S::AirConfig::NUM_EXTENSION_COLUMNS,
+    )?;
+    check_num_values(
+        TraceKind::Composition,
+        num_composition_values,
+        air.ce_blowup_factor(),
+    )?;
 
-    let base_trace_rows = trace_queries
-        .base_trace_values
+    let base_trace_values = trace_queries.base_trace_values()?;
+    let base_trace_rows = base_trace_values
         .chunks(S::AirConfig::NUM_BASE_COLUMNS)
         .collect::<Vec<_>>();
     let extension_trace_rows = if S::AirConfig::NUM_EXTENSION_COLUMNS == 0 {
@@ -131,33 +348,37 @@ pub fn default_verify<S: Stark>(
         .chunks(air.ce_blowup_factor())
         .collect::<Vec<&[S::Fq]>>();
 
-    // base trace positions
-    S::MerkleTree::verify_rows(
-        &base_trace_commitment,
-        &query_positions,
-        &base_trace_rows,
-        trace_queries.base_trace_proof,
-    )
-    .map_err(|_| BaseTraceQueryDoesNotMatchCommitment)?;
+    // base trace positions (each column group is committed to separately)
+    let base_trace_groups = zip(&base_trace_commitments, &trace_queries.base_trace_groups);
+    for (group, ((commitment, queries), columns)) in
+        zip(base_trace_groups, base_column_groups).enumerate()
+    {
+        queries
+            .verify(commitment, columns.len(), &query_positions)
+            .map_err(|source| BaseTraceQueryDoesNotMatchCommitment { group, source })?;
+    }
 
     if let Some(extension_trace_commitment) = extension_trace_commitment {
-        S::MerkleTree::verify_rows(
+        let proof = trace_queries
+            .extension_trace_proof
+            .ok_or(MissingExtensionTraceProof)?;
+        S::MerkleTree::verify_rows_to_cap(
             &extension_trace_commitment,
             &query_positions,
             &extension_trace_rows,
-            trace_queries.extension_trace_proof.unwrap(),
+            proof,
         )
-        .map_err(|_| ExtensionTraceQueryDoesNotMatchCommitment)?;
+        .map_err(|source| ExtensionTraceQueryDoesNotMatchCommitment { source })?;
     }
 
     // composition trace positions
-    S::MerkleTree::verify_rows(
+    S::MerkleTree::verify_rows_to_cap(
         &composition_trace_commitment,
         &query_positions,
         &composition_trace_rows,
         trace_queries.composition_trace_proof,
     )
-    .map_err(|_| CompositionTraceQueryDoesNotMatchCommitment)?;
+    .map_err(|source| CompositionTraceQueryDoesNotMatchCommitment { source })?;
 
     let deep_evaluations = deep_composition_evaluations(
         &air,
@@ -170,6 +391,7 @@ pub fn default_verify<S: Stark>(
         &composition_trace_ood_evals,
         z,
     );
+    explanation.deep_evaluations.clone_from(&deep_evaluations);
 
     let fri_alphas = fri_verifier.layer_alphas.clone();
     fri_verifier.verify(&query_positions, &deep_evaluations)?;
@@ -182,24 +404,157 @@ pub fn default_verify<S: Stark>(
     })
 }
 
+/// Verifies many proofs of the same STARK with the same security
+/// requirements. The AIR for each trace length is built once (see
+/// [`PreparedAir::cached`]) and shared by every proof with that trace length.
+///
+/// Proofs are verified with [`Stark::verify_with_prepared_air`] so they get
+/// the same checks as [`Stark::verify`], including any checks a STARK adds by
+/// overriding it.
+pub struct PreparedVerifier<S: Stark> {
+    required_security_bits: u32,
+    security_model: SecurityModel,
+    _stark: PhantomData<S>,
+}
+
+impl<S: Stark> PreparedVerifier<S> {
+    pub const fn new(required_security_bits: u32, security_model: SecurityModel) -> Self {
+        Self {
+            required_security_bits,
+            security_model,
+            _stark: PhantomData,
+        }
+    }
+
+    /// Builds the AIR for `trace_len` ahead of time
+    ///
+    /// # Errors
+    /// Returns an error if the AIR can't be built for `trace_len`. See
+    /// [`PreparedAir::cached`].
+    pub fn prepare(&self, trace_len: usize) -> Result<(), air::Error> {
+        PreparedAir::<S::AirConfig>::cached(trace_len)?;
+        Ok(())
+    }
+
+    pub fn verify(
+        &self,
+        claim: &S,
+        proof: Proof<S>,
+    ) -> Result<VerifierChannelArtifacts<S::Fq>, VerificationError> {
+        let prepared_air = PreparedAir::cached(proof.trace_len)
+            .map_err(|source| VerificationError::InvalidTraceLen { source })?;
+        claim.verify_with_prepared_air(
+            &prepared_air,
+            proof,
+            self.required_security_bits,
+            self.security_model,
+            &mut VerifierExplanation::default(),
+        )
+    }
+
+    /// Verifies each claim against its proof. Proofs are verified in parallel
+    /// with the `parallel` feature. Results are in the same order as `batch`.
+    pub fn verify_batch(
+        &self,
+        batch: Vec<(&S, Proof<S>)>,
+    ) -> Vec<Result<VerifierChannelArtifacts<S::Fq>, VerificationError>> {
+        ark_std::cfg_into_iter!(batch)
+            .map(|(claim, proof)| self.verify(claim, proof))
+            .collect()
+    }
+}
+
+/// Identifies one of the traces committed to in a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceKind {
+    Base,
+    Extension,
+    Composition,
+}
+
+impl Display for TraceKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Base => write!(f, "base"),
+            Self::Extension => write!(f, "extension"),
+            Self::Composition => write!(f, "composition"),
+        }
+    }
+}
+
 /// Errors that are returned during verification of a STARK proof
 #[derive(Debug, Snafu)]
 pub enum VerificationError {
-    #[snafu(display("proof params do not satisfy security requirements"))]
-    InvalidProofSecurity,
-    #[snafu(display("constraint evaluations at the out-of-domain point are inconsistent"))]
-    InconsistentOodConstraintEvaluations,
+    #[snafu(display("proof has {actual} bits of security but {required} bits are required"))]
+    InvalidProofSecurity { required: u32, actual: u32 },
+    #[snafu(display(
+        "constraint evaluation at the out-of-domain point is {calculated} but the composition \
+         trace evaluates to {provided} (terms of each constraint: {})",
+        constraint_terms.join(", ")
+    ))]
+    InconsistentOodConstraintEvaluations {
+        calculated: String,
+        provided: String,
+        /// Term of each constraint in the calculated evaluation (in the order
+        /// of [`AirConfig::constraints`])
+        constraint_terms: Vec<String>,
+    },
     #[snafu(context(false))]
     #[snafu(display("fri verification failed: {source}"))]
     FriVerification { source: fri::VerificationError },
-    #[snafu(display("query does not resolve to the base trace commitment"))]
-    BaseTraceQueryDoesNotMatchCommitment,
-    #[snafu(display("query does not resolve to the extension trace commitment"))]
-    ExtensionTraceQueryDoesNotMatchCommitment,
-    #[snafu(display("query does not resolve to the composition trace commitment"))]
-    CompositionTraceQueryDoesNotMatchCommitment,
-    #[snafu(display("insufficient proof of work on fri commitments"))]
-    FriProofOfWork,
+    #[snafu(display(
+        "query does not resolve to the commitment of base column group {group}: {source}"
+    ))]
+    BaseTraceQueryDoesNotMatchCommitment { group: usize, source: merkle::Error },
+    #[snafu(display("query does not resolve to the extension trace commitment: {source}"))]
+    ExtensionTraceQueryDoesNotMatchCommitment { source: merkle::Error },
+    #[snafu(display("query does not resolve to the composition trace commitment: {source}"))]
+    CompositionTraceQueryDoesNotMatchCommitment { source: merkle::Error },
+    #[snafu(display("proof has an extension trace commitment but no extension trace proof"))]
+    MissingExtensionTraceProof,
+    #[snafu(display("proof has an extension trace proof but no extension trace commitment"))]
+    UnexpectedExtensionTraceProof,
+    #[snafu(display(
+        "AIR has {num_columns} extension columns but the proof has no extension trace commitment"
+    ))]
+    MissingExtensionTraceCommitment { num_columns: usize },
+    #[snafu(display(
+        "AIR has no extension columns but the proof has an extension trace commitment"
+    ))]
+    UnexpectedExtensionTraceCommitment,
+    #[snafu(display("{trace} trace has {actual} query values but expected {expected}"))]
+    InvalidNumTraceQueryValues {
+        trace: TraceKind,
+        expected: usize,
+        actual: usize,
+    },
+    #[snafu(display(
+        "{trace} trace has {actual} out-of-domain evaluations but expected {expected}"
+    ))]
+    InvalidNumOodEvals {
+        trace: TraceKind,
+        expected: usize,
+        actual: usize,
+    },
+    #[snafu(display("nonce {nonce} is insufficient proof of work ({grinding_factor} bits)"))]
+    FriProofOfWork { grinding_factor: u8, nonce: u64 },
+    #[snafu(display("trace commitment merkle cap has {actual} nodes but expected {expected}"))]
+    InvalidMerkleCapSize { expected: usize, actual: usize },
+    #[snafu(display(
+        "expected {expected} base trace commitments and proofs (one for each column group) but \
+         got {commitments} commitments and {proofs} proofs"
+    ))]
+    BaseTraceCommitmentsMismatch {
+        expected: usize,
+        commitments: usize,
+        proofs: usize,
+    },
+    #[snafu(display("proof options can't be used with the AIR: {source}"))]
+    InvalidOptions { source: air::Error },
+    #[snafu(display("proof's trace length can't be used with the AIR: {source}"))]
+    InvalidTraceLen { source: air::Error },
+    #[snafu(display("AIR hints can't be generated: {source}"))]
+    InvalidHints { source: air::Error },
 }
 
 pub fn ood_constraint_evaluation<A: AirConfig>(
@@ -210,30 +565,70 @@ pub fn ood_constraint_evaluation<A: AirConfig>(
     air: &Air<A>,
     x: A::Fq,
 ) -> A::Fq {
-    use AlgebraicItem::*;
     use CompositionItem::*;
     air.composition_constraint()
         .graph_eval(&mut |leaf| match leaf {
-            Item(X) => FieldVariant::Fq(x),
-            &Item(Constant(v)) => v,
-            &Item(Challenge(i)) => FieldVariant::Fq(challenges[i]),
-            &Item(Hint(i)) => FieldVariant::Fq(hints[i]),
-            &Item(Periodic(col)) => {
-                let trace_len = air.trace_len();
-                let point = x.pow([(trace_len / col.interval_size()) as u64]);
-                let coeffs = col
-                    .coeffs()
-                    .iter()
-                    .map(FieldVariant::as_fq)
-                    .collect::<Vec<_>>();
-                FieldVariant::Fq(horner_evaluate(&coeffs, &point))
-            }
-            &Item(Trace(i, j)) => FieldVariant::Fq(trace_ood_eval_map[&(i, j)]),
+            Item(item) => ood_item_evaluation(item, challenges, hints, trace_ood_eval_map, air, x),
             &CompositionCoeff(i) => FieldVariant::Fq(composition_coefficients[i]),
         })
         .as_fq()
 }
 
+/// Evaluates the term of each constraint in the composition constraint at `x`
+/// i.e. the constraint scaled by its composition coefficients. The terms sum
+/// to [`ood_constraint_evaluation`].
+pub fn ood_constraint_terms<A: AirConfig>(
+    composition_coefficients: &[A::Fq],
+    challenges: &Challenges<A::Fq>,
+    hints: &Hints<A::Fq>,
+    trace_ood_eval_map: &BTreeMap<(usize, isize), A::Fq>,
+    air: &Air<A>,
+    x: A::Fq,
+) -> Vec<A::Fq> {
+    let trace_degree = air.trace_len() - 1;
+    let composition_degree = air.composition_degree();
+    zip(air.constraints(), composition_coefficients.chunks(2))
+        .map(|(constraint, coeffs)| {
+            let (numerator_degree, denominator_degree) = constraint.degree(trace_degree);
+            let degree_adjustment = composition_degree - (numerator_degree - denominator_degree);
+            let eval = constraint
+                .graph_eval(&mut |item| {
+                    ood_item_evaluation(item, challenges, hints, trace_ood_eval_map, air, x)
+                })
+                .as_fq();
+            eval * (x.pow([degree_adjustment as u64]) * coeffs[0] + coeffs[1])
+        })
+        .collect()
+}
+
+fn ood_item_evaluation<A: AirConfig>(
+    item: &AlgebraicItem<FieldVariant<A::Fp, A::Fq>>,
+    challenges: &Challenges<A::Fq>,
+    hints: &Hints<A::Fq>,
+    trace_ood_eval_map: &BTreeMap<(usize, isize), A::Fq>,
+    air: &Air<A>,
+    x: A::Fq,
+) -> FieldVariant<A::Fp, A::Fq> {
+    use AlgebraicItem::*;
+    match item {
+        X => FieldVariant::Fq(x),
+        &Constant(v) => v,
+        &Challenge(i) => FieldVariant::Fq(challenges[i]),
+        &Hint(i) => FieldVariant::Fq(hints[i]),
+        Periodic(col) => {
+            let trace_len = air.trace_len();
+            let point = x.pow([(trace_len / col.interval_size()) as u64]);
+            let coeffs = col
+                .coeffs()
+                .iter()
+                .map(FieldVariant::as_fq)
+                .collect::<Vec<_>>();
+            FieldVariant::Fq(horner_evaluate(&coeffs, &point))
+        }
+        &Trace(i, j) => FieldVariant::Fq(trace_ood_eval_map[&(i, j)]),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn deep_composition_evaluations<A: AirConfig>(
     air: &Air<A>,
@@ -0,0 +1,85 @@
+use ministark::brainfuck::prove_program;
+use ministark::brainfuck::verify_program;
+use ministark::brainfuck::Error;
+
+const HELLO_WORLD: &str = include_str!("../examples/brainfuck/hello_world.bf");
+const FIBONACCI: &str = include_str!("../examples/brainfuck/fibonacci.bf");
+const ECHO: &str = ",[.,]";
+
+#[test]
+fn hello_world_proof_verifies() {
+    let (output, proof) = pollster::block_on(prove_program(HELLO_WORLD, &[])).unwrap();
+
+    assert_eq!(b"Hello World", &*output);
+    verify_program(HELLO_WORLD, &[], &output, proof).unwrap();
+}
+
+#[test]
+fn fibonacci_proof_verifies() {
+    let (output, proof) = pollster::block_on(prove_program(FIBONACCI, &[])).unwrap();
+
+    assert_eq!(b"1, 1, 2, 3, 5, 8, 13, 21, 34, 55", &*output);
+    verify_program(FIBONACCI, &[], &output, proof).unwrap();
+}
+
+#[test]
+fn echo_proof_verifies() {
+    let input = b"ministark\0";
+
+    let (output, proof) = pollster::block_on(prove_program(ECHO, input)).unwrap();
+
+    assert_eq!(b"ministark", &*output);
+    verify_program(ECHO, input, &output, proof).unwrap();
+}
+
+#[test]
+fn proof_fails_with_other_output() {
+    let (_, proof) = pollster::block_on(prove_program(HELLO_WORLD, &[])).unwrap();
+
+    let result = verify_program(HELLO_WORLD, &[], b"Hello Wordl", proof);
+
+    assert!(matches!(result, Err(Error::Verification { .. })));
+}
+
+#[test]
+fn proof_fails_with_other_input() {
+    let (output, proof) = pollster::block_on(prove_program(ECHO, b"abc\0")).unwrap();
+
+    let result = verify_program(ECHO, b"abd\0", &output, proof);
+
+    assert!(matches!(result, Err(Error::Verification { .. })));
+}
+
+#[test]
+fn prove_fails_when_reading_past_input() {
+    let result = pollster::block_on(prove_program(ECHO, b"abc"));
+
+    assert!(matches!(result, Err(Error::InputExhausted { cycle: 9 })));
+}
+
+#[test]
+fn prove_fails_with_unread_input() {
+    let result = pollster::block_on(prove_program(",.", b"ab"));
+
+    assert!(matches!(
+        result,
+        Err(Error::UnreadInput {
+            read: 1,
+            provided: 2
+        })
+    ));
+}
+
+#[test]
+fn prove_fails_with_unmatched_loop() {
+    let result = pollster::block_on(prove_program("+[>+", &[]));
+
+    assert!(matches!(result, Err(Error::UnmatchedLoop { ip: 1 })));
+}
+
+#[test]
+fn prove_fails_when_memory_pointer_underflows() {
+    let result = pollster::block_on(prove_program("+<", &[]));
+
+    assert!(matches!(result, Err(Error::MemoryOutOfBounds { cycle: 1 })));
+}
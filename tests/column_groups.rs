@@ -0,0 +1,183 @@
+#![feature(allocator_api)]
+//! Commits to a static table separately from the rest of the trace
+
+use ark_ff::One;
+use ministark::air;
+use ministark::air::AirConfig;
+use ministark::challenges::Challenges;
+use ministark::constraints::AlgebraicItem;
+use ministark::constraints::Constraint;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::hash::Sha256HashFn;
+use ministark::hints::Hints;
+use ministark::merkle::MatrixMerkleTree;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::merkle::MerkleTree;
+use ministark::random::PublicCoinImpl;
+use ministark::stark::Stark;
+use ministark::trace::BaseGroupQueries;
+use ministark::utils::FieldVariant;
+use ministark::utils::GpuAllocator;
+use ministark::utils::SerdeOutput;
+use ministark::verifier::VerificationError;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use sha2::Sha256;
+
+const A: usize = 0;
+const B: usize = 1;
+const TABLE: usize = 2;
+const TRACE_LEN: usize = 256;
+const OPTIONS: ProofOptions = ProofOptions::new(32, 4, 0, 2, 8).with_merkle_cap_height(2);
+
+struct GroupsTrace(Matrix<Fp>);
+
+impl Trace for GroupsTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+struct GroupsAirConfig;
+
+impl AirConfig for GroupsAirConfig {
+    const AIR_ID: &'static str = "column-groups";
+    const NUM_BASE_COLUMNS: usize = 3;
+    // the table is committed to separately from the dynamic columns
+    const BASE_COLUMN_GROUPS: &'static [usize] = &[2, 1];
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn gen_hints(
+        _trace_len: usize,
+        claim: &Fp,
+        _: &Challenges<Fp>,
+    ) -> Result<Hints<Fp>, air::Error> {
+        Ok(Hints::new(vec![(0, *claim)]))
+    }
+
+    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        let one = AlgebraicItem::Constant(FieldVariant::Fp(Fp::one()));
+        let boundary_constraints = [A.curr() - &one, B.curr() - &one, TABLE.curr()]
+            .into_iter()
+            .map(|constraint| Constraint::boundary(trace_len, 0, constraint));
+        // the dynamic columns depend on the table
+        let transition_constraints = [
+            A.next() - B.curr(),
+            B.next() - A.curr() - B.curr() - TABLE.curr(),
+            TABLE.next() - TABLE.curr() - one,
+        ]
+        .into_iter()
+        .map(|constraint| Constraint::transition(trace_len, constraint));
+        let terminal_constraint =
+            Constraint::terminal(trace_len, B.curr() - AlgebraicItem::Hint(0));
+        boundary_constraints
+            .chain(transition_constraints)
+            .chain([terminal_constraint])
+            .collect()
+    }
+}
+
+struct GroupsClaim(Fp);
+
+impl Stark for GroupsClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = GroupsAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = ();
+    type Trace = GroupsTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, (): ()) -> GroupsTrace {
+        gen_trace()
+    }
+}
+
+fn table() -> Vec<Fp, GpuAllocator> {
+    (0..TRACE_LEN as u64)
+        .map(Fp::from)
+        .collect::<Vec<_>>()
+        .to_vec_in(GpuAllocator)
+}
+
+fn gen_trace() -> GroupsTrace {
+    let table = table();
+    let mut a = Vec::new_in(GpuAllocator);
+    let mut b = Vec::new_in(GpuAllocator);
+    let (mut a_i, mut b_i) = (Fp::one(), Fp::one());
+    for t in &table {
+        a.push(a_i);
+        b.push(b_i);
+        (a_i, b_i) = (b_i, a_i + b_i + t);
+    }
+    GroupsTrace(Matrix::new(vec![a, b, table]))
+}
+
+fn claim() -> GroupsClaim {
+    let trace = gen_trace();
+    GroupsClaim(*trace.0[B].last().unwrap())
+}
+
+#[test]
+fn proof_with_column_groups_verifies() {
+    let claim = claim();
+
+    let proof = pollster::block_on(claim.prove(OPTIONS, ())).unwrap();
+
+    assert_eq!(2, proof.base_trace_commitments.len());
+    assert_eq!(2, proof.trace_queries.base_trace_groups.len());
+    claim.verify(proof, 10).unwrap();
+}
+
+#[test]
+fn tampered_group_is_detected() {
+    let claim = claim();
+    let mut proof = pollster::block_on(claim.prove(OPTIONS, ())).unwrap();
+
+    proof.trace_queries.base_trace_groups[1].values[0] += Fp::one();
+
+    assert!(matches!(
+        claim.verify(proof, 10),
+        Err(VerificationError::BaseTraceQueryDoesNotMatchCommitment { group: 1, .. })
+    ));
+}
+
+#[test]
+fn table_can_be_opened_independently() {
+    let claim = claim();
+    let proof = pollster::block_on(claim.prove(OPTIONS, ())).unwrap();
+    let air = Air::<GroupsAirConfig>::new(TRACE_LEN, claim.0, OPTIONS);
+    let cap_height = u32::from(OPTIONS.merkle_cap_height);
+    let table_lde = Matrix::new(vec![table()])
+        .interpolate(air.trace_domain())
+        .bit_reversed_evaluate(air.lde_domain());
+    let table_tree = MatrixMerkleTreeImpl::<Sha256HashFn>::from_matrix(&table_lde);
+    let table_commitment = &proof.base_trace_commitments[1];
+    assert_eq!(*table_commitment, table_tree.cap(cap_height));
+
+    // open only the table at positions of our choosing
+    let positions = [0, 7, 100, 1000];
+    let rows = positions.map(|position| table_lde.get_row(position).unwrap());
+    let rows = rows.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let opening = BaseGroupQueries::<GroupsClaim>::new(&rows, &table_tree, &positions, cap_height);
+
+    opening.verify(table_commitment, 1, &positions).unwrap();
+    let mut tampered_opening = opening;
+    tampered_opening.values[2] += Fp::one();
+    assert!(tampered_opening
+        .verify(table_commitment, 1, &positions)
+        .is_err());
+}
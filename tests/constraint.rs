@@ -13,7 +13,9 @@ use ark_std::rand::seq::SliceRandom;
 use ministark::constraints::AlgebraicItem;
 use ministark::constraints::Constraint;
 use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::PeriodicColumn;
 use ministark::constraints::VerifierChallenge;
+use ministark::eval_cpu::build_periodic_column_evals_map;
 use ministark::expression::Expr;
 use ministark::utils;
 use ministark::utils::tests::gen_binary_valued_matrix;
@@ -23,6 +25,7 @@ use ministark::utils::GpuAllocator;
 use ministark::Matrix;
 use ministark::StarkExtensionOf;
 use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use ministark_gpu::fields::p18446744069414584321::ark::Fq3;
 use ministark_gpu::GpuFftField;
 use ministark_gpu::GpuField;
 use num_traits::Pow;
@@ -329,6 +332,127 @@ fn evaluate_zerofier_constraint() {
     assert_valid_over_transition_domain(trace_domain, constraint_eval_poly);
 }
 
+#[test]
+fn constraint_helpers_have_expected_divisors() {
+    let n = 2048;
+    let trace_degree = n - 1;
+    let expr: Expr<AlgebraicItem<FieldVariant<Fp, Fp>>> = 0.curr() - 1.curr();
+
+    let boundary = Constraint::boundary(n, 3, expr.clone());
+    let terminal = Constraint::terminal(n, expr.clone());
+    let transition = Constraint::transition(n, expr.clone());
+    let some_rows = Constraint::on_rows(n, [1, 3, 5, 3], expr.clone());
+    let all_rows = Constraint::on_rows(n, 0..n, expr);
+
+    assert_eq!((trace_degree, 1), boundary.degree(trace_degree));
+    assert_eq!((trace_degree, 1), terminal.degree(trace_degree));
+    assert_eq!((trace_degree + 1, n), transition.degree(trace_degree));
+    assert_eq!((trace_degree, 3), some_rows.degree(trace_degree));
+    assert_eq!((trace_degree, n), all_rows.degree(trace_degree));
+}
+
+#[test]
+fn evaluate_constraint_helpers() {
+    let n = 2048;
+    let blowup = 2;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::<Fp>::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+    let mut matrix = gen_fib_matrix(n);
+    let first = FieldVariant::Fp(matrix.0[0][0]);
+    let last = FieldVariant::Fp(*matrix.0[1].last().unwrap());
+    let middle = FieldVariant::Fp(matrix.0[0][n / 2]);
+    let constraints = |matrix: &Matrix<Fp>| {
+        let lde_matrix = matrix.interpolate(trace_domain).evaluate(lde_domain);
+        Matrix::join(
+            [
+                Constraint::boundary(n, 0, 0.curr() - AlgebraicItem::Constant(first)),
+                Constraint::terminal(n, 1.curr() - AlgebraicItem::Constant(last)),
+                Constraint::on_rows(n, [n / 2], 0.curr() - AlgebraicItem::Constant(middle)),
+                Constraint::transition(n, 0.next() - (0.curr() + 1.curr())),
+                Constraint::transition(n, 1.next() - (0.next() + 1.curr())),
+            ]
+            .iter()
+            .map(|c| evaluate_symbolic(lde_domain, blowup, &[], &[], c, &lde_matrix))
+            .collect(),
+        )
+        .interpolate(lde_domain)
+    };
+
+    // constraints are only polynomials (of low degree) if they hold
+    for column in constraints(&matrix).iter() {
+        assert!(DensePolynomial::from_coefficients_slice(column).degree() < n);
+    }
+    matrix.0[0][n / 2] += Fp::one();
+    let invalid = constraints(&matrix)
+        .iter()
+        .filter(|column| DensePolynomial::from_coefficients_slice(column).degree() >= n)
+        .count();
+    // breaks the `on_rows` constraint and both transition constraints
+    assert_eq!(3, invalid);
+}
+
+#[test]
+fn periodic_column_from_evals_takes_values_over_each_interval() {
+    let n = 64;
+    let mut rng = ark_std::test_rng();
+    let values = (0..8).map(|_| Fq3::rand(&mut rng)).collect::<Vec<_>>();
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+
+    let column =
+        PeriodicColumn::<FieldVariant<Fp, Fq3>>::from_evals(FieldVariant::Fq(values.clone()));
+
+    for (i, x) in trace_domain.elements().enumerate() {
+        assert_eq!(
+            values[i % values.len()],
+            eval_periodic_column(&column, n, x)
+        );
+    }
+    // columns with the same values are the same column
+    let other = PeriodicColumn::from_evals(FieldVariant::Fq(values));
+    assert_eq!(column, other);
+}
+
+#[test]
+fn build_periodic_column_evals_map_with_fq_columns() {
+    use AlgebraicItem::Periodic;
+    let n = 64;
+    let blowup = 4;
+    let mut rng = ark_std::test_rng();
+    let fq_values = (0..8).map(|_| Fq3::rand(&mut rng)).collect::<Vec<_>>();
+    let fq_column = PeriodicColumn::from_evals(FieldVariant::Fq(fq_values));
+    // interval that depends on the trace length
+    let fp_values = (0..n / 2).map(|i| Fp::from(i as u64)).collect::<Vec<_>>();
+    let fp_column = PeriodicColumn::from_evals(FieldVariant::Fp(fp_values));
+    let expr = Periodic(fq_column.clone()) * Periodic(fp_column.clone());
+    let lde_domain = Radix2EvaluationDomain::<Fp>::new_coset(n * blowup, Fp::GENERATOR).unwrap();
+
+    let evals_map = build_periodic_column_evals_map(&expr, Fp::GENERATOR, n, blowup, 1);
+
+    assert_eq!(2, evals_map.len());
+    assert!(matches!(evals_map[&fq_column], FieldVariant::Fq(_)));
+    assert!(matches!(evals_map[&fp_column], FieldVariant::Fp(_)));
+    for (column, evals) in &evals_map {
+        for (i, x) in lde_domain.elements().enumerate() {
+            let actual = match evals {
+                FieldVariant::Fp(evals) => Fq3::from(evals[i % evals.len()]),
+                FieldVariant::Fq(evals) => evals[i % evals.len()],
+            };
+            assert_eq!(eval_periodic_column(column, n, x), actual);
+        }
+    }
+}
+
+/// Evaluates a periodic column at `x`
+fn eval_periodic_column(
+    column: &PeriodicColumn<FieldVariant<Fp, Fq3>>,
+    trace_len: usize,
+    x: Fp,
+) -> Fq3 {
+    let point = Fq3::from(x.pow([(trace_len / column.interval_size()) as u64]));
+    let coeffs = column.coeffs().iter().rev();
+    coeffs.fold(Fq3::zero(), |acc, coeff| acc * point + coeff.as_fq())
+}
+
 fn assert_valid_over_transition_domain<F: GpuField + Field>(
     domain: Radix2EvaluationDomain<F::FftField>,
     poly_matrix: Matrix<F>,
@@ -370,7 +494,7 @@ fn evaluate_symbolic<Fp: GpuFftField + FftField, Fq: StarkExtensionOf<Fp>>(
             &Constant(v) => v,
             &Hint(i) => FieldVariant::Fq(hints[i]),
             &Challenge(i) => FieldVariant::Fq(challenges[i]),
-            &Periodic(_col) => todo!(),
+            &Periodic(_) => todo!(),
             &Trace(col_idx, offset) => {
                 let pos = (i as isize + blowup_factor * offset).rem_euclid(n as isize) as usize;
                 let column = &lde_matrix[col_idx];
@@ -0,0 +1,176 @@
+#![feature(allocator_api)]
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibAirConfig;
+use air::FibClaim;
+use air::FibTrace;
+use ark_ff::One;
+use ark_serialize::CanonicalSerialize;
+use ministark::envelope::decode_header;
+use ministark::envelope::decode_proof;
+use ministark::envelope::encode_proof;
+use ministark::envelope::Error;
+use ministark::envelope::LEGACY_VERSION;
+use ministark::envelope::MAGIC;
+use ministark::envelope::VERSION;
+use ministark::hash::Sha256HashFn;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::random::PublicCoinImpl;
+use ministark::stark::Stark;
+use ministark::utils::SerdeOutput;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use sha2::Sha256;
+
+const OPTIONS: ProofOptions = ProofOptions::new(16, 4, 4, 4, 16);
+
+/// Fibonacci claim that identifies as a different AIR
+struct RenamedFibClaim(Fp);
+
+impl Stark for RenamedFibClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = FibAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = FibTrace;
+    type Trace = FibTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, witness: FibTrace) -> FibTrace {
+        witness
+    }
+
+    fn air_id(&self) -> String {
+        "fibonacci-v2".into()
+    }
+}
+
+/// Fibonacci claim committed to with 4-ary merkle trees
+struct QuaternaryFibClaim(Fp);
+
+impl Stark for QuaternaryFibClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = FibAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn, 4>;
+    type Witness = FibTrace;
+    type Trace = FibTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, witness: FibTrace) -> FibTrace {
+        witness
+    }
+}
+
+fn prove_fib() -> (FibClaim, Proof<FibClaim>) {
+    let trace = gen_trace(1 << 10);
+    let claim = FibClaim(trace.last_value());
+    let proof = pollster::block_on(claim.prove(OPTIONS, trace)).unwrap();
+    (claim, proof)
+}
+
+#[test]
+fn encoded_proof_round_trips() {
+    let (claim, proof) = prove_fib();
+
+    let bytes = encode_proof(&claim, &proof).unwrap();
+    let decoded = decode_proof(&claim, &bytes).unwrap();
+
+    let (version, header) = decode_header(&bytes).unwrap();
+    let header = header.unwrap();
+    assert_eq!(VERSION, version);
+    assert_eq!("fibonacci", header.air_id);
+    assert_eq!("sha256-2;sha256", header.hash_id);
+    let mut expected_bytes = Vec::new();
+    proof.serialize_compressed(&mut expected_bytes).unwrap();
+    let mut decoded_bytes = Vec::new();
+    decoded.serialize_compressed(&mut decoded_bytes).unwrap();
+    assert_eq!(expected_bytes, decoded_bytes);
+    claim.verify(decoded, 8).unwrap();
+}
+
+#[test]
+fn different_air_id_is_rejected() {
+    let (claim, proof) = prove_fib();
+    let bytes = encode_proof(&claim, &proof).unwrap();
+
+    let res = decode_proof(&RenamedFibClaim(claim.0), &bytes);
+
+    assert!(matches!(
+        res,
+        Err(Error::AirMismatch { expected, actual })
+            if expected == "fibonacci-v2" && actual == "fibonacci"
+    ));
+}
+
+#[test]
+fn different_hash_id_is_rejected() {
+    let (claim, proof) = prove_fib();
+    let bytes = encode_proof(&claim, &proof).unwrap();
+
+    let res = decode_proof(&QuaternaryFibClaim(claim.0), &bytes);
+
+    assert!(matches!(
+        res,
+        Err(Error::HashMismatch { expected, actual })
+            if expected == "sha256-4;sha256" && actual == "sha256-2;sha256"
+    ));
+}
+
+#[test]
+fn different_public_inputs_are_rejected() {
+    let (claim, proof) = prove_fib();
+    let bytes = encode_proof(&claim, &proof).unwrap();
+
+    let res = decode_proof(&FibClaim(claim.0 + Fp::one()), &bytes);
+
+    assert!(matches!(res, Err(Error::PublicInputsMismatch)));
+}
+
+#[test]
+fn unsupported_version_is_rejected() {
+    let (claim, proof) = prove_fib();
+    let mut bytes = encode_proof(&claim, &proof).unwrap();
+    let version_bytes = MAGIC.len()..MAGIC.len() + 2;
+    bytes[version_bytes].copy_from_slice(&(VERSION + 1).to_le_bytes());
+
+    let res = decode_proof(&claim, &bytes);
+
+    assert!(matches!(
+        res,
+        Err(Error::UnsupportedVersion { version }) if version == VERSION + 1
+    ));
+}
+
+#[test]
+fn legacy_proof_is_decoded() {
+    let (claim, proof) = prove_fib();
+    // proofs were serialized directly before the envelope was introduced
+    let mut legacy_bytes = Vec::new();
+    proof.serialize_compressed(&mut legacy_bytes).unwrap();
+
+    let decoded = decode_proof(&claim, &legacy_bytes).unwrap();
+
+    assert_eq!(
+        (LEGACY_VERSION, None),
+        decode_header(&legacy_bytes).unwrap()
+    );
+    claim.verify(decoded, 8).unwrap();
+}
@@ -0,0 +1,42 @@
+#![feature(allocator_api)]
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibClaim;
+use ark_serialize::CanonicalSerialize;
+use ministark::stark::Stark;
+use ministark::Proof;
+use ministark::ProofOptions;
+
+const OPTIONS: ProofOptions = ProofOptions::new(16, 8, 4, 4, 16);
+
+fn serialize(proof: &Proof<FibClaim>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn low_memory_proof_matches_default_proof() {
+    let n = 1 << 12;
+    let claim = FibClaim(gen_trace(n).last_value());
+    let expected = pollster::block_on(claim.prove(OPTIONS, gen_trace(n))).unwrap();
+
+    // from one coset of the trace domain at a time to the whole LDE at once
+    for lde_memory_budget in [0, 1 << 16, 1 << 17, usize::MAX] {
+        let proof = pollster::block_on(claim.prove_with_lde_memory_budget(
+            OPTIONS,
+            gen_trace(n),
+            lde_memory_budget,
+        ))
+        .unwrap();
+
+        assert_eq!(serialize(&expected), serialize(&proof));
+        claim.verify(proof, 16).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! Proves knowledge of a leaf and its path to a public merkle root
+
+use ark_ff::One;
+use ministark::air;
+use ministark::air::AirConfig;
+use ministark::challenges::Challenges;
+use ministark::constraints::AlgebraicItem;
+use ministark::constraints::Constraint;
+use ministark::gadgets::merkle::authentication_path;
+use ministark::gadgets::MerkleMembership;
+use ministark::hash::rescue::RescueDigest;
+use ministark::hash::RescueHashFn;
+use ministark::hash::Sha256HashFn;
+use ministark::hints::Hints;
+use ministark::merkle::MatrixMerkleTree;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::merkle::MerkleTree;
+use ministark::random::PublicCoinImpl;
+use ministark::stark::Stark;
+use ministark::utils::FieldVariant;
+use ministark::utils::SerdeOutput;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use sha2::Sha256;
+
+const LEAF_WIDTH: usize = 3;
+const HEIGHT: u32 = 5;
+const MEMBERSHIP: MerkleMembership = MerkleMembership::new(0, LEAF_WIDTH, HEIGHT);
+const OPTIONS: ProofOptions = ProofOptions::new(32, 8, 0, 2, 8);
+
+struct MembershipTrace(Matrix<Fp>);
+
+impl Trace for MembershipTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+struct MembershipAirConfig;
+
+impl AirConfig for MembershipAirConfig {
+    const AIR_ID: &'static str = "merkle-membership";
+    const NUM_BASE_COLUMNS: usize = MerkleMembership::NUM_COLUMNS;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = RescueDigest;
+
+    fn gen_hints(
+        _trace_len: usize,
+        root: &RescueDigest,
+        _: &Challenges<Fp>,
+    ) -> Result<Hints<Fp>, air::Error> {
+        Ok(Hints::new(root.0.into_iter().enumerate().collect()))
+    }
+
+    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        let root = [0, 1, 2, 3].map(|i| AlgebraicItem::Hint(i).into());
+        MEMBERSHIP.constraints(trace_len, root)
+    }
+}
+
+/// Claim of knowing a leaf in the tree with the given root
+struct MembershipClaim(RescueDigest);
+
+impl Stark for MembershipClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = MembershipAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    /// Leaf, leaf index and authentication path
+    type Witness = (Vec<Fp>, usize, Vec<RescueDigest>);
+    type Trace = MembershipTrace;
+
+    fn get_public_inputs(&self) -> RescueDigest {
+        self.0
+    }
+
+    fn generate_trace(&self, (leaf, index, path): Self::Witness) -> MembershipTrace {
+        let rows = MEMBERSHIP.gen_rows(MEMBERSHIP.trace_len(), &leaf, index, &path);
+        MembershipTrace(Matrix::from_arrays(&rows))
+    }
+}
+
+fn gen_tree() -> (Vec<[Fp; LEAF_WIDTH]>, MatrixMerkleTreeImpl<RescueHashFn>) {
+    let leaves = (0..1u64 << HEIGHT)
+        .map(|i| [i, i + 100, i * i].map(Fp::from))
+        .collect::<Vec<_>>();
+    let tree = MatrixMerkleTreeImpl::from_matrix(&Matrix::from_arrays(&leaves));
+    (leaves, tree)
+}
+
+fn prove_membership(index: usize) -> (MembershipClaim, ministark::Proof<MembershipClaim>) {
+    let (leaves, tree) = gen_tree();
+    let path = authentication_path(&tree, index).unwrap();
+    let claim = MembershipClaim(tree.root());
+    let witness = (leaves[index].to_vec(), index, path);
+    let proof = pollster::block_on(claim.prove(OPTIONS, witness)).unwrap();
+    (claim, proof)
+}
+
+#[test]
+fn membership_proof_verifies() {
+    for index in [0, 13, (1 << HEIGHT) - 1] {
+        let (claim, proof) = prove_membership(index);
+
+        claim.verify(proof, 30).unwrap();
+    }
+}
+
+#[test]
+fn membership_proof_fails_with_other_root() {
+    let (claim, proof) = prove_membership(13);
+
+    let mut other_root = claim.0;
+    other_root.0[0] += Fp::one();
+
+    assert!(MembershipClaim(other_root).verify(proof, 30).is_err());
+}
@@ -0,0 +1,55 @@
+#![feature(allocator_api)]
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibClaim;
+use ark_serialize::CanonicalSerialize;
+use ministark::prover::LdeStorage;
+use ministark::stark::Stark;
+use ministark::Proof;
+use ministark::ProofOptions;
+
+const OPTIONS: ProofOptions = ProofOptions::new(16, 8, 4, 4, 16);
+
+fn serialize(proof: &Proof<FibClaim>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn mmap_proof_verifies() {
+    let dir = tempfile::tempdir().unwrap();
+    let trace = gen_trace(1 << 12);
+    let claim = FibClaim(trace.last_value());
+    let lde_storage = LdeStorage::Mmap {
+        dir: dir.path().to_path_buf(),
+    };
+
+    let proof =
+        pollster::block_on(claim.prove_with_lde_storage(OPTIONS, trace, &lde_storage)).unwrap();
+
+    claim.verify(proof, 16).unwrap();
+}
+
+#[test]
+fn mmap_proof_matches_default_proof() {
+    let dir = tempfile::tempdir().unwrap();
+    let n = 1 << 12;
+    let claim = FibClaim(gen_trace(n).last_value());
+    let lde_storage = LdeStorage::Mmap {
+        dir: dir.path().to_path_buf(),
+    };
+
+    let expected = pollster::block_on(claim.prove(OPTIONS, gen_trace(n))).unwrap();
+    let actual =
+        pollster::block_on(claim.prove_with_lde_storage(OPTIONS, gen_trace(n), &lde_storage))
+            .unwrap();
+
+    assert_eq!(serialize(&expected), serialize(&actual));
+}
@@ -0,0 +1,127 @@
+#![feature(allocator_api)]
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibAirConfig;
+use air::FibClaim;
+use ark_ff::One;
+use ministark::air::PreparedAir;
+use ministark::security::SecurityModel;
+use ministark::stark::Stark;
+use ministark::verifier::PreparedVerifier;
+use ministark::verifier::VerificationError;
+use ministark::Air;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use std::sync::Arc;
+
+const OPTIONS: ProofOptions = ProofOptions::new(16, 4, 4, 4, 16);
+
+fn prove_fib(n: usize) -> (FibClaim, Proof<FibClaim>) {
+    let trace = gen_trace(n);
+    let claim = FibClaim(trace.last_value());
+    let proof = pollster::block_on(claim.prove(OPTIONS, trace)).unwrap();
+    (claim, proof)
+}
+
+#[test]
+fn verify_batch_returns_result_for_each_proof() {
+    let (claim_a, proof_a) = prove_fib(1 << 10);
+    let (claim_b, proof_b) = prove_fib(1 << 11);
+    let wrong_claim = FibClaim(claim_a.0 + Fp::one());
+    let verifier = PreparedVerifier::<FibClaim>::new(16, SecurityModel::Conjectured);
+
+    let results = verifier.verify_batch(vec![
+        (&claim_a, proof_a.clone()),
+        (&claim_b, proof_b.clone()),
+        (&wrong_claim, proof_a.clone()),
+        (&claim_a, proof_a),
+    ]);
+
+    assert_eq!(4, results.len());
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    assert!(results[2].is_err());
+    assert!(results[3].is_ok());
+}
+
+#[test]
+fn prepared_verifier_matches_default_verify() {
+    let (claim, proof) = prove_fib(1 << 10);
+    let verifier = PreparedVerifier::<FibClaim>::new(16, SecurityModel::Conjectured);
+    verifier.prepare(proof.trace_len).unwrap();
+
+    let expected = claim.verify(proof.clone(), 16).unwrap();
+    let actual = verifier.verify(&claim, proof).unwrap();
+
+    assert_eq!(*expected.air_challenges, *actual.air_challenges);
+    assert_eq!(expected.fri_alphas, actual.fri_alphas);
+    assert_eq!(expected.query_positions, actual.query_positions);
+}
+
+#[test]
+fn prepared_verifier_checks_security() {
+    let (claim, proof) = prove_fib(1 << 10);
+    let verifier = PreparedVerifier::<FibClaim>::new(1000, SecurityModel::Conjectured);
+
+    let result = verifier.verify(&claim, proof);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidProofSecurity { required: 1000, .. })
+    ));
+}
+
+#[test]
+fn invalid_trace_len_is_rejected() {
+    let (claim, mut proof) = prove_fib(1 << 10);
+    proof.trace_len = 1000;
+    let verifier = PreparedVerifier::<FibClaim>::new(16, SecurityModel::Conjectured);
+
+    let result = verifier.verify(&claim, proof);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidTraceLen {
+            source: ministark::air::Error::InvalidTraceLen {
+                trace_len: 1000,
+                ..
+            }
+        })
+    ));
+}
+
+#[test]
+fn prepared_airs_are_shared() {
+    let a = PreparedAir::<FibAirConfig>::cached(128).unwrap();
+    let b = PreparedAir::<FibAirConfig>::cached(128).unwrap();
+    let c = PreparedAir::<FibAirConfig>::cached(256).unwrap();
+
+    assert!(Arc::ptr_eq(&a, &b));
+    assert!(!Arc::ptr_eq(&a, &c));
+    assert_eq!(128, a.trace_len());
+    assert_eq!(256, c.trace_len());
+}
+
+#[test]
+fn air_can_be_shared_across_threads() {
+    let air = Air::<FibAirConfig>::new(128, Fp::one(), OPTIONS);
+    let expected = air.trace_arguments();
+
+    let handles = (0..4)
+        .map(|_| {
+            let air = air.clone();
+            std::thread::spawn(move || air.trace_arguments())
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        assert_eq!(expected, handle.join().unwrap());
+    }
+}
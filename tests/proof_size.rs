@@ -0,0 +1,103 @@
+#![feature(allocator_api)]
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibAirConfig;
+use air::FibClaim;
+use air::FibTrace;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ministark::hash::Sha256HashFn;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::proof::ProofSizeBreakdown;
+use ministark::random::PublicCoinImpl;
+use ministark::stark::Stark;
+use ministark::utils::SerdeOutput;
+use ministark::Air;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use sha2::Sha256;
+
+const OPTION_SETS: [ProofOptions; 4] = [
+    ProofOptions::new(16, 4, 4, 4, 16),
+    ProofOptions::new(32, 8, 0, 2, 8).with_merkle_cap_height(3),
+    ProofOptions::new(24, 16, 8, 8, 32).with_merkle_cap_height(6),
+    ProofOptions::new(40, 4, 0, 16, 64).with_merkle_cap_height(16),
+];
+
+/// Fibonacci claim committed to with 4-ary merkle trees
+struct QuaternaryFibClaim(Fp);
+
+impl Stark for QuaternaryFibClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = FibAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn, 4>;
+    type Witness = FibTrace;
+    type Trace = FibTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, witness: FibTrace) -> FibTrace {
+        witness
+    }
+}
+
+fn assert_estimate_matches<S: Stark<Fp = Fp, AirConfig = FibAirConfig>>(
+    claim: &S,
+    proof: &Proof<S>,
+) {
+    let compress = Compress::Yes;
+    let air = Air::<FibAirConfig>::new(proof.trace_len, claim.get_public_inputs(), proof.options);
+    let actual = proof.size_breakdown(compress);
+    let estimate = ProofSizeBreakdown::estimate::<S>(&air, compress);
+
+    assert_eq!(proof.serialized_size(compress), actual.total());
+    // components that don't depend on the query positions are exact
+    assert_eq!(actual.metadata, estimate.metadata);
+    assert_eq!(actual.commitments, estimate.commitments);
+    assert_eq!(actual.trace_query_values, estimate.trace_query_values);
+    assert_eq!(actual.fri_remainder, estimate.fri_remainder);
+    assert_eq!(actual.ood_evals, estimate.ood_evals);
+    assert_eq!(actual.overhead, estimate.overhead);
+    assert_eq!(actual.fri_layer_rows.len(), estimate.fri_layer_rows.len());
+    // merkle proofs and FRI layer rows are expected values
+    let error = actual.total().abs_diff(estimate.total());
+    assert!(
+        error * 10 <= actual.total(),
+        "options: {:?}\nactual:\n{actual}\nestimate:\n{estimate}",
+        proof.options
+    );
+}
+
+#[test]
+fn estimate_matches_binary_tree_proofs() {
+    for options in OPTION_SETS {
+        let trace = gen_trace(1 << 14);
+        let claim = FibClaim(trace.last_value());
+        let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+        assert_estimate_matches(&claim, &proof);
+    }
+}
+
+#[test]
+fn estimate_matches_quaternary_tree_proofs() {
+    for options in OPTION_SETS {
+        let trace = gen_trace(1 << 14);
+        let claim = QuaternaryFibClaim(trace.last_value());
+        let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+
+        assert_estimate_matches(&claim, &proof);
+    }
+}
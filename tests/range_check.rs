@@ -0,0 +1,180 @@
+#![feature(allocator_api)]
+//! Range checks a column with an extension field permutation argument. The
+//! column is also constrained to sum to a public value.
+
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_serialize::CanonicalSerialize;
+use ministark::air;
+use ministark::air::AirConfig;
+use ministark::challenges::Challenges;
+use ministark::constraints::AlgebraicItem;
+use ministark::constraints::Constraint;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::gadgets::RangeCheck;
+use ministark::hash::Sha256HashFn;
+use ministark::hints::Hints;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::random::PublicCoinImpl;
+use ministark::stark::Stark;
+use ministark::utils::FieldVariant;
+use ministark::utils::GpuAllocator;
+use ministark::utils::SerdeOutput;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use ministark_gpu::fields::p18446744069414584321::ark::Fq3;
+use sha2::Sha256;
+
+const VALUES: usize = 0;
+const TOTAL: usize = 1;
+const TABLE: usize = 2;
+const SORTED: usize = 3;
+const PERMUTATION: usize = 5;
+const RANGE_CHECK: RangeCheck = RangeCheck::new(VALUES, TABLE, SORTED, PERMUTATION, 0).with_bits(8);
+const TRACE_LEN: usize = 512;
+const OPTIONS: ProofOptions = ProofOptions::new(32, 4, 0, 2, 8);
+
+struct RangeCheckTrace(Matrix<Fp>);
+
+impl Trace for RangeCheckTrace {
+    type Fp = Fp;
+    type Fq = Fq3;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+
+    fn build_extension_columns(&self, challenges: &Challenges<Fq3>) -> Option<Matrix<Fq3>> {
+        let values = &self.0[VALUES];
+        let table = &self.0[TABLE];
+        let [sorted0, sorted1] = RANGE_CHECK.sorted_columns();
+        let sorted = [&*self.0[sorted0], &*self.0[sorted1]];
+        let permutation = RangeCheck::gen_permutation_column(values, table, sorted, challenges[0]);
+        Some(Matrix::new(vec![permutation]))
+    }
+}
+
+struct RangeCheckAirConfig;
+
+impl AirConfig for RangeCheckAirConfig {
+    const AIR_ID: &'static str = "range-check";
+    const NUM_BASE_COLUMNS: usize = 5;
+    const NUM_EXTENSION_COLUMNS: usize = 1;
+    type Fp = Fp;
+    type Fq = Fq3;
+    type PublicInputs = Fp;
+
+    fn gen_hints(
+        _trace_len: usize,
+        sum: &Fp,
+        _: &Challenges<Fq3>,
+    ) -> Result<Hints<Fq3>, air::Error> {
+        Ok(Hints::new(vec![(0, Fq3::from_base_prime_field(*sum))]))
+    }
+
+    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Fp, Fq3>>> {
+        let sum = AlgebraicItem::Hint(0);
+        let total_constraints = [
+            Constraint::boundary(trace_len, 0, TOTAL.curr() - VALUES.curr()),
+            Constraint::transition(trace_len, TOTAL.next() - TOTAL.curr() - VALUES.next()),
+            Constraint::terminal(trace_len, TOTAL.curr() - sum),
+        ];
+        RANGE_CHECK
+            .constraints(trace_len)
+            .into_iter()
+            .chain(total_constraints)
+            .collect()
+    }
+}
+
+/// Claim that all the witness values are in `[0, 256)` and sum to a value
+struct RangeCheckClaim(Fp);
+
+impl Stark for RangeCheckClaim {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type AirConfig = RangeCheckAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fq3, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = Vec<Fp>;
+    type Trace = RangeCheckTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, values: Vec<Fp>) -> RangeCheckTrace {
+        let mut values_column = Vec::with_capacity_in(TRACE_LEN, GpuAllocator);
+        values_column.extend(values);
+        values_column.resize(TRACE_LEN, Fp::zero());
+        let mut total_column = Vec::with_capacity_in(TRACE_LEN, GpuAllocator);
+        total_column.extend(values_column.iter().scan(Fp::zero(), |total, &v| {
+            *total += v;
+            Some(*total)
+        }));
+        let table = RANGE_CHECK.gen_table_column(TRACE_LEN);
+        let [sorted0, sorted1] = RANGE_CHECK.gen_sorted_columns(&values_column, &table);
+        let columns = vec![values_column, total_column, table, sorted0, sorted1];
+        RangeCheckTrace(Matrix::new(columns))
+    }
+}
+
+fn gen_values() -> (RangeCheckClaim, Vec<Fp>) {
+    let values = (0..200u64)
+        .map(|i| Fp::from(i * 37 % 256))
+        .collect::<Vec<_>>();
+    let claim = RangeCheckClaim(values.iter().sum());
+    (claim, values)
+}
+
+#[test]
+fn range_check_proof_verifies() {
+    let (claim, values) = gen_values();
+
+    let proof = pollster::block_on(claim.prove(OPTIONS, values)).unwrap();
+
+    claim.verify(proof, 30).unwrap();
+}
+
+#[test]
+fn proof_fails_for_a_different_sum() {
+    let (claim, values) = gen_values();
+    let proof = pollster::block_on(claim.prove(OPTIONS, values)).unwrap();
+
+    let res = RangeCheckClaim(claim.0 + Fp::one()).verify(proof, 30);
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn low_memory_proof_matches_default_proof() {
+    let (claim, values) = gen_values();
+    let expected = pollster::block_on(claim.prove(OPTIONS, values.clone())).unwrap();
+
+    let proof = pollster::block_on(claim.prove_with_lde_memory_budget(OPTIONS, values, 0)).unwrap();
+
+    let mut expected_bytes = Vec::new();
+    let mut proof_bytes = Vec::new();
+    expected.serialize_compressed(&mut expected_bytes).unwrap();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    assert_eq!(expected_bytes, proof_bytes);
+}
+
+#[test]
+fn range_check_trace_has_valid_columns() {
+    let values = vec![Fp::from(255u8), Fp::from(0u8), Fp::from(17u8)];
+
+    let trace = RangeCheckClaim(Fp::from(272u32)).generate_trace(values.clone());
+
+    let [sorted0, sorted1] = RANGE_CHECK.sorted_columns();
+    assert_eq!(TRACE_LEN, trace.len());
+    assert_eq!(values, trace.0[VALUES][..3]);
+    assert!(trace.0[VALUES][3..].iter().all(|v| v.is_zero()));
+    assert_eq!(Fp::from(272u32), trace.0[TOTAL][TRACE_LEN - 1]);
+    assert_eq!(Fp::from(0u8), trace.0[sorted0][0]);
+    assert_eq!(Fp::from(255u8), trace.0[sorted1][TRACE_LEN - 1]);
+}
@@ -0,0 +1,184 @@
+#![feature(allocator_api)]
+//! Proves the validity of a fibonacci proof with a recursive proof
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibAirConfig;
+use air::FibTrace;
+use ministark::air::AirConfig;
+use ministark::fri;
+use ministark::hash::rescue::RescueDigest;
+use ministark::hash::RescueHashFn;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::prover::ProvingError;
+use ministark::random::PublicCoinImpl;
+use ministark::recursion::InnerStark;
+use ministark::recursion::RecursiveStark;
+use ministark::security::SecurityModel;
+use ministark::stark::Stark;
+use ministark::verifier::PreparedVerifier;
+use ministark::verifier::VerificationError;
+use ministark::ProofOptions;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+/// Fibonacci claim that commits with Rescue-Prime so it can be verified
+/// recursively
+struct RescueFibClaim(Fp);
+
+impl Stark for RescueFibClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = FibAirConfig;
+    type Digest = RescueDigest;
+    type PublicCoin = PublicCoinImpl<Fp, RescueHashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<RescueHashFn>;
+    type Witness = FibTrace;
+    type Trace = FibTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, witness: FibTrace) -> FibTrace {
+        witness
+    }
+}
+
+impl InnerStark for RescueFibClaim {
+    const TRACE_LEN: usize = 64;
+    const OPTIONS: ProofOptions = ProofOptions::new(8, 4, 4, 2, 8);
+
+    fn from_public_inputs(public_inputs: &<FibAirConfig as AirConfig>::PublicInputs) -> Self {
+        Self(*public_inputs)
+    }
+}
+
+/// Fibonacci claim proven with options that give the inner proof about 55
+/// bits of conjectured security. More queries or grinding don't help: an
+/// [`InnerStark`] must use the 64-bit base field for its challenges so the
+/// field size caps its security.
+struct SecureRescueFibClaim(Fp);
+
+impl Stark for SecureRescueFibClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = FibAirConfig;
+    type Digest = RescueDigest;
+    type PublicCoin = PublicCoinImpl<Fp, RescueHashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<RescueHashFn>;
+    type Witness = FibTrace;
+    type Trace = FibTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, witness: FibTrace) -> FibTrace {
+        witness
+    }
+}
+
+impl InnerStark for SecureRescueFibClaim {
+    const TRACE_LEN: usize = 64;
+    const OPTIONS: ProofOptions = ProofOptions::new(24, 8, 16, 2, 8);
+
+    fn from_public_inputs(public_inputs: &<FibAirConfig as AirConfig>::PublicInputs) -> Self {
+        Self(*public_inputs)
+    }
+}
+
+const OUTER_OPTIONS: ProofOptions = ProofOptions::new(24, 8, 0, 8, 16);
+
+fn prove_inner() -> (RescueFibClaim, ministark::Proof<RescueFibClaim>) {
+    let trace = gen_trace(RescueFibClaim::TRACE_LEN * 8);
+    let claim = RescueFibClaim(trace.last_value());
+    assert_eq!(RescueFibClaim::TRACE_LEN, trace.len());
+    let proof = pollster::block_on(claim.prove(RescueFibClaim::OPTIONS, trace)).unwrap();
+    claim.verify(proof.clone(), 8).unwrap();
+    (claim, proof)
+}
+
+#[test]
+fn recursive_proof_verifies() {
+    let (claim, inner_proof) = prove_inner();
+    let recursive_claim = RecursiveStark::new(&claim, &inner_proof);
+
+    let outer_proof = pollster::block_on(recursive_claim.prove(OUTER_OPTIONS, inner_proof));
+
+    let outer_proof = outer_proof.unwrap();
+    assert_eq!(
+        RecursiveStark::<RescueFibClaim>::trace_len(),
+        outer_proof.trace_len
+    );
+    recursive_claim.verify(outer_proof, 8).unwrap();
+}
+
+#[test]
+fn recursive_proof_fails_with_tampered_header() {
+    let (claim, inner_proof) = prove_inner();
+    let recursive_claim = RecursiveStark::new(&claim, &inner_proof);
+    let outer_proof = pollster::block_on(recursive_claim.prove(OUTER_OPTIONS, inner_proof));
+    let outer_proof = outer_proof.unwrap();
+
+    let mut tampered_claim = RecursiveStark(recursive_claim.0.clone());
+    tampered_claim.0.composition_trace_ood_evals[0] += Fp::from(1u8);
+
+    assert!(tampered_claim.verify(outer_proof, 8).is_err());
+}
+
+#[test]
+fn proving_fails_with_tampered_header() {
+    let (claim, inner_proof) = prove_inner();
+    let mut tampered_claim = RecursiveStark::new(&claim, &inner_proof);
+    tampered_claim.0.fri_layer_roots.pop();
+
+    let res = pollster::block_on(tampered_claim.prove(OUTER_OPTIONS, inner_proof));
+
+    assert!(matches!(res, Err(ProvingError::InvalidPublicInputs(_))));
+}
+
+#[test]
+fn recursive_proof_verifies_with_realistic_security() {
+    const SECURITY_BITS: u32 = 45;
+    let trace = gen_trace(SecureRescueFibClaim::TRACE_LEN * 8);
+    let claim = SecureRescueFibClaim(trace.last_value());
+    let options = SecureRescueFibClaim::OPTIONS;
+    let inner_proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    claim.verify(inner_proof.clone(), SECURITY_BITS).unwrap();
+    let recursive_claim = RecursiveStark::new(&claim, &inner_proof);
+
+    let outer_proof = pollster::block_on(recursive_claim.prove(OUTER_OPTIONS, inner_proof));
+
+    recursive_claim
+        .verify(outer_proof.unwrap(), SECURITY_BITS)
+        .unwrap();
+}
+
+#[test]
+fn prepared_verifier_checks_header() {
+    let (claim, inner_proof) = prove_inner();
+    let recursive_claim = RecursiveStark::new(&claim, &inner_proof);
+    let outer_proof = pollster::block_on(recursive_claim.prove(OUTER_OPTIONS, inner_proof));
+    let outer_proof = outer_proof.unwrap();
+    let verifier = PreparedVerifier::new(8, SecurityModel::Conjectured);
+    verifier
+        .verify(&recursive_claim, outer_proof.clone())
+        .unwrap();
+
+    let mut tampered_claim = RecursiveStark(recursive_claim.0.clone());
+    tampered_claim.0.fri_layer_roots.pop();
+    let res = verifier.verify(&tampered_claim, outer_proof);
+
+    assert!(matches!(
+        res,
+        Err(VerificationError::FriVerification {
+            source: fri::VerificationError::NumLayersMismatch { .. }
+        })
+    ));
+}
@@ -0,0 +1,96 @@
+extern crate alloc;
+
+#[path = "../examples/rescue/air.rs"]
+mod air;
+
+use air::PreimageClaim;
+use air::RescueAirConfig;
+use air::RescueTrace;
+use air::PREIMAGE_LEN;
+use ark_ff::One;
+use ark_serialize::CanonicalSerialize;
+use ministark::gadgets::rescue::PERMUTATION_ROWS;
+use ministark::hash::rescue::RescueDigest;
+use ministark::hash::ElementHashFn;
+use ministark::hash::RescueHashFn;
+use ministark::hash::Sha256HashFn;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::random::PublicCoin;
+use ministark::random::PublicCoinImpl;
+use ministark::stark::assert_public_inputs_bound;
+use ministark::stark::Stark;
+use ministark::utils::SerdeOutput;
+use ministark::Air;
+use ministark::ProofOptions;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use sha2::Sha256;
+
+const OPTIONS: ProofOptions = ProofOptions::new(32, 16, 0, 2, 8);
+
+#[test]
+fn preimage_proof_verifies() {
+    let preimage = [5u8, 6, 7, 8].map(Fp::from);
+    let claim = PreimageClaim(RescueHashFn::hash_elements(preimage));
+
+    let proof = pollster::block_on(claim.prove(OPTIONS, preimage)).unwrap();
+
+    claim.verify(proof, 30).unwrap();
+}
+
+#[test]
+fn preimage_proof_fails_with_other_digest() {
+    let preimage = [5u8, 6, 7, 8].map(Fp::from);
+    let claim = PreimageClaim(RescueHashFn::hash_elements(preimage));
+    let proof = pollster::block_on(claim.prove(OPTIONS, preimage)).unwrap();
+
+    let mut other_digest = claim.0;
+    other_digest.0[0] += Fp::one();
+
+    assert!(PreimageClaim(other_digest).verify(proof, 30).is_err());
+}
+
+#[test]
+fn default_public_coin_binds_digest() {
+    let claims = [[1u8, 2, 3, 4], [1, 2, 3, 5], [5, 6, 7, 8]]
+        .map(|preimage| PreimageClaim(RescueHashFn::hash_elements(preimage.map(Fp::from))));
+
+    assert_public_inputs_bound(&claims, PERMUTATION_ROWS, OPTIONS);
+}
+
+#[test]
+#[should_panic(expected = "same public coin")]
+fn public_coin_without_digest_is_detected() {
+    let claims = [[1u8, 2, 3, 4], [5, 6, 7, 8]]
+        .map(|preimage| UnboundClaim(RescueHashFn::hash_elements(preimage.map(Fp::from))));
+
+    assert_public_inputs_bound(&claims, PERMUTATION_ROWS, OPTIONS);
+}
+
+/// Preimage claim with a public coin that forgets to bind the digest
+struct UnboundClaim(RescueDigest);
+
+impl Stark for UnboundClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = RescueAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = [Fp; PREIMAGE_LEN];
+    type Trace = RescueTrace;
+
+    fn get_public_inputs(&self) -> RescueDigest {
+        self.0
+    }
+
+    fn generate_trace(&self, preimage: [Fp; PREIMAGE_LEN]) -> RescueTrace {
+        PreimageClaim(self.0).generate_trace(preimage)
+    }
+
+    fn gen_public_coin(&self, air: &Air<RescueAirConfig>) -> Self::PublicCoin {
+        let mut seed = Vec::new();
+        air.trace_len().serialize_compressed(&mut seed).unwrap();
+        air.options().serialize_compressed(&mut seed).unwrap();
+        PublicCoinImpl::from_seed(&seed)
+    }
+}
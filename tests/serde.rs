@@ -0,0 +1,79 @@
+#![feature(allocator_api)]
+//! JSON round trips of proofs and their components
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibClaim;
+use ark_serialize::CanonicalSerialize;
+use ministark::fri::FriProof;
+use ministark::hash::Sha256HashFn;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::stark::Stark;
+use ministark::trace::Queries;
+use ministark::utils::SerdeOutput;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use sha2::Sha256;
+
+type FibFriProof = FriProof<Fp, SerdeOutput<Sha256>, MatrixMerkleTreeImpl<Sha256HashFn>>;
+
+const OPTIONS: ProofOptions = ProofOptions::new(16, 4, 4, 4, 16).with_merkle_cap_height(2);
+
+fn prove_fib() -> (FibClaim, Proof<FibClaim>) {
+    let trace = gen_trace(1 << 10);
+    let claim = FibClaim(trace.last_value());
+    let proof = pollster::block_on(claim.prove(OPTIONS, trace)).unwrap();
+    (claim, proof)
+}
+
+fn to_bytes(value: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn proof_json_round_trip() {
+    let (claim, proof) = prove_fib();
+
+    let json = serde_json::to_string(&proof).unwrap();
+    let decoded: Proof<FibClaim> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(to_bytes(&proof), to_bytes(&decoded));
+    claim.verify(decoded, 8).unwrap();
+}
+
+#[test]
+fn fri_proof_json_round_trip() {
+    let (claim, proof) = prove_fib();
+
+    let json = serde_json::to_string(&proof.fri_proof).unwrap();
+    let decoded: FibFriProof = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(to_bytes(&proof.fri_proof), to_bytes(&decoded));
+    let mut decoded_proof = proof;
+    decoded_proof.fri_proof = decoded;
+    claim.verify(decoded_proof, 8).unwrap();
+}
+
+#[test]
+fn queries_json_round_trip() {
+    let (claim, proof) = prove_fib();
+
+    let json = serde_json::to_value(&proof.trace_queries).unwrap();
+    let decoded: Queries<FibClaim> = serde_json::from_value(json.clone()).unwrap();
+
+    let groups = json["base_trace_groups"].as_array().unwrap();
+    assert_eq!(proof.trace_queries.base_trace_groups.len(), groups.len());
+    assert!(groups[0]["values"][0].is_string());
+    assert_eq!(to_bytes(&proof.trace_queries), to_bytes(&decoded));
+    let mut decoded_proof = proof;
+    decoded_proof.trace_queries = decoded;
+    claim.verify(decoded_proof, 8).unwrap();
+}
@@ -0,0 +1,176 @@
+#![feature(allocator_api)]
+//! Verifies proofs with a generated Solidity verifier running in a local EVM.
+//! Requires `solc` (0.8.19 or later) to be on the `PATH`. Tests are skipped if
+//! `solc` can't be found unless `MINISTARK_REQUIRE_SOLC` is set (as it is in
+//! CI).
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibClaim;
+use ark_ff::One;
+use ark_serialize::CanonicalSerialize;
+use ministark::solidity::SolidityVerifier;
+use ministark::stark::Stark;
+use ministark::Air;
+use ministark::ProofOptions;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use revm::primitives::Address;
+use revm::primitives::ExecutionResult;
+use revm::primitives::Output;
+use revm::primitives::TransactTo;
+use revm::InMemoryDB;
+use revm::EVM;
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+const OPTIONS: ProofOptions = ProofOptions::new(16, 4, 4, 4, 16);
+
+/// Concrete verifier that binds the public inputs the same way as
+/// `FibClaim::gen_public_coin` and `FibAirConfig::gen_hints`. `{{SEED_SUFFIX}}`
+/// is the serialized trace length and proof options.
+const FIB_VERIFIER: &str = r#"
+contract FibVerifier is FibStarkVerifier {
+    function publicCoinSeed(bytes calldata publicInputs) internal pure override returns (bytes32) {
+        return sha256(abi.encodePacked(publicInputs, hex"{{SEED_SUFFIX}}"));
+    }
+
+    function hints(bytes calldata publicInputs, uint256[] memory)
+        internal
+        pure
+        override
+        returns (uint256[] memory h)
+    {
+        require(publicInputs.length == 8, "invalid public inputs");
+        uint256 claimedFibNum = 0;
+        for (uint256 i = 0; i < 8; i++) {
+            claimedFibNum |= uint256(uint8(publicInputs[i])) << (8 * i);
+        }
+        h = new uint256[](1);
+        h[0] = claimedFibNum;
+    }
+}
+"#;
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn hex_decode(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Returns true if `solc` is on the `PATH`
+///
+/// # Panics
+/// Panics if `solc` can't be found and `MINISTARK_REQUIRE_SOLC` is set
+fn solc_available() -> bool {
+    let available = Command::new("solc")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    let required = std::env::var_os("MINISTARK_REQUIRE_SOLC").is_some();
+    assert!(
+        available || !required,
+        "solc is required but isn't on the PATH"
+    );
+    available
+}
+
+/// Compiles the contract called `name` with solc and returns its bytecode
+fn compile(source: &str, name: &str) -> Vec<u8> {
+    let mut solc = Command::new("solc")
+        .args(["--combined-json", "bin", "--optimize", "--via-ir", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run solc");
+    solc.stdin
+        .take()
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    let output = solc.wait_with_output().unwrap();
+    assert!(output.status.success(), "failed to compile the verifier");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let bin = json["contracts"][format!("<stdin>:{name}")]["bin"]
+        .as_str()
+        .unwrap();
+    hex_decode(bin)
+}
+
+fn deploy(evm: &mut EVM<InMemoryDB>, bytecode: Vec<u8>) -> Address {
+    evm.env.tx.transact_to = TransactTo::create();
+    evm.env.tx.data = bytecode.into();
+    match evm.transact_commit().unwrap() {
+        ExecutionResult::Success {
+            output: Output::Create(_, Some(address)),
+            ..
+        } => address,
+        res => panic!("failed to deploy the verifier: {res:?}"),
+    }
+}
+
+/// Calls the verifier and returns if the call succeeded and returned `true`
+fn call(evm: &mut EVM<InMemoryDB>, address: Address, calldata: Vec<u8>) -> bool {
+    evm.env.tx.transact_to = TransactTo::Call(address);
+    evm.env.tx.data = calldata.into();
+    match evm.transact_ref().unwrap().result {
+        ExecutionResult::Success {
+            output: Output::Call(output),
+            ..
+        } => output.last() == Some(&1),
+        _ => false,
+    }
+}
+
+#[test]
+fn fib_proof_verifies_in_evm() {
+    if !solc_available() {
+        eprintln!("skipping: solc isn't on the PATH");
+        return;
+    }
+    let trace = gen_trace(1024);
+    let claim = FibClaim(trace.last_value());
+    let air = Air::new(trace.len(), claim.get_public_inputs(), OPTIONS);
+    let verifier = SolidityVerifier::<FibClaim>::new(air).unwrap();
+    let mut seed_suffix = Vec::new();
+    trace.len().serialize_compressed(&mut seed_suffix).unwrap();
+    OPTIONS.serialize_compressed(&mut seed_suffix).unwrap();
+    let source = format!(
+        "{}{}",
+        verifier.contract("FibStarkVerifier"),
+        FIB_VERIFIER.replace("{{SEED_SUFFIX}}", &hex_encode(&seed_suffix))
+    );
+    let proof = pollster::block_on(claim.prove(OPTIONS, trace)).unwrap();
+    let mut public_inputs = Vec::new();
+    claim.0.serialize_compressed(&mut public_inputs).unwrap();
+    let mut wrong_public_inputs = Vec::new();
+    (claim.0 + Fp::one())
+        .serialize_compressed(&mut wrong_public_inputs)
+        .unwrap();
+    let mut tampered_proof = proof.clone();
+    tampered_proof.trace_queries.base_trace_groups[0].values[0] += claim.0;
+
+    let mut evm = EVM::new();
+    evm.database(InMemoryDB::default());
+    evm.env.cfg.limit_contract_code_size = Some(usize::MAX);
+    evm.env.tx.gas_limit = 1 << 32;
+    let address = deploy(&mut evm, compile(&source, "FibVerifier"));
+
+    let calldata = verifier.calldata(&proof, &public_inputs).unwrap();
+    assert!(call(&mut evm, address, calldata));
+    let calldata = verifier.calldata(&proof, &wrong_public_inputs).unwrap();
+    assert!(!call(&mut evm, address, calldata));
+    let calldata = verifier.calldata(&tampered_proof, &public_inputs).unwrap();
+    assert!(!call(&mut evm, address, calldata));
+}
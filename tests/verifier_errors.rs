@@ -0,0 +1,559 @@
+#![feature(allocator_api)]
+//! Tampered proofs are rejected with the error describing the tampering
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibAirConfig;
+use air::FibClaim;
+use air::FibTrace;
+use ark_ff::One;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ministark::air::AirConfig;
+use ministark::challenges::Challenges;
+use ministark::composer::DeepCompositionCoeffs;
+use ministark::constraints::AlgebraicItem;
+use ministark::constraints::Constraint;
+use ministark::constraints::ExecutionTraceColumn;
+use ministark::constraints::VerifierChallenge;
+use ministark::fri;
+use ministark::fri::FriVerifier;
+use ministark::hash::Sha256HashFn;
+use ministark::merkle;
+use ministark::merkle::MatrixMerkleTreeImpl;
+use ministark::random::PublicCoinImpl;
+use ministark::security::SecurityModel;
+use ministark::stark::Stark;
+use ministark::utils::FieldVariant;
+use ministark::utils::GpuAllocator;
+use ministark::utils::SerdeOutput;
+use ministark::verifier::TraceKind;
+use ministark::verifier::VerificationError;
+use ministark::verifier::VerifierExplanation;
+use ministark::Air;
+use ministark::Matrix;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Trace;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+use sha2::Sha256;
+
+type FibFriVerifier = FriVerifier<Fp, SerdeOutput<Sha256>, MatrixMerkleTreeImpl<Sha256HashFn>>;
+
+const OPTIONS: ProofOptions = ProofOptions::new(16, 4, 4, 4, 16).with_merkle_cap_height(2);
+
+/// Running product of `challenge - value` over a base column
+struct ProductTrace(Matrix<Fp>);
+
+impl Trace for ProductTrace {
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+
+    fn build_extension_columns(&self, challenges: &Challenges<Fp>) -> Option<Matrix<Fp>> {
+        let mut product = Vec::with_capacity_in(self.len(), GpuAllocator);
+        let mut acc = Fp::one();
+        for value in self.0[0].iter() {
+            product.push(acc);
+            acc *= challenges[0] - value;
+        }
+        Some(Matrix::new(vec![product]))
+    }
+}
+
+struct ProductAirConfig;
+
+impl AirConfig for ProductAirConfig {
+    const AIR_ID: &'static str = "running-product";
+    const NUM_BASE_COLUMNS: usize = 1;
+    const NUM_EXTENSION_COLUMNS: usize = 1;
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn constraints(trace_len: usize) -> Vec<Constraint<FieldVariant<Fp, Fp>>> {
+        let one = AlgebraicItem::Constant(FieldVariant::Fp(Fp::one()));
+        vec![
+            Constraint::boundary(trace_len, 0, 1.curr() - one),
+            Constraint::transition(trace_len, 1.next() - 1.curr() * (0.challenge() - 0.curr())),
+        ]
+    }
+}
+
+struct ProductClaim;
+
+impl Stark for ProductClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = ProductAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = ProductTrace;
+    type Trace = ProductTrace;
+
+    fn get_public_inputs(&self) {}
+
+    fn generate_trace(&self, witness: ProductTrace) -> ProductTrace {
+        witness
+    }
+}
+
+/// Fibonacci claim whose verifier draws different DEEP composition
+/// coefficients to the prover. The proof's commitments are all valid but the
+/// DEEP composition evaluations don't match the FRI proof.
+struct TamperedDeepFibClaim(Fp);
+
+impl Stark for TamperedDeepFibClaim {
+    type Fp = Fp;
+    type Fq = Fp;
+    type AirConfig = FibAirConfig;
+    type Digest = SerdeOutput<Sha256>;
+    type PublicCoin = PublicCoinImpl<Fp, Sha256HashFn>;
+    type MerkleTree = MatrixMerkleTreeImpl<Sha256HashFn>;
+    type Witness = FibTrace;
+    type Trace = FibTrace;
+
+    fn get_public_inputs(&self) -> Fp {
+        self.0
+    }
+
+    fn generate_trace(&self, witness: FibTrace) -> FibTrace {
+        witness
+    }
+
+    fn gen_public_coin(&self, air: &Air<FibAirConfig>) -> Self::PublicCoin {
+        FibClaim(self.0).gen_public_coin(air)
+    }
+
+    fn gen_deep_coeffs(
+        &self,
+        public_coin: &mut Self::PublicCoin,
+        air: &Air<FibAirConfig>,
+    ) -> DeepCompositionCoeffs<Fp> {
+        let mut coeffs = FibClaim(self.0).gen_deep_coeffs(public_coin, air);
+        coeffs.degree.0 += Fp::one();
+        coeffs
+    }
+}
+
+fn prove_fib(options: ProofOptions) -> (FibClaim, Proof<FibClaim>) {
+    let trace = gen_trace(1 << 10);
+    let claim = FibClaim(trace.last_value());
+    let proof = pollster::block_on(claim.prove(options, trace)).unwrap();
+    (claim, proof)
+}
+
+fn prove_product() -> Proof<ProductClaim> {
+    let values = (0..128u64).map(Fp::from).collect::<Vec<Fp>>();
+    let trace = ProductTrace(Matrix::new(vec![values.to_vec_in(GpuAllocator)]));
+    pollster::block_on(ProductClaim.prove(OPTIONS, trace)).unwrap()
+}
+
+/// Verifies a proof that is expected to be rejected
+fn verify_err<S: Stark>(
+    claim: &S,
+    proof: Proof<S>,
+) -> (VerificationError, VerifierExplanation<S::Fq>) {
+    let mut explanation = VerifierExplanation::default();
+    let result = claim.verify_and_explain(proof, 8, SecurityModel::Conjectured, &mut explanation);
+    let Err(error) = result else {
+        panic!("tampered proof was accepted")
+    };
+    (error, explanation)
+}
+
+#[test]
+fn untampered_proofs_verify() {
+    let (claim, proof) = prove_fib(OPTIONS);
+    claim.verify(proof, 8).unwrap();
+
+    ProductClaim.verify(prove_product(), 8).unwrap();
+}
+
+#[test]
+fn insufficient_security_is_rejected() {
+    let (claim, proof) = prove_fib(OPTIONS);
+
+    let res = claim.verify(proof, 200);
+
+    assert!(matches!(
+        res,
+        Err(VerificationError::InvalidProofSecurity { required: 200, .. })
+    ));
+}
+
+#[test]
+fn inconsistent_ood_evaluations_report_each_constraint_term() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.composition_trace_ood_evals[0] += Fp::one();
+
+    let (error, explanation) = verify_err(&claim, proof);
+
+    let VerificationError::InconsistentOodConstraintEvaluations {
+        calculated,
+        constraint_terms,
+        ..
+    } = error
+    else {
+        panic!("expected inconsistent out-of-domain evaluations")
+    };
+    let num_constraints = FibAirConfig::constraints(128).len();
+    assert_eq!(num_constraints, constraint_terms.len());
+    let terms = explanation.ood_constraint_terms;
+    assert_eq!(num_constraints, terms.len());
+    let sum = terms.iter().sum::<Fp>();
+    assert_eq!(explanation.calculated_ood_constraint_evaluation, Some(sum));
+    assert_eq!(sum.to_string(), calculated);
+}
+
+#[test]
+fn wrong_number_of_ood_evals_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.execution_trace_ood_evals.pop();
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::InvalidNumOodEvals {
+            trace: TraceKind::Base,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn tampered_base_trace_value_reports_query_position() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.trace_queries.base_trace_groups[0].values[0] += Fp::one();
+
+    let (error, explanation) = verify_err(&claim, proof);
+
+    let position = explanation.query_positions[0];
+    assert!(matches!(
+        error,
+        VerificationError::BaseTraceQueryDoesNotMatchCommitment {
+            group: 0,
+            source: merkle::Error::LeafMismatch { i }
+        } if i == position
+    ));
+}
+
+#[test]
+fn tampered_composition_trace_value_reports_query_position() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.trace_queries.composition_trace_values[0] += Fp::one();
+
+    let (error, explanation) = verify_err(&claim, proof);
+
+    let position = explanation.query_positions[0];
+    assert!(matches!(
+        error,
+        VerificationError::CompositionTraceQueryDoesNotMatchCommitment {
+            source: merkle::Error::LeafMismatch { i }
+        } if i == position
+    ));
+}
+
+#[test]
+fn tampered_merkle_path_reports_query_position() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    let nodes = &mut proof.trace_queries.composition_trace_proof.nodes;
+    nodes[0] = nodes[1].clone();
+
+    let (error, explanation) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::CompositionTraceQueryDoesNotMatchCommitment {
+            source: merkle::Error::CapMismatch { i }
+        } if explanation.query_positions.contains(&i)
+    ));
+}
+
+#[test]
+fn tampered_extension_trace_value_is_rejected() {
+    let mut proof = prove_product();
+    proof.trace_queries.extension_trace_values[0] += Fp::one();
+
+    let (error, explanation) = verify_err(&ProductClaim, proof);
+
+    let position = explanation.query_positions[0];
+    assert!(matches!(
+        error,
+        VerificationError::ExtensionTraceQueryDoesNotMatchCommitment {
+            source: merkle::Error::LeafMismatch { i }
+        } if i == position
+    ));
+}
+
+#[test]
+fn missing_extension_trace_proof_is_rejected() {
+    let mut proof = prove_product();
+    proof.trace_queries.extension_trace_proof = None;
+
+    let (error, _) = verify_err(&ProductClaim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::MissingExtensionTraceProof
+    ));
+}
+
+#[test]
+fn missing_extension_trace_commitment_is_rejected() {
+    let mut proof = prove_product();
+    proof.extension_trace_commitment = None;
+
+    let (error, _) = verify_err(&ProductClaim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::MissingExtensionTraceCommitment { num_columns: 1 }
+    ));
+}
+
+#[test]
+fn unexpected_extension_trace_commitment_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.extension_trace_commitment = Some(proof.composition_trace_commitment.clone());
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::UnexpectedExtensionTraceCommitment
+    ));
+}
+
+#[test]
+fn unexpected_extension_trace_proof_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    let composition_trace_proof = proof.trace_queries.composition_trace_proof.clone();
+    proof.trace_queries.extension_trace_proof = Some(composition_trace_proof);
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::UnexpectedExtensionTraceProof
+    ));
+}
+
+#[test]
+fn wrong_number_of_query_values_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.trace_queries.composition_trace_values.pop();
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::InvalidNumTraceQueryValues {
+            trace: TraceKind::Composition,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn invalid_proof_of_work_is_rejected() {
+    let options = ProofOptions::new(16, 4, 16, 4, 16);
+    let (claim, mut proof) = prove_fib(options);
+    proof.pow_nonce = 0;
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::FriProofOfWork {
+            grinding_factor: 16,
+            nonce: 0
+        }
+    ));
+}
+
+#[test]
+fn wrong_merkle_cap_size_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.base_trace_commitments[0].0.pop();
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::InvalidMerkleCapSize {
+            expected: 4,
+            actual: 3
+        }
+    ));
+}
+
+#[test]
+fn extra_base_trace_commitment_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    let commitment = proof.base_trace_commitments[0].clone();
+    proof.base_trace_commitments.push(commitment);
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::BaseTraceCommitmentsMismatch {
+            expected: 1,
+            commitments: 2,
+            proofs: 1
+        }
+    ));
+}
+
+#[test]
+fn missing_fri_layer_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    let num_layers = proof.fri_proof.layers.len();
+    proof.fri_proof.layers.pop();
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::FriVerification {
+            source: fri::VerificationError::NumLayersMismatch { expected, actual }
+        } if expected == num_layers && actual == num_layers - 1
+    ));
+}
+
+#[test]
+fn wrong_fri_layer_cap_size_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.fri_proof.layers[0].commitment.0.pop();
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::FriVerification {
+            source: fri::VerificationError::InvalidLayerCapSize {
+                layer: 0,
+                expected: 4,
+                actual: 3
+            }
+        }
+    ));
+}
+
+#[test]
+fn missing_fri_layer_row_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    let rows = &mut proof.fri_proof.layers[0].flattenend_rows;
+    rows.truncate(rows.len() - OPTIONS.fri_folding_factor as usize);
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::FriVerification {
+            source: fri::VerificationError::NumLayerRowsMismatch { layer: 0, .. }
+        }
+    ));
+}
+
+#[test]
+fn tampered_fri_layer_row_is_rejected() {
+    let (claim, mut proof) = prove_fib(OPTIONS);
+    proof.fri_proof.layers[0].flattenend_rows[0] += Fp::one();
+
+    let (error, _) = verify_err(&claim, proof);
+
+    assert!(matches!(
+        error,
+        VerificationError::FriVerification {
+            source: fri::VerificationError::LayerCommitmentInvalid {
+                layer: 0,
+                source: merkle::Error::LeafMismatch { .. }
+            }
+        }
+    ));
+}
+
+#[test]
+fn wrong_deep_evaluations_fail_the_first_fri_layer() {
+    let (claim, proof) = prove_fib(OPTIONS);
+    let claim = TamperedDeepFibClaim(claim.0);
+
+    let (error, explanation) = verify_err(&claim, reinterpret(&proof));
+
+    assert!(matches!(
+        error,
+        VerificationError::FriVerification {
+            source: fri::VerificationError::InvalidDegreeRespectingProjection {
+                layer: 0,
+                query: 0,
+                position,
+                ..
+            }
+        } if position == explanation.query_positions[0]
+    ));
+}
+
+#[test]
+fn wrong_deep_evaluations_fail_the_remainder() {
+    // the FRI remainder is the whole DEEP composition polynomial
+    let options = ProofOptions::new(16, 4, 4, 4, 128);
+    let (claim, proof) = prove_fib(options);
+    assert!(proof.fri_proof.layers.is_empty());
+    let claim = TamperedDeepFibClaim(claim.0);
+
+    let (error, explanation) = verify_err(&claim, reinterpret(&proof));
+
+    assert!(matches!(
+        error,
+        VerificationError::FriVerification {
+            source: fri::VerificationError::RemainderCommitmentInvalid {
+                query: 0,
+                position,
+                ..
+            }
+        } if position == explanation.query_positions[0]
+    ));
+}
+
+#[test]
+fn fri_evaluations_must_match_positions() {
+    let (claim, proof) = prove_fib(OPTIONS);
+    let air = Air::new(proof.trace_len, claim.0, OPTIONS);
+    let mut public_coin = claim.gen_public_coin(&air);
+    let fri_verifier = FibFriVerifier::new(
+        &mut public_coin,
+        OPTIONS.into_fri_options(),
+        proof.fri_proof,
+        proof.trace_len - 1,
+    )
+    .unwrap();
+
+    let res = fri_verifier.verify(&[0, 1], &[Fp::one()]);
+
+    assert!(matches!(
+        res,
+        Err(fri::VerificationError::NumPositionEvaluationMismatch {
+            positions: 2,
+            evaluations: 1
+        })
+    ));
+}
+
+/// Reads a proof of one STARK as a proof of another
+fn reinterpret<A: Stark, B: Stark>(proof: &Proof<A>) -> Proof<B> {
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    Proof::deserialize_compressed(&*bytes).unwrap()
+}
@@ -0,0 +1,88 @@
+#![feature(allocator_api)]
+//! Verifies proofs inside a WebAssembly runtime with the `no_std` verifier in
+//! `verifier-wasm`. Requires the `wasm32-unknown-unknown` target:
+//!
+//! ```text
+//! rustup target add wasm32-unknown-unknown
+//! ```
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../examples/fib/air.rs"]
+mod air;
+
+use air::gen_trace;
+use air::FibClaim;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use ministark::stark::Stark;
+use ministark::ProofOptions;
+use std::path::Path;
+use std::process::Command;
+use wasmtime::Engine;
+use wasmtime::Instance;
+use wasmtime::Module;
+use wasmtime::Store;
+
+const OPTIONS: ProofOptions = ProofOptions::new(32, 4, 8, 8, 64);
+
+/// Builds the wasm verifier and returns the compiled module
+fn build_verifier(engine: &Engine) -> Module {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("verifier-wasm");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--release", "--target", "wasm32-unknown-unknown"])
+        .current_dir(&manifest_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to build the wasm verifier");
+    let wasm_path =
+        manifest_dir.join("target/wasm32-unknown-unknown/release/ministark_verifier_wasm.wasm");
+    Module::from_file(engine, wasm_path).unwrap()
+}
+
+/// Runs the wasm verifier on a proof and returns its status code
+fn verify_in_wasm(module: &Module, proof: &[u8], claimed_fib_num: u64) -> i32 {
+    let mut store = Store::new(module.engine(), ());
+    let instance = Instance::new(&mut store, module, &[]).unwrap();
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    let alloc = instance
+        .get_typed_func::<u32, u32>(&mut store, "alloc")
+        .unwrap();
+    let verify = instance
+        .get_typed_func::<(u32, u32, u64), i32>(&mut store, "verify")
+        .unwrap();
+    let proof_len = u32::try_from(proof.len()).unwrap();
+    let proof_ptr = alloc.call(&mut store, proof_len).unwrap();
+    memory.write(&mut store, proof_ptr as usize, proof).unwrap();
+    verify
+        .call(&mut store, (proof_ptr, proof_len, claimed_fib_num))
+        .unwrap()
+}
+
+#[test]
+fn fib_proof_verifies_in_wasm() {
+    let engine = Engine::default();
+    let module = build_verifier(&engine);
+    let trace = gen_trace(2048);
+    let claimed_fib_num = trace.last_value();
+    let claim = FibClaim(claimed_fib_num);
+    let proof = pollster::block_on(claim.prove(OPTIONS, trace)).unwrap();
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    let claimed_fib_num = claimed_fib_num.into_bigint().0[0];
+
+    assert_eq!(0, verify_in_wasm(&module, &proof_bytes, claimed_fib_num));
+    assert_eq!(
+        2,
+        verify_in_wasm(&module, &proof_bytes, claimed_fib_num + 1)
+    );
+    assert_eq!(
+        1,
+        verify_in_wasm(
+            &module,
+            &proof_bytes[..proof_bytes.len() / 2],
+            claimed_fib_num
+        )
+    );
+}
@@ -0,0 +1,72 @@
+//! A `no_std` verifier for the Fibonacci example that compiles to
+//! `wasm32-unknown-unknown`. Build with:
+//!
+//! ```text
+//! cargo build --release --target wasm32-unknown-unknown
+//! ```
+//!
+//! The host allocates a buffer with [`alloc`], writes the serialized proof
+//! into the module's memory and calls [`verify`].
+#![no_std]
+#![feature(allocator_api)]
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../../examples/fib/air.rs"]
+mod air;
+
+use air::FibClaim;
+use alloc::boxed::Box;
+use alloc::vec;
+use ark_serialize::CanonicalDeserialize;
+use ministark::stark::Stark;
+use ministark::Proof;
+use ministark_gpu::fields::p18446744069414584321::ark::Fp;
+
+#[cfg(target_arch = "wasm32")]
+#[global_allocator]
+static ALLOCATOR: dlmalloc::GlobalDlmalloc = dlmalloc::GlobalDlmalloc;
+
+#[cfg(target_arch = "wasm32")]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+/// Security level proofs must have to pass verification
+pub const SECURITY_LEVEL: u32 = 30;
+
+/// The proof verified successfully
+pub const VERIFIED: i32 = 0;
+/// The proof could not be deserialized
+pub const INVALID_PROOF_ENCODING: i32 = 1;
+/// The proof failed verification
+pub const VERIFICATION_FAILED: i32 = 2;
+
+/// Allocates `len` bytes for the host to write a proof into. Ownership of the
+/// buffer is passed back to the module by [`verify`].
+#[no_mangle]
+pub extern "C" fn alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()).cast()
+}
+
+/// Verifies a proof that the Fibonacci trace ends with `claimed_fib_num`.
+/// Returns [`VERIFIED`], [`INVALID_PROOF_ENCODING`] or
+/// [`VERIFICATION_FAILED`].
+///
+/// # Safety
+///
+/// `proof_ptr` must be a buffer returned by [`alloc`] with `proof_len` bytes
+/// initialized. The buffer is freed by this call.
+#[no_mangle]
+pub unsafe extern "C" fn verify(proof_ptr: *mut u8, proof_len: usize, claimed_fib_num: u64) -> i32 {
+    let bytes = Box::from_raw(core::ptr::slice_from_raw_parts_mut(proof_ptr, proof_len));
+    let Ok(proof) = Proof::deserialize_compressed(&*bytes) else {
+        return INVALID_PROOF_ENCODING;
+    };
+    match FibClaim(Fp::from(claimed_fib_num)).verify(proof, SECURITY_LEVEL) {
+        Ok(_) => VERIFIED,
+        Err(_) => VERIFICATION_FAILED,
+    }
+}